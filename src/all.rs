@@ -0,0 +1,236 @@
+//! `cargo fund all`: one report across every workspace on the machine.
+//!
+//! Each run of `cargo fund` remembers the workspace it ran in, in the user's data directory, so
+//! that `cargo fund all` can later merge their dependencies. Directories can instead be searched
+//! for workspaces with `--root`, or `roots` in the configuration. Maintainers are then ranked by
+//! how many of the workspaces depend on their packages.
+
+use crate::args::Args;
+use anyhow::{anyhow, Context as _, Error};
+use cargo_fund::Group;
+use cargo_metadata::Metadata;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+/// Where the workspaces that `cargo fund` has been run in are remembered.
+fn known_path() -> Option<PathBuf> {
+    Some(
+        dirs::data_local_dir()?
+            .join("cargo-fund")
+            .join("workspaces.json"),
+    )
+}
+
+fn load_known(path: &Path) -> BTreeSet<PathBuf> {
+    std::fs::read(path)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default()
+}
+
+/// Remember that `cargo fund` was run in the workspace at `root`.
+pub fn remember(root: &Path) -> Result<(), Error> {
+    let Some(path) = known_path() else {
+        return Ok(());
+    };
+    let mut known = load_known(&path);
+    if !known.insert(root.to_path_buf()) {
+        return Ok(());
+    }
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(&path, serde_json::to_vec(&known)?)
+        .with_context(|| format!("error writing {}", path.display()))
+}
+
+/// The workspaces that `cargo fund` has been run in, and that still exist.
+fn known() -> Vec<PathBuf> {
+    let known = known_path().map(|path| load_known(&path));
+    let known = known.unwrap_or_default().into_iter();
+    known.filter(|root| is_workspace(root)).collect()
+}
+
+fn is_workspace(dir: &Path) -> bool {
+    dir.join("Cargo.toml").is_file() && dir.join("Cargo.lock").is_file()
+}
+
+/// Search `roots` for workspaces, which are directories with both a manifest and a lockfile.
+/// Hidden directories, `target` directories, and the insides of workspaces are skipped.
+pub fn search<'a>(roots: impl IntoIterator<Item = &'a PathBuf>) -> Vec<PathBuf> {
+    let mut found = vec![];
+    let mut dirs = roots.into_iter().cloned().collect::<Vec<_>>();
+    while let Some(dir) = dirs.pop() {
+        if is_workspace(&dir) {
+            found.push(dir);
+            continue;
+        }
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            // the file type of a symlink isn't followed, so links can't lead the search in circles
+            if entry.file_type().is_ok_and(|t| t.is_dir())
+                && !name.starts_with('.')
+                && name != "target"
+            {
+                dirs.push(entry.path());
+            }
+        }
+    }
+    found.sort();
+    found
+}
+
+/// The dependencies of several workspaces, merged.
+pub struct Projects {
+    pub roots: Vec<PathBuf>,
+    /// The workspaces using each package, by package name, as indexes into `roots`.
+    users: BTreeMap<String, BTreeSet<usize>>,
+}
+
+/// Get the metadata for every workspace found, merged as if they were a single workspace.
+///
+/// Workspaces are searched for in `roots`, or if there are none, are those `cargo fund` has been
+/// run in. A workspace whose metadata can't be read is skipped with a warning.
+pub fn metadata(args: &Args, roots: &[PathBuf]) -> Result<(Metadata, Projects), Error> {
+    let workspaces = if roots.is_empty() {
+        known()
+    } else {
+        search(roots)
+    };
+    let mut merged: Option<Metadata> = None;
+    let mut projects = Projects {
+        roots: vec![],
+        users: BTreeMap::new(),
+    };
+    for root in workspaces {
+        let metadata = match crate::metadata::get_at(args, Some(&root.join("Cargo.toml"))) {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                tracing::warn!("skipping {}: {:#}", root.display(), e);
+                continue;
+            }
+        };
+        let ix = projects.roots.len();
+        projects.roots.push(root);
+        for pkg in &metadata.packages {
+            if !metadata.workspace_members.contains(&pkg.id) {
+                let users = projects.users.entry(pkg.name.clone()).or_default();
+                users.insert(ix);
+            }
+        }
+        match &mut merged {
+            None => merged = Some(metadata),
+            Some(merged) => {
+                let seen = merged
+                    .packages
+                    .iter()
+                    .map(|pkg| pkg.id.clone())
+                    .collect::<HashSet<_>>();
+                let packages = metadata.packages.into_iter();
+                merged
+                    .packages
+                    .extend(packages.filter(|pkg| !seen.contains(&pkg.id)));
+                merged.workspace_members.extend(metadata.workspace_members);
+                // the dependency graphs of different workspaces can't be merged meaningfully
+                merged.resolve = None;
+            }
+        }
+    }
+    let merged = merged.ok_or_else(|| {
+        anyhow!("no workspaces found; run `cargo fund` in some first, or pass --root")
+    })?;
+    Ok((merged, projects))
+}
+
+/// A maintainer, and how many of the workspaces depend on their packages.
+#[derive(Debug, Serialize)]
+pub struct Usage {
+    pub maintainer: String,
+    /// The number of workspaces using any of the maintainer's packages.
+    pub projects: usize,
+    pub links: Vec<String>,
+    /// Each of the maintainer's packages, with the number of workspaces using it.
+    pub packages: BTreeMap<String, usize>,
+}
+
+impl Projects {
+    /// Rank the maintainers of `groups` by how many workspaces use their packages, most first.
+    pub fn usage(&self, metadata: &Metadata, groups: &[Group]) -> Vec<Usage> {
+        let mut usage = groups
+            .iter()
+            .map(|group| {
+                let names = group
+                    .pkgs
+                    .iter()
+                    .map(|id| metadata[id].name.as_str())
+                    .collect::<BTreeSet<_>>();
+                let users = |name: &str| self.users.get(name).into_iter().flatten();
+                let projects = names.iter().flat_map(|name| users(name));
+                Usage {
+                    maintainer: group.heading.clone().unwrap_or_else(|| {
+                        let names = names.iter().copied();
+                        names.collect::<Vec<_>>().join(", ")
+                    }),
+                    projects: projects.collect::<BTreeSet<_>>().len(),
+                    links: group.links.iter().map(|link| link.url.clone()).collect(),
+                    packages: names
+                        .iter()
+                        .map(|name| (name.to_string(), users(name).count()))
+                        .collect(),
+                }
+            })
+            .collect::<Vec<_>>();
+        usage.sort_by(|a, b| {
+            (b.projects.cmp(&a.projects)).then_with(|| a.maintainer.cmp(&b.maintainer))
+        });
+        usage
+    }
+}
+
+/// Write each maintainer with the number of workspaces using them, their funding links, and their
+/// packages.
+pub fn write_usage(out: &mut String, usage: &[Usage], projects: usize) {
+    for maintainer in usage {
+        writeln!(
+            out,
+            "{} (used by {} of {} projects)",
+            maintainer.maintainer, maintainer.projects, projects
+        )
+        .unwrap();
+        for link in &maintainer.links {
+            writeln!(out, "  {}", link).unwrap();
+        }
+        let packages = maintainer
+            .packages
+            .iter()
+            .map(|(name, projects)| format!("{} ({})", name, projects));
+        writeln!(out, "  {}", packages.collect::<Vec<_>>().join(", ")).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn searches() {
+        let root = std::env::temp_dir().join(format!("cargo-fund-search-{}", std::process::id()));
+        for dir in ["a", "b/c", "b/c/d", ".hidden/e", "target/f"] {
+            let dir = root.join(dir);
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join("Cargo.toml"), "").unwrap();
+            std::fs::write(dir.join("Cargo.lock"), "").unwrap();
+        }
+        std::fs::create_dir_all(root.join("g")).unwrap();
+        std::fs::write(root.join("g/Cargo.toml"), "").unwrap();
+        let found = search([&root]);
+        std::fs::remove_dir_all(&root).unwrap();
+        assert_eq!(found, [root.join("a"), root.join("b/c")]);
+    }
+}
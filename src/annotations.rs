@@ -0,0 +1,131 @@
+//! `--format github-annotations`: the results as GitHub Actions workflow commands, which show up
+//! as annotations on the run and on pull requests.
+
+use crate::check;
+use cargo_fund::report::FundReport;
+use cargo_fund::LinkSource;
+use cargo_metadata::{Metadata, PackageId};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+/// Write the results as GitHub Actions workflow commands, one per maintainer. Maintainers without
+/// a funding decision are warnings, if `warn` is set, and the rest are notices.
+pub fn write_github_annotations(
+    out: &mut String,
+    metadata: &Metadata,
+    source_map: &HashMap<LinkSource, HashSet<PackageId>>,
+    report: &FundReport,
+    decisions: &check::Decisions,
+    warn: bool,
+) {
+    let outcome = check::check(source_map, report, decisions);
+    let decided = outcome
+        .decided
+        .into_iter()
+        .map(|(group, _, _)| (group, false));
+    let undecided = outcome.undecided.into_iter().map(|group| (group, warn));
+    for (group, undecided) in decided.chain(undecided) {
+        let pkgs = group
+            .pkgs
+            .iter()
+            .map(|id| format!("{} {}", metadata[id].name, metadata[id].version))
+            .collect::<Vec<_>>();
+        let links = group
+            .links
+            .iter()
+            .map(|link| link.url.as_str())
+            .collect::<Vec<_>>();
+        let (command, title) = if undecided {
+            ("warning", "New fundable maintainer")
+        } else {
+            ("notice", "Fundable dependency")
+        };
+        let message = match &group.heading {
+            Some(owner) => format!(
+                "{} maintains {} and can be funded at {}",
+                owner,
+                pkgs.join(", "),
+                links.join(", ")
+            ),
+            None => format!("{} can be funded at {}", pkgs.join(", "), links.join(", ")),
+        };
+        writeln!(out, "::{} title={}::{}", command, title, escape(&message)).unwrap();
+    }
+}
+
+/// Escape `s` for the message of a workflow command, which ends at a line break.
+fn escape(s: &str) -> String {
+    s.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cargo_fund::github::GithubLinkSource;
+
+    #[test]
+    fn escapes_messages() {
+        assert_eq!(escape("100% of\r\nit"), "100%25 of%0D%0Ait");
+    }
+
+    #[test]
+    fn writes_annotations() {
+        let packages = [("decided", "1.0.0", None), ("new", "1.0.0", None)];
+        let metadata = crate::test::metadata(&packages);
+        let link = |url: &str| serde_json::json!({ "platform": "CUSTOM", "url": url, "provenance": ["repository"] });
+        let funded = |name: &str, url: &str| {
+            serde_json::json!({
+                "id": format!("{} 1.0.0", name), "name": name, "version": "1.0.0",
+                "description": null, "links": [link(url)],
+            })
+        };
+        let report: FundReport = serde_json::from_value(serde_json::json!({
+            "workspace_root": "/ws",
+            "dependencies": 2,
+            "packages": [
+                funded("decided", "https://example.com/a%20b"),
+                funded("new", "https://example.com/new"),
+            ],
+        }))
+        .unwrap();
+        let source_map = ["decided", "new"]
+            .into_iter()
+            .map(|name| {
+                let source = LinkSource::Github(GithubLinkSource::Repo {
+                    owner: format!("{}-owner", name),
+                    name: name.to_string(),
+                });
+                let pkgs = HashSet::from([PackageId {
+                    repr: format!("{} 1.0.0", name),
+                }]);
+                (source, pkgs)
+            })
+            .collect();
+        let decisions = toml::from_str(
+            r#"
+            [maintainers.decided-owner]
+            decision = "sponsor"
+            "#,
+        )
+        .unwrap();
+
+        let mut out = String::new();
+        write_github_annotations(&mut out, &metadata, &source_map, &report, &decisions, true);
+        assert_eq!(
+            out,
+            "::notice title=Fundable dependency::decided-owner maintains decided 1.0.0 and can be \
+             funded at https://example.com/a%2520b\n\
+             ::warning title=New fundable maintainer::new-owner maintains new 1.0.0 and can be \
+             funded at https://example.com/new\n"
+        );
+        let mut out = String::new();
+        write_github_annotations(&mut out, &metadata, &source_map, &report, &decisions, false);
+        assert!(
+            out.lines().all(|line| line.starts_with("::notice ")),
+            "{}",
+            out
+        );
+    }
+}
@@ -1,13 +1,32 @@
 //! Adapted from the `cargo_tree::args` module.
 
-use clap::{ArgAction, Parser};
+use crate::config::Config;
+use clap::{ArgAction, Parser, Subcommand, ValueEnum};
 use serde::Deserialize;
+use std::net::SocketAddr;
 use std::path::PathBuf;
+use std::time::Duration;
 
+/// Environment variables that aren't equivalents of flags.
 #[derive(Deserialize, Debug)]
 pub struct Env {
-    #[serde(rename = "cargo_fund_github_api_token")]
-    pub github_api_token: Option<String>,
+    #[serde(rename = "cargo_fund_pager")]
+    pub pager: Option<String>,
+    #[serde(rename = "pager")]
+    pub system_pager: Option<String>,
+    /// The file GitHub Actions shows as the job summary, when running there.
+    #[serde(rename = "github_step_summary")]
+    pub step_summary: Option<PathBuf>,
+}
+
+impl Env {
+    /// The pager command, preferring `CARGO_FUND_PAGER` over `PAGER`.
+    pub fn pager(&self) -> &str {
+        self.pager
+            .as_deref()
+            .or(self.system_pager.as_deref())
+            .unwrap_or(crate::pager::DEFAULT_PAGER)
+    }
 }
 
 #[derive(Parser)]
@@ -20,25 +39,853 @@ pub enum Opts {
 
 #[derive(Parser)]
 pub struct Args {
-    /// Github API token, which must have the scope `public_repo`. This option overrides the token
-    /// provided in the `CARGO_FUND_GITHUB_API_TOKEN` environment variable.
-    #[clap(long = "github-api-token", value_name = "TOKEN")]
+    #[clap(subcommand)]
+    pub command: Option<Command>,
+    /// Github API token, which must have the scope `public_repo`
+    #[clap(
+        long = "github-api-token",
+        env = "CARGO_FUND_GITHUB_API_TOKEN",
+        hide_env_values = true,
+        value_name = "TOKEN"
+    )]
     pub github_api_token: Option<String>,
-    #[clap(long = "manifest-path", value_name = "PATH", value_parser)]
+    #[clap(long = "batch-size", env = "CARGO_FUND_BATCH_SIZE", value_name = "N")]
+    /// The number of repositories and owners to look up in each Github API query [default: 100]
+    pub batch_size: Option<usize>,
+    #[clap(short = 'j', long = "jobs", env = "CARGO_FUND_JOBS", value_name = "N")]
+    /// The number of Github API queries to send at once [default: 4]
+    pub jobs: Option<usize>,
+    #[clap(long = "strict", env = "CARGO_FUND_STRICT")]
+    /// Stop at the first malformed funding link or response, or failed query, rather than
+    /// skipping it with a warning and showing the funding links that were found
+    pub strict: bool,
+    #[clap(
+        long = "lenient",
+        env = "CARGO_FUND_LENIENT",
+        conflicts_with = "strict"
+    )]
+    /// Skip malformed funding data and failed queries with a warning, even if the configuration
+    /// asks to be strict. This is the default.
+    pub lenient: bool,
+    #[clap(long = "wait-for-rate-limit", env = "CARGO_FUND_WAIT_FOR_RATE_LIMIT")]
+    /// If the Github API rate limit runs out, wait for it to reset rather than stopping
+    pub wait_for_rate_limit: bool,
+    #[clap(
+        long = "min-coverage",
+        env = "CARGO_FUND_MIN_COVERAGE",
+        value_name = "PERCENT",
+        value_parser = clap::value_parser!(u8).range(0..=100)
+    )]
+    /// Fail with exit status 5 if fewer than this percentage of dependencies have funding links
+    pub min_coverage: Option<u8>,
+    #[clap(long = "fail-if-empty", env = "CARGO_FUND_FAIL_IF_EMPTY")]
+    /// Fail if no funding links are found at all, which usually means something is misconfigured
+    pub fail_if_empty: bool,
+    #[clap(
+        long = "save-baseline",
+        env = "CARGO_FUND_SAVE_BASELINE",
+        value_name = "PATH"
+    )]
+    /// Also save the report as JSON to this file, with the time and a hash of Cargo.lock, for
+    /// `cargo fund diff --baseline`
+    pub save_baseline: Option<PathBuf>,
+    #[clap(long = "timeout", env = "CARGO_FUND_TIMEOUT", value_name = "SECS")]
+    /// How many seconds to wait for each Github API response [default: 120]
+    pub timeout: Option<u64>,
+    #[clap(long = "proxy", env = "CARGO_FUND_PROXY", value_name = "URL")]
+    /// Proxy to send Github API requests through, such as `http://proxy:3128` or
+    /// `socks5://localhost:1080`. By default, the proxy is taken from the `HTTPS_PROXY`,
+    /// `HTTP_PROXY`, or `ALL_PROXY` environment variables.
+    pub proxy: Option<String>,
+    #[clap(long = "cacert", env = "CARGO_FUND_CACERT", value_name = "PATH")]
+    /// PEM file of extra CA certificates to trust, such as those of a TLS-intercepting proxy.
+    /// The operating system's trusted certificates are always used as well.
+    pub cacert: Option<PathBuf>,
+    #[clap(long = "cache-ttl", env = "CARGO_FUND_CACHE_TTL", value_name = "DURATION", default_value = "7d", value_parser = parse_duration)]
+    /// How long cached funding links are used before querying again, such as `12h` or `2w`
+    pub cache_ttl: Duration,
+    #[clap(long = "ledger", env = "CARGO_FUND_LEDGER", value_name = "PATH")]
+    /// The ledger of donations, instead of `ledger.toml` in the platform's data directory
+    pub ledger: Option<PathBuf>,
+    #[clap(long = "cache-dir", env = "CARGO_FUND_CACHE_DIR", value_name = "PATH")]
+    /// Where to keep the cache of funding links, instead of the platform's cache directory
+    pub cache_dir: Option<PathBuf>,
+    #[clap(long = "no-cache", env = "CARGO_FUND_NO_CACHE")]
+    /// Neither read nor update the cache of funding links
+    pub no_cache: bool,
+    #[clap(
+        long = "incremental",
+        env = "CARGO_FUND_INCREMENTAL",
+        conflicts_with = "no_cache"
+    )]
+    /// If Cargo.lock hasn't changed since the last complete run, use the cached funding links
+    /// regardless of `--cache-ttl` rather than querying again
+    pub incremental: bool,
+    #[clap(
+        long = "offline",
+        env = "CARGO_FUND_OFFLINE",
+        conflicts_with = "no_cache"
+    )]
+    /// Answer entirely from the cache and the lockfile without accessing the network. No Github
+    /// API token is needed.
+    pub offline: bool,
+    #[clap(
+        long = "record",
+        env = "CARGO_FUND_RECORD",
+        value_name = "DIR",
+        conflicts_with_all = ["offline", "replay"]
+    )]
+    /// Record the Github API's responses in this directory, for replaying with `--replay`. The
+    /// cache isn't used, so that every query is recorded
+    pub record: Option<PathBuf>,
+    #[clap(
+        long = "replay",
+        env = "CARGO_FUND_REPLAY",
+        value_name = "DIR",
+        conflicts_with = "offline"
+    )]
+    /// Answer the Github API's queries with the responses recorded by `--record` in this
+    /// directory, without a token or network access. The cache isn't used
+    pub replay: Option<PathBuf>,
+    #[clap(
+        long = "registry",
+        env = "CARGO_FUND_REGISTRY",
+        value_name = "NAME",
+        value_delimiter = ','
+    )]
+    /// Only include packages from this registry, such as `crates-io`, along with path and git
+    /// dependencies. May be given more than once, or as a comma-separated list. Registries are
+    /// named as in Cargo's configuration, or by their index URL.
+    pub registries: Vec<String>,
+    #[clap(
+        long = "exclude-registry",
+        env = "CARGO_FUND_EXCLUDE_REGISTRY",
+        value_name = "NAME",
+        value_delimiter = ','
+    )]
+    /// Leave out packages from this registry, such as an internal one, from the report and its
+    /// counts. May be given more than once, or as a comma-separated list.
+    pub exclude_registries: Vec<String>,
+    #[clap(long = "include-git-deps", env = "CARGO_FUND_INCLUDE_GIT_DEPS")]
+    /// Include git dependencies, which are left out by default since they're usually forks or
+    /// unreleased work rather than the crates their maintainers publish
+    pub include_git_deps: bool,
+    #[clap(long = "exclude-path-deps", env = "CARGO_FUND_EXCLUDE_PATH_DEPS")]
+    /// Leave out path dependencies outside the workspace, such as crates checked out alongside it,
+    /// from the report and its counts
+    pub exclude_path_deps: bool,
+    #[clap(long = "author", env = "CARGO_FUND_AUTHOR", value_name = "PATTERN")]
+    /// Only include dependencies with an author matching this pattern, such as a maintainer's name
+    /// or email address, ignoring case. May be given more than once.
+    pub authors: Vec<String>,
+    #[clap(long = "since", env = "CARGO_FUND_SINCE", value_name = "REF")]
+    /// Only include dependencies added to Cargo.lock since this git revision, such as the base
+    /// branch of a pull request.
+    pub since: Option<String>,
+    #[clap(long = "profile", env = "CARGO_FUND_PROFILE", value_name = "NAME")]
+    /// The profile to select in the configuration files, such as `ci` for the `[profile.ci]`
+    /// section
+    pub profile: Option<String>,
+    #[clap(
+        long = "manifest-path",
+        env = "CARGO_FUND_MANIFEST_PATH",
+        value_name = "PATH",
+        value_parser
+    )]
     /// Path to Cargo.toml
     pub manifest_path: Option<PathBuf>,
+    #[clap(
+        long = "git",
+        env = "CARGO_FUND_GIT",
+        value_name = "URL",
+        conflicts_with_all = ["manifest_path", "offline", "since", "watch"]
+    )]
+    /// Report on the repository at this URL instead, from a shallow clone of its default branch
+    pub git: Option<String>,
     #[clap(long = "verbose", short = 'v', action = ArgAction::Count)]
-    /// Use verbose output (-vv very verbose/build.rs output)
+    /// Use verbose output (-vv very verbose/build.rs output). Logs info messages with -v, debug
+    /// messages with -vv, and trace messages with -vvv, unless RUST_LOG is set
     pub verbose: u8,
-    #[clap(long = "quiet", short = 'q')]
+    #[clap(long = "quiet", env = "CARGO_FUND_QUIET", short = 'q')]
     /// No output printed to stdout other than the funding information
     pub quiet: bool,
-    #[clap(long = "color", value_name = "WHEN")]
-    /// Coloring: auto, always, never
-    pub color: Option<String>,
+    #[clap(
+        long = "log-format",
+        env = "CARGO_FUND_LOG_FORMAT",
+        value_name = "FORMAT",
+        value_enum,
+        default_value = "text"
+    )]
+    /// How to write log messages, which always go to stderr
+    pub log_format: LogFormat,
+    #[clap(
+        long = "color",
+        env = "CARGO_FUND_COLOR",
+        value_name = "WHEN",
+        value_enum
+    )]
+    /// Coloring [default: auto]
+    pub color: Option<Color>,
+    #[clap(
+        long = "theme",
+        env = "CARGO_FUND_THEME",
+        value_name = "THEME",
+        value_enum
+    )]
+    /// The colors to use for the output [default: default]
+    pub theme: Option<ThemeName>,
     #[clap(short = 'Z', value_name = "FLAG")]
     /// Unstable (nightly-only) flags to Cargo
     pub unstable_flags: Vec<String>,
+    #[clap(
+        long = "group-by",
+        env = "CARGO_FUND_GROUP_BY",
+        value_name = "KEY",
+        value_enum
+    )]
+    /// How to group packages in the output [default: links]
+    pub group_by: Option<GroupBy>,
+    #[clap(long = "sort", env = "CARGO_FUND_SORT", value_name = "KEY", value_enum)]
+    /// How to order the groups in the output [default: platform]
+    pub sort: Option<SortKey>,
+    #[clap(long = "reverse", env = "CARGO_FUND_REVERSE")]
+    /// Reverse the order of the groups in the output
+    pub reverse: bool,
+    #[clap(long = "format", env = "CARGO_FUND_FORMAT", value_name = "FORMAT", value_enum, conflicts_with_all = ["porcelain", "links_only"])]
+    /// The format to print results in [default: tree]
+    pub format: Option<Format>,
+    #[clap(
+        long = "output",
+        env = "CARGO_FUND_OUTPUT",
+        value_name = "PATH",
+        conflicts_with_all = ["stream", "watch"]
+    )]
+    /// Write the results to this file instead of stdout, creating its directory if needed. Without
+    /// `--format`, the format follows the extension: `.json` for JSON, `.tsv` for porcelain, and
+    /// the tree otherwise.
+    pub output: Option<PathBuf>,
+    #[clap(long = "porcelain", env = "CARGO_FUND_PORCELAIN")]
+    /// Print a stable, line-oriented format for scripts instead of the tree. Each line holds the
+    /// package name, package version, funding platform, and funding URL, separated by tabs.
+    /// Shorthand for `--format porcelain`.
+    pub porcelain: bool,
+    #[clap(
+        long = "links-only",
+        env = "CARGO_FUND_LINKS_ONLY",
+        conflicts_with = "porcelain"
+    )]
+    /// Print only the unique funding URLs, one per line. Shorthand for `--format links-only`.
+    pub links_only: bool,
+    #[clap(long = "stream", env = "CARGO_FUND_STREAM", conflicts_with_all = ["porcelain", "links_only"])]
+    /// Print the funding links of each repository and owner as soon as they are found, rather than
+    /// waiting to print them all as one sorted tree
+    pub stream: bool,
+    #[clap(
+        long = "final-tree",
+        env = "CARGO_FUND_FINAL_TREE",
+        requires = "stream"
+    )]
+    /// With `--stream`, also print the usual sorted tree once everything has been found
+    pub final_tree: bool,
+    #[clap(long = "watch", env = "CARGO_FUND_WATCH")]
+    /// After printing the results, keep watching Cargo.lock, and print what changed whenever it
+    /// does. Output isn't paged.
+    pub watch: bool,
+    #[clap(long = "timings", env = "CARGO_FUND_TIMINGS")]
+    /// After the results, print how long each phase of the run took to stderr, with the round
+    /// trip of each Github query at -v
+    pub timings: bool,
+    #[clap(long = "job-summary", env = "CARGO_FUND_JOB_SUMMARY")]
+    /// When running in GitHub Actions, also add a Markdown summary of the results to the job
+    /// summary
+    pub job_summary: bool,
+    #[clap(long = "open", env = "CARGO_FUND_OPEN")]
+    /// After printing the results, ask about each maintainer in turn, and open the funding links
+    /// of those you choose in your browser
+    pub open: bool,
+    #[clap(
+        long = "open-limit",
+        env = "CARGO_FUND_OPEN_LIMIT",
+        value_name = "N",
+        default_value = "10",
+        requires = "open"
+    )]
+    /// With `--open`, the most links to open in one run
+    pub open_limit: usize,
+    #[clap(long = "no-pager", env = "CARGO_FUND_NO_PAGER")]
+    /// Never pipe output through a pager. By default, output that doesn't fit on the terminal is
+    /// paged with `CARGO_FUND_PAGER`, `PAGER`, or `less -R`.
+    pub no_pager: bool,
+    #[clap(long = "show-descriptions", env = "CARGO_FUND_SHOW_DESCRIPTIONS")]
+    /// Show each package's description next to its name
+    pub show_descriptions: bool,
+    #[clap(long = "show-activity", env = "CARGO_FUND_SHOW_ACTIVITY")]
+    /// Show when each package was last released on crates.io, marking those released in the last
+    /// six months as active, and include the dates in JSON output
+    pub show_activity: bool,
+    #[clap(long = "show-downloads", env = "CARGO_FUND_SHOW_DOWNLOADS")]
+    /// Show how many times each package has been downloaded from crates.io, and include the
+    /// counts in JSON output
+    pub show_downloads: bool,
+    #[clap(long = "show-outdated", env = "CARGO_FUND_SHOW_OUTDATED")]
+    /// Mark packages whose latest version on crates.io is a breaking change from the one in use,
+    /// and include the latest versions in JSON output
+    pub show_outdated: bool,
+    #[clap(long = "show-goals", env = "CARGO_FUND_SHOW_GOALS")]
+    /// Show how far each maintainer is towards their Github Sponsors goal, and include the goals
+    /// in JSON output
+    pub show_goals: bool,
+    #[clap(long = "show-sponsoring", env = "CARGO_FUND_SHOW_SPONSORING")]
+    /// Mark the maintainers you already sponsor on Github Sponsors, as the owner of the Github API
+    /// token, and include it in JSON output
+    pub show_sponsoring: bool,
+    #[clap(long = "show-sponsors", env = "CARGO_FUND_SHOW_SPONSORS")]
+    /// Show how many sponsors each maintainer has on Github Sponsors, so that you can favor those
+    /// with few, and include the counts in JSON output
+    pub show_sponsors: bool,
+    #[clap(long = "show-patrons", env = "CARGO_FUND_SHOW_PATRONS")]
+    /// Show how many patrons each Patreon campaign has, leaving out the links of deleted
+    /// campaigns, and include the counts in JSON output
+    pub show_patrons: bool,
+    #[clap(long = "hide-sponsored", env = "CARGO_FUND_HIDE_SPONSORED")]
+    /// Leave out the Github Sponsors links of maintainers you already sponsor, and the packages
+    /// that have no other links, so that only the gaps in your giving are shown
+    pub hide_sponsored: bool,
+    #[clap(long = "only-unsponsored", env = "CARGO_FUND_ONLY_UNSPONSORED")]
+    /// Leave out every package with a maintainer you already sponsor on Github Sponsors, even if
+    /// it has other links, so that the report lists only the maintainers you don't support yet
+    pub only_unsponsored: bool,
+    #[clap(long = "show-impact", env = "CARGO_FUND_SHOW_IMPACT")]
+    /// Show how much the workspace relies on each group's packages
+    pub show_impact: bool,
+    #[clap(long = "show-missing", env = "CARGO_FUND_SHOW_MISSING")]
+    /// After the results, list Github repositories that were found but have no funding links
+    pub show_missing: bool,
+    #[clap(long = "show-vet", env = "CARGO_FUND_SHOW_VET")]
+    /// Show whether each package is audited or exempted in the workspace's cargo-vet store, in
+    /// `supply-chain`, and include it in JSON output
+    pub show_vet: bool,
+    #[clap(long = "show-provenance", env = "CARGO_FUND_SHOW_PROVENANCE")]
+    /// Show where each funding link was found
+    pub show_provenance: bool,
+    #[clap(
+        long = "style",
+        env = "CARGO_FUND_STYLE",
+        value_name = "STYLE",
+        value_enum
+    )]
+    /// The style of the tree [default: unicode, or ascii with `--charset ascii`]
+    pub style: Option<Style>,
+    #[clap(
+        long = "charset",
+        env = "CARGO_FUND_CHARSET",
+        value_name = "CHARSET",
+        value_enum
+    )]
+    /// The characters the tree may use [default: unicode, or ascii when stdout isn't a terminal]
+    pub charset: Option<Charset>,
+    #[clap(long = "glyph", env = "CARGO_FUND_GLYPH", value_name = "NAME=GLYPH")]
+    /// Override one of the characters used to draw the tree: horizontal, vertical, tee, corner,
+    /// or down-tee
+    pub glyphs: Vec<String>,
+    #[clap(long = "width", env = "CARGO_FUND_WIDTH", value_name = "COLUMNS")]
+    /// Shorten long lines to fit this many columns. Defaults to the width of the terminal, if
+    /// output is going to one.
+    pub width: Option<usize>,
+    #[clap(long = "icons", env = "CARGO_FUND_ICONS")]
+    /// Prefix each funding link with an icon for its platform
+    pub icons: bool,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Print the funding links of the workspace's dependencies, as without a subcommand
+    Report,
+    /// Print only the unique funding URLs, one per line. The same as `--links-only`.
+    Links,
+    /// Keep a Github API token on this machine for later runs, reading it from stdin
+    Login,
+    #[clap(subcommand)]
+    /// Show or clear the cache of funding links
+    Cache(CacheCommand),
+    /// Answer funding queries over HTTP, reusing the cache and Github client between requests.
+    /// The options given before `serve`, such as `--cache-ttl`, apply to every request.
+    Serve(ServeArgs),
+    /// Check that a decision has been made for every fundable maintainer, failing when there are
+    /// maintainers without one.
+    Check(CheckArgs),
+    /// Show only what changed since a report saved with `--format json`
+    Diff(DiffArgs),
+    #[clap(subcommand)]
+    /// Write files from the funding links found
+    Generate(Generate),
+    /// Browse the funding links interactively, marking maintainers as funded
+    #[cfg(feature = "tui")]
+    Tui,
+    /// Propose how to split a budget between the maintainers of your dependencies
+    Plan(PlanArgs),
+    /// Record a donation to a maintainer in the ledger
+    Record(RecordArgs),
+    /// List the maintainers depended on who haven't been funded recently, according to the ledger
+    Remind(RemindArgs),
+    /// Report on the dependencies of every workspace on this machine, weighted by how many use each
+    All(AllArgs),
+    /// Split the maintainers to fund between the members of a team
+    Assign(AssignArgs),
+    /// Write messages asking the maintainers of dependencies without funding links to add them
+    Outreach(OutreachArgs),
+    /// Check that every funding link still works, listing those that are dead or redirect
+    /// elsewhere
+    Verify(VerifyArgs),
+    /// Post a summary of the funding links, or the changes since a baseline, to a Slack or
+    /// Discord webhook
+    Notify(NotifyArgs),
+    /// Explain what an error code, such as `rate-limited`, means and how to fix it, or list the
+    /// codes
+    Explain(ExplainArgs),
+    #[clap(hide = true)]
+    /// Write a manual page for `cargo fund` to stdout, in roff
+    Man,
+}
+
+#[derive(Parser)]
+pub struct ServeArgs {
+    #[clap(
+        long = "listen",
+        env = "CARGO_FUND_LISTEN",
+        value_name = "ADDR",
+        default_value = "127.0.0.1:8080"
+    )]
+    /// The address to listen on
+    pub listen: SocketAddr,
+}
+
+#[derive(Parser)]
+pub struct CheckArgs {
+    #[clap(long = "decisions", env = "CARGO_FUND_DECISIONS", value_name = "PATH")]
+    /// The file of funding decisions. Defaults to `funding-decisions.toml` in the workspace root.
+    pub decisions: Option<PathBuf>,
+}
+
+#[derive(Parser)]
+pub struct DiffArgs {
+    #[clap(long = "baseline", env = "CARGO_FUND_BASELINE", value_name = "PATH")]
+    /// The earlier report to compare against
+    pub baseline: PathBuf,
+}
+
+#[derive(Parser)]
+pub struct NotifyArgs {
+    #[clap(long = "webhook", env = "CARGO_FUND_WEBHOOK", value_name = "URL")]
+    /// The Slack or Discord incoming webhook to post to. Discord webhooks are recognized by their
+    /// host; any other URL is sent Slack's payload.
+    pub webhook: String,
+    #[clap(long = "baseline", env = "CARGO_FUND_BASELINE", value_name = "PATH")]
+    /// Post only the changes since this earlier report, and nothing when there are none
+    pub baseline: Option<PathBuf>,
+}
+
+#[derive(Parser)]
+pub struct VerifyArgs {
+    #[clap(long = "report", env = "CARGO_FUND_VERIFY_REPORT", value_name = "PATH")]
+    /// Check the links in a report saved with `--format json`, instead of finding them again. No
+    /// Github API token is needed.
+    pub report: Option<PathBuf>,
+}
+
+#[derive(Parser)]
+pub struct ExplainArgs {
+    #[clap(value_name = "CODE")]
+    /// The error code to explain. Without one, every code is listed.
+    pub code: Option<String>,
+}
+
+#[derive(Parser)]
+pub struct PlanArgs {
+    #[clap(
+        long = "budget",
+        env = "CARGO_FUND_BUDGET",
+        value_name = "AMOUNT",
+        value_parser = parse_amount,
+        required_unless_present = "check"
+    )]
+    /// The amount to split, such as `200` or `49.50`, in any currency
+    pub budget: Option<u64>,
+    #[clap(
+        long = "strategy",
+        env = "CARGO_FUND_STRATEGY",
+        value_name = "STRATEGY",
+        value_enum,
+        default_value = "equal"
+    )]
+    /// How to weigh each maintainer's share
+    pub strategy: Strategy,
+    #[clap(
+        long = "shopping-list",
+        env = "CARGO_FUND_SHOPPING_LIST",
+        value_name = "PATH"
+    )]
+    /// Also write the plan as a Markdown checklist, with a link to pay each maintainer
+    pub shopping_list: Option<PathBuf>,
+    #[clap(
+        long = "check",
+        env = "CARGO_FUND_PLAN_CHECK",
+        conflicts_with_all = ["budget", "shopping_list"]
+    )]
+    /// Instead of proposing a plan, check the committed plan against the current dependencies,
+    /// and show the payments it would make
+    pub check: bool,
+    #[clap(
+        long = "plan-file",
+        env = "CARGO_FUND_PLAN_FILE",
+        value_name = "PATH",
+        requires = "check"
+    )]
+    /// The committed plan to check, instead of `fund-plan.toml` in the workspace root
+    pub plan_file: Option<PathBuf>,
+}
+
+#[derive(Parser)]
+pub struct OutreachArgs {
+    #[clap(
+        long = "kind",
+        env = "CARGO_FUND_OUTREACH_KIND",
+        value_name = "KIND",
+        value_enum,
+        default_value = "issue"
+    )]
+    /// The kind of message to write
+    pub kind: OutreachKind,
+    #[clap(
+        long = "template",
+        env = "CARGO_FUND_OUTREACH_TEMPLATE",
+        value_name = "PATH",
+        conflicts_with = "kind"
+    )]
+    /// Fill in this template instead of a built-in one
+    pub template: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+pub enum OutreachKind {
+    /// The text of an issue on the repository
+    Issue,
+    /// An email to the maintainer
+    Email,
+}
+
+#[derive(Parser)]
+pub struct AssignArgs {
+    #[clap(
+        long = "team-file",
+        env = "CARGO_FUND_TEAM_FILE",
+        value_name = "PATH",
+        default_value = "team.toml"
+    )]
+    /// The team members to split the maintainers between
+    pub team_file: PathBuf,
+    #[clap(
+        long = "strategy",
+        env = "CARGO_FUND_ASSIGN_STRATEGY",
+        value_name = "STRATEGY",
+        value_enum,
+        default_value = "round-robin"
+    )]
+    /// How to split the maintainers
+    pub strategy: AssignStrategy,
+    #[clap(long = "per-member", env = "CARGO_FUND_PER_MEMBER", value_name = "N")]
+    /// How many maintainers each member takes on, instead of splitting all of them
+    pub per_member: Option<usize>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+pub enum AssignStrategy {
+    /// The same number of maintainers for each member
+    RoundRobin,
+    /// Maintainers in proportion to each member's weight
+    Weighted,
+}
+
+#[derive(Parser)]
+pub struct AllArgs {
+    #[clap(long = "root", env = "CARGO_FUND_ROOT", value_name = "DIR")]
+    /// Search this directory for workspaces, instead of using those `cargo fund` has been run in
+    pub roots: Vec<PathBuf>,
+}
+
+#[derive(Parser)]
+pub struct RemindArgs {
+    #[clap(
+        long = "interval",
+        env = "CARGO_FUND_INTERVAL",
+        value_name = "DURATION",
+        value_parser = parse_duration,
+        default_value = "6months"
+    )]
+    /// How long since a maintainer was last funded before they are listed, such as `3months`
+    pub interval: Duration,
+}
+
+#[derive(Parser)]
+pub struct RecordArgs {
+    #[clap(value_name = "MAINTAINER")]
+    /// The Github account donated to, or the funding link for maintainers without one
+    pub maintainer: String,
+    #[clap(long = "amount", value_name = "AMOUNT", value_parser = parse_amount)]
+    /// The amount donated, such as `10` or `4.50`, in any currency
+    pub amount: u64,
+    #[clap(long = "date", value_name = "DATE")]
+    /// When the donation was made, such as `2024-03-01`. Defaults to today.
+    pub date: Option<crate::ledger::Date>,
+    #[clap(long = "note", value_name = "TEXT")]
+    /// A note to keep with the donation
+    pub note: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+pub enum Strategy {
+    /// The same amount for each maintainer
+    Equal,
+    /// In proportion to the number of packages each maintainer has among the dependencies
+    Packages,
+    /// Like `packages`, but each package counts less the further it is from the workspace, so a
+    /// direct dependency counts twice as much as one of its dependencies
+    Depth,
+}
+
+#[derive(Subcommand)]
+pub enum CacheCommand {
+    /// Print where the cache is kept
+    Path,
+    /// Delete the cache, so that every source is looked up again
+    Clear,
+}
+
+#[derive(Subcommand)]
+pub enum Generate {
+    /// A Markdown file crediting the maintainers of dependencies, with their funding links
+    Acknowledgments(AcknowledgmentsArgs),
+    /// A JSON file of each dependency's funding links, keyed by crate name and version the way
+    /// cargo-about and cargo-deny name crates
+    Interop(InteropArgs),
+}
+
+#[derive(Parser)]
+pub struct AcknowledgmentsArgs {
+    #[clap(
+        long = "output",
+        short = 'o',
+        env = "CARGO_FUND_OUTPUT",
+        value_name = "PATH"
+    )]
+    /// The file to write. Defaults to `ACKNOWLEDGMENTS.md` in the workspace root.
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Parser)]
+pub struct InteropArgs {
+    #[clap(
+        long = "output",
+        short = 'o',
+        env = "CARGO_FUND_OUTPUT",
+        value_name = "PATH"
+    )]
+    /// The file to write. Defaults to `funding.json` in the workspace root.
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum GroupBy {
+    /// Group packages that share the same set of funding links
+    Links,
+    /// Group packages by repository owner, showing the union of the owner's funding links
+    Owner,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Format {
+    /// A tree of funding links and the packages they support
+    Tree,
+    /// A JSON report of each package with funding links
+    Json,
+    /// Tab-separated lines in a stable format for scripts
+    Porcelain,
+    /// Only the unique funding URLs, one per line
+    LinksOnly,
+    /// GitHub Actions workflow commands, which show each fundable maintainer on pull requests
+    GithubAnnotations,
+    /// Metrics in the Prometheus text format, for node_exporter's textfile collector
+    Prometheus,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum SortKey {
+    /// Alphabetically by owner, or by first funding link when not grouping by owner
+    Name,
+    /// Groups with the most packages first
+    PackageCount,
+    /// By funding platform, then by link
+    Platform,
+    /// Groups whose packages the workspace relies on most first
+    Impact,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+pub enum LogFormat {
+    /// Human-readable lines
+    Text,
+    /// One JSON object per line
+    Json,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Style {
+    /// Unicode box-drawing characters
+    Unicode,
+    /// ASCII characters only
+    Ascii,
+    /// Unicode box-drawing characters with rounded corners
+    Rounded,
+    /// Unicode box-drawing characters, with all the links of a group on one line
+    Compact,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Color {
+    /// Color output going to a terminal, unless `NO_COLOR` is set
+    Auto,
+    /// Always color output
+    Always,
+    /// Never color output
+    Never,
+}
+
+impl Color {
+    /// The value as Cargo takes it.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Color::Auto => "auto",
+            Color::Always => "always",
+            Color::Never => "never",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Charset {
+    /// Any characters, including box-drawing characters and icons
+    Unicode,
+    /// ASCII characters only: the tree is drawn in ASCII whatever its style, icons are left out,
+    /// and shortened text ends with `...`
+    Ascii,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum ThemeName {
+    /// Colors that work on most terminals
+    Default,
+    /// Bright colors for dark backgrounds
+    Dark,
+    /// Deeper colors for light backgrounds
+    Light,
+    /// No colors, only bold and underlined text
+    MonochromeBold,
+}
+
+impl Args {
+    /// Whether to record the Github API's responses with `--record` or replay them with
+    /// `--replay`.
+    pub fn fixtures(&self) -> Option<cargo_fund::fixtures::Fixtures> {
+        use cargo_fund::fixtures::Fixtures;
+        match (&self.record, &self.replay) {
+            (Some(dir), _) => Some(Fixtures::Record(dir.clone())),
+            (None, Some(dir)) => Some(Fixtures::Replay(dir.clone())),
+            (None, None) => None,
+        }
+    }
+
+    /// The directory of the manifest given with `--manifest-path`, or the current directory.
+    pub fn manifest_dir(&self) -> Result<PathBuf, std::io::Error> {
+        let dir = std::env::current_dir()?;
+        match self.manifest_path.as_ref().and_then(|path| path.parent()) {
+            Some(parent) => Ok(dir.join(parent)),
+            None => Ok(dir),
+        }
+    }
+
+    /// The output format, taking `--porcelain`, `--links-only`, and the configuration file into
+    /// account.
+    pub fn format(&self, config: &Config) -> Format {
+        if self.porcelain {
+            Format::Porcelain
+        } else if self.links_only || matches!(self.command, Some(Command::Links)) {
+            Format::LinksOnly
+        } else {
+            let inferred = self.output.as_deref().and_then(Format::for_path);
+            self.format
+                .or(inferred)
+                .or(config.format)
+                .unwrap_or(Format::Tree)
+        }
+    }
+}
+
+impl Format {
+    /// The format for a file written with `--output`, judging by its extension.
+    pub fn for_path(path: &std::path::Path) -> Option<Format> {
+        match path.extension()?.to_str()? {
+            "json" => Some(Format::Json),
+            "tsv" => Some(Format::Porcelain),
+            "prom" => Some(Format::Prometheus),
+            "txt" => Some(Format::Tree),
+            _ => None,
+        }
+    }
+}
+
+/// Parse a duration such as `90s`, `30m`, `12h`, `7d`, `2w`, or `6months`.
+///
+/// A number without a unit is taken as seconds. Months are 30 days, and years are 365 days.
+pub fn parse_duration(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (count, unit) = s.split_at(split);
+    let count: u64 = count
+        .parse()
+        .map_err(|_| format!("invalid duration {:?}; expected e.g. `12h` or `7d`", s))?;
+    let secs = match unit.trim() {
+        "" | "s" | "sec" | "secs" | "second" | "seconds" => 1,
+        "m" | "min" | "mins" | "minute" | "minutes" => 60,
+        "h" | "hour" | "hours" => 60 * 60,
+        "d" | "day" | "days" => 24 * 60 * 60,
+        "w" | "week" | "weeks" => 7 * 24 * 60 * 60,
+        "mo" | "month" | "months" => 30 * 24 * 60 * 60,
+        "y" | "year" | "years" => 365 * 24 * 60 * 60,
+        unit => return Err(format!("unknown unit {:?} in duration {:?}", unit, s)),
+    };
+    Ok(Duration::from_secs(count * secs))
+}
+
+/// Parse an amount of money, such as `200` or `49.50`, into hundredths.
+pub fn parse_amount(s: &str) -> Result<u64, String> {
+    let invalid = || format!("invalid amount {:?}; expected e.g. `200` or `49.50`", s);
+    let (whole, fraction) = s.trim().split_once('.').unwrap_or((s.trim(), ""));
+    if fraction.len() > 2 || !fraction.chars().all(|c| c.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    let whole: u64 = whole.parse().map_err(|_| invalid())?;
+    let fraction: u64 = format!("{:0<2}", fraction).parse().map_err(|_| invalid())?;
+    whole
+        .checked_mul(100)
+        .and_then(|cents| cents.checked_add(fraction))
+        .ok_or_else(invalid)
 }
 
 #[cfg(test)]
@@ -50,4 +897,94 @@ mod test {
         use clap::CommandFactory;
         Opts::command().debug_assert();
     }
+
+    #[test]
+    fn options_have_variables() {
+        use clap::CommandFactory;
+        // `-v` and `-Z` are exempt, and so is `record`, which describes a single donation
+        fn check(cmd: &clap::Command, missing: &mut Vec<String>) {
+            for arg in cmd.get_arguments() {
+                let id = arg.get_id().as_str();
+                if arg.get_long().is_some()
+                    && arg.get_env().is_none()
+                    && !matches!(id, "verbose" | "unstable_flags" | "help" | "version")
+                {
+                    missing.push(format!("{} --{}", cmd.get_name(), id));
+                }
+            }
+            for sub in cmd.get_subcommands() {
+                if sub.get_name() != "record" {
+                    check(sub, missing);
+                }
+            }
+        }
+        let mut missing = vec![];
+        check(&Opts::command(), &mut missing);
+        assert!(
+            missing.is_empty(),
+            "options without a variable: {:?}",
+            missing
+        );
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(parse_duration("90"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("12h"), Ok(Duration::from_secs(12 * 60 * 60)));
+        assert_eq!(
+            parse_duration("6months"),
+            Ok(Duration::from_secs(6 * 30 * 24 * 60 * 60))
+        );
+        assert!(parse_duration("d").is_err());
+        assert!(parse_duration("7 fortnights").is_err());
+    }
+
+    #[test]
+    fn amounts() {
+        assert_eq!(parse_amount("200"), Ok(20000));
+        assert_eq!(parse_amount("49.5"), Ok(4950));
+        assert_eq!(parse_amount("0.07"), Ok(7));
+        assert!(parse_amount("1.234").is_err());
+        assert!(parse_amount("-5").is_err());
+        assert!(parse_amount("$5").is_err());
+    }
+
+    #[test]
+    fn colors() {
+        let color = |when: &str| match Opts::try_parse_from(["cargo", "fund", "--color", when]) {
+            Ok(Opts::Fund(args)) => Ok(args.color),
+            Err(e) => Err(e),
+        };
+        assert_eq!(color("never").unwrap(), Some(Color::Never));
+        assert!(color("nevr").is_err());
+    }
+
+    #[test]
+    fn output_formats() {
+        let args = |argv: &[&str]| match Opts::parse_from(argv) {
+            Opts::Fund(args) => args,
+        };
+        let config = Config::default();
+        let format = |argv: &[&str]| args(argv).format(&config);
+        assert_eq!(
+            format(&["cargo", "fund", "--output", "out/fund.json"]),
+            Format::Json
+        );
+        assert_eq!(
+            format(&["cargo", "fund", "--output", "fund.tsv"]),
+            Format::Porcelain
+        );
+        assert_eq!(
+            format(&["cargo", "fund", "--output", "fund.prom"]),
+            Format::Prometheus
+        );
+        assert_eq!(
+            format(&["cargo", "fund", "--output", "fund.md"]),
+            Format::Tree
+        );
+        assert_eq!(
+            format(&["cargo", "fund", "--format", "tree", "--output", "fund.json"]),
+            Format::Tree
+        );
+    }
 }
@@ -0,0 +1,170 @@
+//! `cargo fund assign`: a split of the fundable maintainers between the members of a team.
+//!
+//! The team is listed in a TOML file, with an optional weight for each member:
+//!
+//! ```toml
+//! [members.alice]
+//! weight = 2
+//!
+//! [members.bob]
+//! ```
+//!
+//! Maintainers and members are both taken in order of their names, so the same dependencies and
+//! team always give the same assignments.
+
+use crate::args::AssignStrategy;
+use crate::plan::allocate;
+use anyhow::{bail, Context, Error};
+use cargo_fund::Group;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::Path;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Team {
+    members: BTreeMap<String, Member>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Member {
+    /// How many maintainers the member takes on relative to the others, when weighted.
+    weight: u32,
+}
+
+impl Default for Member {
+    fn default() -> Self {
+        Member { weight: 1 }
+    }
+}
+
+impl Team {
+    pub fn load(path: &Path) -> Result<Team, Error> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("error reading {}", path.display()))?;
+        let team: Team =
+            toml::from_str(&text).with_context(|| format!("error parsing {}", path.display()))?;
+        if team.members.is_empty() {
+            bail!("no team members in {}", path.display());
+        }
+        Ok(team)
+    }
+}
+
+/// A maintainer to fund, as assigned.
+#[derive(Debug, Serialize)]
+pub struct Assigned {
+    pub maintainer: String,
+    pub links: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Assignments {
+    /// The maintainers assigned to each member, by the member's name.
+    pub members: BTreeMap<String, Vec<Assigned>>,
+    /// The maintainers left over once every member has as many as they take on.
+    pub unassigned: Vec<Assigned>,
+}
+
+/// Hand out `items` in turn to the members with the given capacities, each to the member who is
+/// furthest from filling their capacity. Returns the member for each item, or `None` once every
+/// member is full.
+fn distribute(capacities: &[usize], items: usize) -> Vec<Option<usize>> {
+    let mut taken = vec![0; capacities.len()];
+    (0..items)
+        .map(|_| {
+            let open = (0..capacities.len()).filter(|&ix| taken[ix] < capacities[ix]);
+            // compare taken[a] / capacities[a] with taken[b] / capacities[b] without dividing
+            let next =
+                open.min_by(|&a, &b| (taken[a] * capacities[b]).cmp(&(taken[b] * capacities[a])))?;
+            taken[next] += 1;
+            Some(next)
+        })
+        .collect()
+}
+
+/// Split the maintainers of `groups` between the members of `team`. With `per_member`, each
+/// member takes on that many maintainers, times their weight when weighted; otherwise all the
+/// maintainers are split between them.
+pub fn assign(
+    team: &Team,
+    groups: &[Group],
+    strategy: AssignStrategy,
+    per_member: Option<usize>,
+) -> Assignments {
+    let weights = team
+        .members
+        .values()
+        .map(|member| match strategy {
+            AssignStrategy::RoundRobin => 1,
+            AssignStrategy::Weighted => member.weight as usize,
+        })
+        .collect::<Vec<_>>();
+    let capacities: Vec<usize> = match per_member {
+        Some(n) => weights.iter().map(|weight| weight * n).collect(),
+        None => {
+            let weights = weights.iter().map(|&w| w as f64).collect::<Vec<_>>();
+            let shares = allocate(&weights, groups.len() as u64);
+            shares.into_iter().map(|share| share as usize).collect()
+        }
+    };
+    let mut groups = groups.iter().collect::<Vec<_>>();
+    groups.sort_by_cached_key(|group| group.name());
+    let names = team.members.keys().collect::<Vec<_>>();
+    let mut assignments = Assignments {
+        members: names.iter().map(|&name| (name.clone(), vec![])).collect(),
+        unassigned: vec![],
+    };
+    for (group, member) in groups.iter().zip(distribute(&capacities, groups.len())) {
+        let assigned = Assigned {
+            maintainer: group.heading.clone().unwrap_or_else(|| group.name()),
+            links: group.links.iter().map(|link| link.url.clone()).collect(),
+        };
+        match member {
+            Some(ix) => assignments
+                .members
+                .get_mut(names[ix])
+                .unwrap()
+                .push(assigned),
+            None => assignments.unassigned.push(assigned),
+        }
+    }
+    assignments
+}
+
+/// Write each member's list of maintainers and their funding links.
+pub fn write_assignments(out: &mut String, assignments: &Assignments) {
+    let mut write_list = |heading: &str, list: &[Assigned]| {
+        writeln!(out, "{} ({} maintainers)", heading, list.len()).unwrap();
+        for assigned in list {
+            writeln!(out, "  {}", assigned.maintainer).unwrap();
+            for link in &assigned.links {
+                writeln!(out, "    {}", link).unwrap();
+            }
+        }
+    };
+    for (member, list) in &assignments.members {
+        write_list(member, list);
+    }
+    if !assignments.unassigned.is_empty() {
+        write_list("unassigned", &assignments.unassigned);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn distributes() {
+        assert_eq!(
+            distribute(&[2, 2], 5),
+            [Some(0), Some(1), Some(0), Some(1), None]
+        );
+        assert_eq!(distribute(&[2, 1], 3), [Some(0), Some(1), Some(0)]);
+        assert_eq!(distribute(&[0, 1], 2), [Some(1), None]);
+        assert_eq!(distribute(&[], 1), [None]);
+    }
+}
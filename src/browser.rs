@@ -0,0 +1,144 @@
+//! Opening funding links in the user's browser.
+
+use anyhow::Error;
+use cargo_fund::Group;
+use cargo_metadata::Metadata;
+use std::io::{BufRead, IsTerminal, Write};
+use std::process::{Command, Stdio};
+
+/// Whether `url` is one to hand to the system to open: funding links come from other people's
+/// FUNDING.yml files, so only plain web links are opened, never a file or another program.
+fn is_web_link(url: &str) -> bool {
+    let uri = url.parse::<http::Uri>().ok();
+    let scheme = uri.as_ref().and_then(|uri| uri.scheme_str());
+    matches!(scheme, Some("http" | "https")) && !url.chars().any(char::is_control)
+}
+
+/// Open `url` in the user's browser.
+pub fn open(url: &str) -> std::io::Result<()> {
+    if !is_web_link(url) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "not an http or https link",
+        ));
+    }
+    let mut command = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(windows) {
+        // not `cmd /C start`, which would run whatever follows a `&` in the URL as a command
+        let mut command = Command::new("rundll32");
+        command.arg("url.dll,FileProtocolHandler");
+        command
+    } else {
+        Command::new("xdg-open")
+    };
+    command
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map(drop)
+}
+
+/// An answer to whether to open a group's links.
+#[derive(Debug, Eq, PartialEq)]
+enum Answer {
+    Yes,
+    No,
+    All,
+    Quit,
+}
+
+fn parse_answer(line: &str) -> Option<Answer> {
+    match line.trim().to_ascii_lowercase().as_str() {
+        "y" | "yes" => Some(Answer::Yes),
+        "" | "n" | "no" => Some(Answer::No),
+        "a" | "all" => Some(Answer::All),
+        "q" | "quit" => Some(Answer::Quit),
+        _ => None,
+    }
+}
+
+/// Ask about each group in turn, and open the links of those the user chooses, stopping once
+/// `limit` links have been opened so that a slip of the keyboard can't open dozens of tabs.
+pub fn prompt_and_open(metadata: &Metadata, groups: &[Group], limit: usize) -> Result<(), Error> {
+    let stdin = std::io::stdin();
+    if !stdin.is_terminal() {
+        anyhow::bail!("--open asks before opening each link, so it needs an interactive terminal");
+    }
+    let mut lines = stdin.lock().lines();
+    let mut opened = 0;
+    let mut all = false;
+    for group in groups {
+        let name = match &group.heading {
+            Some(heading) => heading.clone(),
+            None => group
+                .pkgs
+                .iter()
+                .map(|id| metadata[id].name.as_str())
+                .collect::<Vec<_>>()
+                .join(", "),
+        };
+        if !all {
+            let answer = loop {
+                eprint!(
+                    "open {} funding links for {} ({} packages)? [y]es, [N]o, [a]ll, [q]uit: ",
+                    group.links.len(),
+                    name,
+                    group.pkgs.len()
+                );
+                std::io::stderr().flush()?;
+                let Some(line) = lines.next().transpose()? else {
+                    return Ok(());
+                };
+                if let Some(answer) = parse_answer(&line) {
+                    break answer;
+                }
+            };
+            match answer {
+                Answer::Yes => (),
+                Answer::No => continue,
+                Answer::All => all = true,
+                Answer::Quit => return Ok(()),
+            }
+        }
+        for link in &group.links {
+            if opened == limit {
+                eprintln!(
+                    "stopping after opening {} links; pass a higher --open-limit to open more",
+                    limit
+                );
+                return Ok(());
+            }
+            match open(&link.url) {
+                Ok(()) => opened += 1,
+                Err(e) => eprintln!("warning: could not open {}: {}", link.url, e),
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn answers() {
+        assert_eq!(parse_answer("Y\n"), Some(Answer::Yes));
+        assert_eq!(parse_answer(""), Some(Answer::No));
+        assert_eq!(parse_answer(" all "), Some(Answer::All));
+        assert_eq!(parse_answer("q"), Some(Answer::Quit));
+        assert_eq!(parse_answer("maybe"), None);
+    }
+
+    #[test]
+    fn opens_only_web_links() {
+        assert!(is_web_link("https://github.com/sponsors/someone"));
+        assert!(is_web_link("http://example.com/donate?x=1&y=2"));
+        assert!(!is_web_link("file:///etc/passwd"));
+        assert!(!is_web_link("javascript:alert(1)"));
+        assert!(!is_web_link("calc.exe"));
+    }
+}
@@ -0,0 +1,207 @@
+//! A local cache of what each source's funding links were when last queried.
+//!
+//! Funding links change rarely, so the cache is shared by every project on the machine, and a run
+//! only queries sources that haven't been seen within the cache's time-to-live. Since most
+//! projects depend on many of the same crates, a fresh project is usually resolved mostly from the
+//! cache.
+
+use super::{Link, Outcome, Platform};
+use crate::error::Error;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::{debug, trace};
+use web_time::SystemTime;
+
+/// Bumped whenever the format changes incompatibly, which discards old caches.
+const VERSION: u32 = 1;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Cache {
+    version: u32,
+    /// Entries keyed by the lowercased URL of their source.
+    entries: BTreeMap<String, Entry>,
+    /// A hash of each lockfile whose dependencies were all resolved, keyed by its path.
+    #[serde(default)]
+    lockfiles: BTreeMap<String, u64>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct Entry {
+    /// When the source was queried, in seconds since the Unix epoch.
+    fetched: u64,
+    /// The funding links found, or `None` if the source was not found.
+    links: Option<Vec<CachedLink>>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct CachedLink {
+    platform: String,
+    url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    login: Option<String>,
+}
+
+impl Default for Cache {
+    fn default() -> Self {
+        Cache {
+            version: VERSION,
+            entries: BTreeMap::new(),
+            lockfiles: BTreeMap::new(),
+        }
+    }
+}
+
+/// The default location of the cache, such as `~/.cache/cargo-fund/links.json` on Linux, or in
+/// `cache_dir` if it is given.
+pub fn default_path(cache_dir: Option<&Path>) -> Option<PathBuf> {
+    match cache_dir {
+        Some(dir) => Some(dir.join("links.json")),
+        None => Some(dirs::cache_dir()?.join("cargo-fund").join("links.json")),
+    }
+}
+
+pub(crate) fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+fn lockfile_key(path: &Path) -> String {
+    path.to_string_lossy().into_owned()
+}
+
+/// The 64-bit FNV-1a hash of `bytes`, which unlike `std`'s hashers is stable between releases.
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+impl Cache {
+    /// Load the cache from `path`. A missing, unreadable, or outdated cache is treated as empty.
+    pub fn load(path: &Path) -> Cache {
+        let cache =
+            std::fs::read(path).and_then(|bytes| Ok(serde_json::from_slice::<Cache>(&bytes)?));
+        match cache {
+            Ok(cache) if cache.version == VERSION => cache,
+            Ok(_) => {
+                debug!(
+                    "discarding cache at {} from another version",
+                    path.display()
+                );
+                Cache::default()
+            }
+            Err(e) => {
+                debug!("not using cache at {}: {}", path.display(), e);
+                Cache::default()
+            }
+        }
+    }
+
+    /// Write the cache to `path`, creating its directory if needed.
+    ///
+    /// Other projects may have updated the cache since it was loaded, so the entries already at
+    /// `path` are merged in, keeping whichever of each was fetched most recently.
+    pub fn save(mut self, path: &Path) -> Result<(), Error> {
+        self.merge(Cache::load(path));
+        let error = |source| Error::Cache {
+            path: path.to_path_buf(),
+            source,
+        };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(error)?;
+        }
+        // write to a temporary file first so that an interrupted run can't corrupt the cache
+        let tmp = path.with_extension("json.tmp");
+        let json = serde_json::to_vec(&self).map_err(|e| error(e.into()))?;
+        std::fs::write(&tmp, json).map_err(error)?;
+        std::fs::rename(&tmp, path).map_err(error)?;
+        Ok(())
+    }
+
+    /// Merge the entries of `other` into this cache, keeping whichever of each was fetched most
+    /// recently.
+    pub fn merge(&mut self, other: Cache) {
+        for (key, entry) in other.entries {
+            match self.entries.get(&key) {
+                Some(ours) if ours.fetched >= entry.fetched => (),
+                _ => {
+                    self.entries.insert(key, entry);
+                }
+            }
+        }
+        for (key, hash) in other.lockfiles {
+            self.lockfiles.entry(key).or_insert(hash);
+        }
+    }
+
+    /// The cached outcome for `key`, if it was fetched within `ttl`.
+    pub fn get(&self, key: &str, ttl: Duration) -> Option<Outcome> {
+        let entry = self.entries.get(&key.to_ascii_lowercase())?;
+        if now().saturating_sub(entry.fetched) > ttl.as_secs() {
+            trace!("cache entry for {} has expired", key);
+            return None;
+        }
+        match &entry.links {
+            None => Some(Outcome::NotFound),
+            Some(links) => {
+                let mut parsed = vec![];
+                for link in links {
+                    let uri = link.url.parse().ok()?;
+                    parsed.push(Link {
+                        platform: Platform::from(link.platform.as_str()),
+                        uri,
+                        login: link.login.clone(),
+                    });
+                }
+                Some(Outcome::Found(parsed))
+            }
+        }
+    }
+
+    /// Whether the lockfile at `path` is unchanged since all of its dependencies were last resolved.
+    pub fn lockfile_unchanged(&self, path: &Path) -> bool {
+        match std::fs::read(path) {
+            Ok(contents) => self.lockfiles.get(&lockfile_key(path)) == Some(&fnv1a(&contents)),
+            Err(_) => false,
+        }
+    }
+
+    /// Record that all of the dependencies in the lockfile at `path` were just resolved.
+    pub fn insert_lockfile(&mut self, path: &Path) {
+        match std::fs::read(path) {
+            Ok(contents) => {
+                self.lockfiles.insert(lockfile_key(path), fnv1a(&contents));
+            }
+            Err(e) => debug!("not recording lockfile {}: {}", path.display(), e),
+        }
+    }
+
+    /// Record the outcome of querying `key` just now. Failures aren't recorded, so that they are
+    /// retried next time.
+    pub fn insert(&mut self, key: &str, outcome: &Outcome) {
+        let links = match outcome {
+            Outcome::Failed(_) | Outcome::RateLimited(_) => return,
+            Outcome::NotFound => None,
+            Outcome::Found(links) => Some(
+                links
+                    .iter()
+                    .map(|link| CachedLink {
+                        platform: link.platform.as_str().to_string(),
+                        url: link.uri.to_string(),
+                        login: link.login.clone(),
+                    })
+                    .collect(),
+            ),
+        };
+        self.entries.insert(
+            key.to_ascii_lowercase(),
+            Entry {
+                fetched: now(),
+                links,
+            },
+        );
+    }
+}
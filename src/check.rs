@@ -0,0 +1,165 @@
+//! `cargo fund check`: a policy check for funding decisions.
+//!
+//! A workspace can commit a `funding-decisions.toml` file recording what it decided for each
+//! fundable maintainer of its dependencies, much like a list of approved licenses:
+//!
+//! ```toml
+//! [maintainers.dtolnay]
+//! decision = "sponsor"
+//!
+//! [maintainers."https://opencollective.com/example"]
+//! decision = "decline"
+//! reason = "funded through our foundation membership"
+//! ```
+//!
+//! Maintainers are keyed by their Github account, or by one of their funding links when they have
+//! no Github account. The check fails when a dependency has funding links but no decision has been
+//! made for its maintainer, so that new maintainers are noticed as they are added.
+
+use anyhow::{Context, Error};
+use cargo_fund::report::FundReport;
+use cargo_fund::{Group, LinkSource};
+use cargo_metadata::PackageId;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+
+/// The name of a workspace's funding decisions file.
+pub const FILE_NAME: &str = "funding-decisions.toml";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Decisions {
+    /// Decisions keyed by Github account or funding link.
+    maintainers: BTreeMap<String, Decision>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Decision {
+    pub decision: Verdict,
+    pub reason: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Verdict {
+    Sponsor,
+    Decline,
+}
+
+impl Decisions {
+    /// Load the decisions at `path`. A missing file holds no decisions.
+    pub fn load(path: &Path) -> Result<Decisions, Error> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Decisions::default()),
+            Err(e) => {
+                return Err(Error::from(e).context(format!("error reading {}", path.display())))
+            }
+        };
+        toml::from_str(&text).with_context(|| format!("error parsing {}", path.display()))
+    }
+
+    /// The decision made for the maintainer of `group`, looked up by its heading or its links.
+    pub fn get(&self, group: &Group) -> Option<(&str, &Decision)> {
+        let keys = group
+            .heading
+            .iter()
+            .chain(group.links.iter().map(|link| &link.url));
+        keys.filter_map(|key| {
+            self.maintainers
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(key))
+        })
+        .map(|(name, decision)| (name.as_str(), decision))
+        .next()
+    }
+}
+
+/// The outcome of checking a report against the decisions.
+pub struct Outcome<'a> {
+    /// The maintainers with decisions, and what was decided.
+    pub decided: Vec<(Group, &'a str, &'a Decision)>,
+    /// The maintainers without decisions.
+    pub undecided: Vec<Group>,
+}
+
+/// Group the fundable packages of `report` by maintainer, and look up the decision for each.
+///
+/// Links on platforms that the configuration doesn't allow can't be used to fund anyone, so they
+/// are left out, and a maintainer with only such links needs no decision.
+pub fn check<'a>(
+    source_map: &HashMap<LinkSource, HashSet<PackageId>>,
+    report: &FundReport,
+    decisions: &'a Decisions,
+) -> Outcome<'a> {
+    let mut outcome = Outcome {
+        decided: vec![],
+        undecided: vec![],
+    };
+    for group in cargo_fund::group_by_owner(source_map, &report.allowed()) {
+        match decisions.get(&group) {
+            Some((name, decision)) => outcome.decided.push((group, name, decision)),
+            None => outcome.undecided.push(group),
+        }
+    }
+    outcome
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cargo_fund::report::FundingLink;
+    use std::collections::BTreeSet;
+
+    fn group(heading: Option<&str>, url: &str) -> Group {
+        let link = FundingLink {
+            platform: "CUSTOM".to_string(),
+            url: url.to_string(),
+            login: None,
+            provenance: BTreeSet::new(),
+            disallowed: false,
+            goal: None,
+            sponsoring: false,
+            sponsors: None,
+            patrons: None,
+        };
+        Group {
+            heading: heading.map(str::to_string),
+            links: BTreeSet::from([link]),
+            pkgs: BTreeSet::new(),
+        }
+    }
+
+    #[test]
+    fn finds_decisions() {
+        let decisions: Decisions = toml::from_str(
+            r#"
+            [maintainers.dtolnay]
+            decision = "sponsor"
+
+            [maintainers."https://example.com/fund"]
+            decision = "decline"
+            reason = "already funded"
+            "#,
+        )
+        .unwrap();
+        let (name, decision) = decisions
+            .get(&group(
+                Some("DTolnay"),
+                "https://github.com/sponsors/dtolnay",
+            ))
+            .unwrap();
+        assert_eq!(name, "dtolnay");
+        assert_eq!(decision.decision, Verdict::Sponsor);
+        let (_, decision) = decisions
+            .get(&group(None, "https://example.com/fund"))
+            .unwrap();
+        assert_eq!(decision.decision, Verdict::Decline);
+        assert!(decisions
+            .get(&group(Some("someone"), "https://example.com/other"))
+            .is_none());
+        assert!(toml::from_str::<Decisions>("[maintainers.x]\ndecision = \"maybe\"").is_err());
+    }
+}
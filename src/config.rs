@@ -0,0 +1,415 @@
+//! Configuration files.
+//!
+//! A workspace can commit its choices for `cargo fund` to a `.cargo-fund.toml` file in its root.
+//! Like Cargo's own configuration, the file is found by searching upwards from the manifest's
+//! directory, so it is also found when running in a member of the workspace. Options given on the
+//! command line or in environment variables take precedence over the file.
+//!
+//! The same options can be kept with the rest of a workspace's settings in the
+//! `[workspace.metadata.fund]` table of its root `Cargo.toml`, beneath `.cargo-fund.toml`.
+//!
+//! Each file can also have named profiles, such as `[profile.ci]`, holding options that apply
+//! over the rest of the file when the profile is selected with `--profile`.
+//!
+//! Machine-wide defaults can be set in a user configuration file, such as
+//! `~/.config/cargo-fund/config.toml` on Linux, in the same format. A workspace's own
+//! configuration takes precedence over the user's.
+
+use crate::args::{Charset, Color, Format, GroupBy, SortKey, Style, ThemeName};
+use anyhow::{bail, Context, Error};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// The name of a workspace's configuration file.
+pub const FILE_NAME: &str = ".cargo-fund.toml";
+
+/// The location of the user's configuration file, if the platform has a configuration directory.
+pub fn user_path() -> Option<PathBuf> {
+    Some(dirs::config_dir()?.join("cargo-fund").join("config.toml"))
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    /// The format to print results in.
+    pub format: Option<Format>,
+    /// The style of the tree.
+    pub style: Option<Style>,
+    /// The characters the tree may use.
+    pub charset: Option<Charset>,
+    /// When to color the output.
+    pub color: Option<Color>,
+    pub theme: Option<ThemeName>,
+    pub group_by: Option<GroupBy>,
+    pub sort: Option<SortKey>,
+    pub reverse: Option<bool>,
+    pub show_descriptions: Option<bool>,
+    pub show_activity: Option<bool>,
+    pub show_downloads: Option<bool>,
+    pub show_outdated: Option<bool>,
+    pub show_goals: Option<bool>,
+    pub show_sponsoring: Option<bool>,
+    pub show_sponsors: Option<bool>,
+    pub show_patrons: Option<bool>,
+    pub hide_sponsored: Option<bool>,
+    pub only_unsponsored: Option<bool>,
+    pub show_impact: Option<bool>,
+    pub show_missing: Option<bool>,
+    pub show_provenance: Option<bool>,
+    pub show_vet: Option<bool>,
+    pub icons: Option<bool>,
+    /// The number of columns to fit the output into.
+    pub width: Option<usize>,
+    /// Never pipe output through a pager.
+    pub no_pager: Option<bool>,
+    /// Add a summary to the job summary when running in GitHub Actions.
+    pub job_summary: Option<bool>,
+    /// Where to keep the cache. Relative paths are relative to the configuration file.
+    pub cache_dir: Option<PathBuf>,
+    /// The ledger of donations. Relative paths are relative to the configuration file.
+    pub ledger: Option<PathBuf>,
+    /// A file holding the Github API token. Relative paths are relative to the configuration file.
+    pub token_file: Option<PathBuf>,
+    /// Directories to search for workspaces with `cargo fund all`. Relative paths are relative to
+    /// the configuration file.
+    pub roots: Vec<PathBuf>,
+    /// The names of packages to leave out of the results.
+    pub ignore: Vec<String>,
+    /// Github accounts to leave out of the results, along with their repositories.
+    pub ignore_owners: Vec<String>,
+    /// The only registries to include packages from, by name or index URL. If empty, packages
+    /// from all registries are included unless excluded.
+    pub registries: Vec<String>,
+    /// Registries to leave packages out from, by name or index URL.
+    pub exclude_registries: Vec<String>,
+    /// Whether to include git dependencies, which are left out by default.
+    pub include_git_deps: Option<bool>,
+    /// Whether to leave out path dependencies outside the workspace.
+    pub exclude_path_deps: Option<bool>,
+    /// The only funding platforms to allow, by their names in Github's `FundingPlatform` enum,
+    /// such as `OPEN_COLLECTIVE`. If empty, all platforms are allowed unless denied.
+    pub allowed_platforms: Vec<String>,
+    /// Funding platforms not to allow.
+    pub denied_platforms: Vec<String>,
+    /// What to do with links on platforms that aren't allowed.
+    pub disallowed_links: Option<DisallowedLinks>,
+    /// The percentage of dependencies that must have funding links.
+    pub min_coverage: Option<u8>,
+    /// Fail if no funding links are found.
+    pub fail_if_empty: Option<bool>,
+    /// Treat malformed funding data and failed queries as errors.
+    pub strict: Option<bool>,
+    pub github: GithubConfig,
+    /// Overrides for individual packages, by name.
+    pub package: BTreeMap<String, PackageConfig>,
+    /// Options that apply over the rest when a profile is selected, by the profile's name.
+    pub profile: BTreeMap<String, Config>,
+}
+
+/// Overrides for a single package's funding links.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct PackageConfig {
+    /// Funding links to add to those that are found.
+    pub links: Vec<String>,
+    /// Leave the package out of the results.
+    pub suppress: bool,
+    /// Why the package is overridden, which is shown in verbose output.
+    pub reason: Option<String>,
+}
+
+/// What to do with funding links on platforms that aren't allowed.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum DisallowedLinks {
+    /// Leave them out of the results.
+    #[default]
+    Hide,
+    /// Show them, marked as not allowed.
+    Flag,
+}
+
+/// Settings for looking up funding links with the Github API.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct GithubConfig {
+    pub batch_size: Option<usize>,
+    pub jobs: Option<usize>,
+    pub timeout: Option<u64>,
+    pub wait_for_rate_limit: Option<bool>,
+    pub proxy: Option<String>,
+    /// Relative paths are relative to the configuration file.
+    pub cacert: Option<PathBuf>,
+}
+
+impl Config {
+    /// Load the first workspace configuration file found in `dir` or its ancestors, with the
+    /// workspace manifest's configuration and then the user's beneath it. If a profile is given,
+    /// it is selected in each of them, and at least one must have it.
+    pub fn discover(dir: &Path, profile: Option<&str>) -> Result<Config, Error> {
+        let mut layers = vec![];
+        if let Some(dir) = dir.ancestors().find(|dir| dir.join(FILE_NAME).is_file()) {
+            layers.push(Config::load(&dir.join(FILE_NAME))?);
+        }
+        layers.push(Config::load_manifest(dir)?);
+        if let Some(path) = user_path() {
+            layers.push(Config::load(&path)?);
+        }
+        let mut config = Config::default();
+        let mut found = profile.is_none();
+        for mut layer in layers {
+            if let Some(profile) = profile {
+                found |= layer.select_profile(profile)?;
+            }
+            config.merge(layer);
+        }
+        if !found {
+            let name = profile.unwrap_or_default();
+            return Err(crate::diagnostic::with_help(
+                anyhow::anyhow!("no profile named {:?} in the configuration", name),
+                "--profile, or CARGO_FUND_PROFILE",
+                format!("define it under [profile.{}] in .cargo-fund.toml", name),
+            ));
+        }
+        Ok(config)
+    }
+
+    /// Apply the profile called `name` over the rest of the configuration, returning whether
+    /// there was one.
+    fn select_profile(&mut self, name: &str) -> Result<bool, Error> {
+        let mut profile = match self.profile.remove(name) {
+            Some(profile) => profile,
+            None => return Ok(false),
+        };
+        if !profile.profile.is_empty() {
+            bail!("profile {:?} can't have profiles of its own", name);
+        }
+        profile.merge(std::mem::take(self));
+        *self = profile;
+        Ok(true)
+    }
+
+    /// Load the configuration file at `path`, if there is one.
+    pub fn load(path: &Path) -> Result<Config, Error> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Config::default()),
+            Err(e) => {
+                return Err(Error::new(e).context(format!("error reading {}", path.display())))
+            }
+        };
+        let mut config: Config = toml::from_str(&contents)
+            .with_context(|| format!("error parsing {}", path.display()))?;
+        config.resolve_paths(path.parent().unwrap_or(Path::new("")));
+        Ok(config)
+    }
+
+    /// Load the `[workspace.metadata.fund]` table of the workspace that `dir` is in, if it has
+    /// one.
+    ///
+    /// The workspace root is the nearest manifest in `dir` or its ancestors with a `[workspace]`
+    /// table, as it is for Cargo.
+    fn load_manifest(dir: &Path) -> Result<Config, Error> {
+        for dir in dir.ancestors() {
+            let path = dir.join("Cargo.toml");
+            let contents = match std::fs::read_to_string(&path) {
+                Ok(contents) => contents,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => {
+                    return Err(Error::new(e).context(format!("error reading {}", path.display())))
+                }
+            };
+            let manifest: toml::Table = toml::from_str(&contents)
+                .with_context(|| format!("error parsing {}", path.display()))?;
+            let workspace = match manifest.get("workspace") {
+                Some(workspace) => workspace,
+                None => continue,
+            };
+            let table = workspace.get("metadata").and_then(|m| m.get("fund"));
+            let mut config: Config = match table {
+                Some(table) => table.clone().try_into().with_context(|| {
+                    format!(
+                        "error parsing [workspace.metadata.fund] in {}",
+                        path.display()
+                    )
+                })?,
+                None => Config::default(),
+            };
+            config.resolve_paths(dir);
+            return Ok(config);
+        }
+        Ok(Config::default())
+    }
+
+    /// Make the relative paths in the configuration relative to `dir`.
+    fn resolve_paths(&mut self, dir: &Path) {
+        let paths = [
+            &mut self.token_file,
+            &mut self.cache_dir,
+            &mut self.ledger,
+            &mut self.github.cacert,
+        ];
+        for path in paths.into_iter().flatten().chain(&mut self.roots) {
+            *path = dir.join(&*path);
+        }
+        for profile in self.profile.values_mut() {
+            profile.resolve_paths(dir);
+        }
+    }
+
+    /// Whether the package called `name` is suppressed.
+    pub fn suppressed(&self, name: &str) -> bool {
+        self.package
+            .get(name)
+            .is_some_and(|package| package.suppress)
+    }
+
+    /// Whether links on `platform` are allowed.
+    pub fn platform_allowed(&self, platform: &str) -> bool {
+        let listed = |list: &[String]| list.iter().any(|p| p.eq_ignore_ascii_case(platform));
+        (self.allowed_platforms.is_empty() || listed(&self.allowed_platforms))
+            && !listed(&self.denied_platforms)
+    }
+
+    /// Fill in the options that aren't set with those from `base`. Ignore lists, denied platforms,
+    /// and excluded registries are combined, but allowed platforms and included registries are
+    /// only taken from `base` if none are set.
+    /// A package's overrides are taken whole from whichever configuration has them first.
+    pub fn merge(&mut self, base: Config) {
+        let github = &mut self.github;
+        self.format = self.format.or(base.format);
+        self.style = self.style.or(base.style);
+        self.charset = self.charset.or(base.charset);
+        self.color = self.color.or(base.color);
+        self.theme = self.theme.or(base.theme);
+        self.group_by = self.group_by.or(base.group_by);
+        self.sort = self.sort.or(base.sort);
+        self.reverse = self.reverse.or(base.reverse);
+        self.show_descriptions = self.show_descriptions.or(base.show_descriptions);
+        self.show_activity = self.show_activity.or(base.show_activity);
+        self.show_downloads = self.show_downloads.or(base.show_downloads);
+        self.show_outdated = self.show_outdated.or(base.show_outdated);
+        self.show_goals = self.show_goals.or(base.show_goals);
+        self.show_sponsoring = self.show_sponsoring.or(base.show_sponsoring);
+        self.show_sponsors = self.show_sponsors.or(base.show_sponsors);
+        self.show_patrons = self.show_patrons.or(base.show_patrons);
+        self.hide_sponsored = self.hide_sponsored.or(base.hide_sponsored);
+        self.only_unsponsored = self.only_unsponsored.or(base.only_unsponsored);
+        self.show_impact = self.show_impact.or(base.show_impact);
+        self.show_missing = self.show_missing.or(base.show_missing);
+        self.show_provenance = self.show_provenance.or(base.show_provenance);
+        self.show_vet = self.show_vet.or(base.show_vet);
+        self.icons = self.icons.or(base.icons);
+        self.width = self.width.or(base.width);
+        self.no_pager = self.no_pager.or(base.no_pager);
+        self.job_summary = self.job_summary.or(base.job_summary);
+        self.cache_dir = self.cache_dir.take().or(base.cache_dir);
+        self.ledger = self.ledger.take().or(base.ledger);
+        self.token_file = self.token_file.take().or(base.token_file);
+        self.ignore.extend(base.ignore);
+        self.roots.extend(base.roots);
+        self.ignore_owners.extend(base.ignore_owners);
+        if self.registries.is_empty() {
+            self.registries = base.registries;
+        }
+        self.exclude_registries.extend(base.exclude_registries);
+        self.include_git_deps = self.include_git_deps.or(base.include_git_deps);
+        self.exclude_path_deps = self.exclude_path_deps.or(base.exclude_path_deps);
+        if self.allowed_platforms.is_empty() {
+            self.allowed_platforms = base.allowed_platforms;
+        }
+        self.denied_platforms.extend(base.denied_platforms);
+        self.disallowed_links = self.disallowed_links.or(base.disallowed_links);
+        self.min_coverage = self.min_coverage.or(base.min_coverage);
+        self.fail_if_empty = self.fail_if_empty.or(base.fail_if_empty);
+        self.strict = self.strict.or(base.strict);
+        for (name, package) in base.package {
+            self.package.entry(name).or_insert(package);
+        }
+        github.batch_size = github.batch_size.or(base.github.batch_size);
+        github.jobs = github.jobs.or(base.github.jobs);
+        github.timeout = github.timeout.or(base.github.timeout);
+        github.wait_for_rate_limit = github
+            .wait_for_rate_limit
+            .or(base.github.wait_for_rate_limit);
+        github.proxy = github.proxy.take().or(base.github.proxy);
+        github.cacert = github.cacert.take().or(base.github.cacert);
+    }
+
+    /// The Github API token from the token file, if one is configured.
+    pub fn token(&self) -> Result<Option<String>, Error> {
+        match &self.token_file {
+            Some(path) => {
+                let token = std::fs::read_to_string(path).with_context(|| {
+                    format!("error reading Github API token from {}", path.display())
+                })?;
+                Ok(Some(token.trim().to_string()))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let config: Config = toml::from_str(
+            r#"
+            format = "json"
+            ignore = ["openssl"]
+
+            [github]
+            jobs = 8
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.format, Some(Format::Json));
+        assert_eq!(config.ignore, ["openssl"]);
+        assert_eq!(config.github.jobs, Some(8));
+        assert!(toml::from_str::<Config>("colour = true").is_err());
+        assert!(toml::from_str::<Config>("color = \"nevr\"").is_err());
+    }
+
+    #[test]
+    fn merge() {
+        let mut config: Config = toml::from_str("format = \"json\"\nignore = [\"a\"]").unwrap();
+        let user: Config = toml::from_str(
+            "format = \"tree\"\ncolor = \"never\"\nignore = [\"b\"]\n[github]\njobs = 2",
+        )
+        .unwrap();
+        config.merge(user);
+        assert_eq!(config.format, Some(Format::Json));
+        assert_eq!(config.color, Some(Color::Never));
+        assert_eq!(config.ignore, ["a", "b"]);
+        assert_eq!(config.github.jobs, Some(2));
+    }
+
+    #[test]
+    fn profiles() {
+        let mut config: Config = toml::from_str(
+            "format = \"tree\"\ncolor = \"always\"\n[profile.ci]\nformat = \"json\"",
+        )
+        .unwrap();
+        assert!(!config.select_profile("local").unwrap());
+        assert!(config.select_profile("ci").unwrap());
+        assert_eq!(config.format, Some(Format::Json));
+        assert_eq!(config.color, Some(Color::Always));
+    }
+
+    #[test]
+    fn platforms() {
+        let config: Config = toml::from_str(
+            "allowed-platforms = [\"GITHUB\", \"OPEN_COLLECTIVE\"]\ndenied-platforms = [\"GITHUB\"]",
+        )
+        .unwrap();
+        assert!(config.platform_allowed("OPEN_COLLECTIVE"));
+        assert!(config.platform_allowed("open_collective"));
+        assert!(!config.platform_allowed("GITHUB"));
+        assert!(!config.platform_allowed("KO_FI"));
+        assert!(Config::default().platform_allowed("KO_FI"));
+    }
+}
@@ -0,0 +1,250 @@
+//! What crates.io knows about each dependency, for `--show-downloads`, `--show-activity`, and
+//! `--show-outdated`: how often it has been downloaded, which helps find crates that much of the
+//! ecosystem relies on while few people know who maintains them; when it was last released, which
+//! shows whether anyone is still maintaining it; and its latest version, which shows whether to
+//! upgrade before funding the version in use.
+//!
+//! The crates are looked up many at a time, and the counts are for all versions of a crate, as
+//! crates.io keeps them. The date of a crate's newest version needs a request of its own, so it's
+//! only looked up for `--show-activity`. Packages from anywhere but crates.io aren't looked up.
+
+use crate::ledger::Date;
+use crate::responses::Responses;
+use anyhow::{Context, Error};
+use cargo_fund::report::FundReport;
+use cargo_metadata::Metadata;
+use serde::Deserialize;
+use std::collections::{BTreeSet, HashMap};
+
+/// The number of crates to look up in each request, which is the most crates.io returns at once.
+const BATCH_SIZE: usize = 100;
+
+/// A crate released within this many days is considered actively maintained.
+pub const ACTIVE_DAYS: i64 = 180;
+
+#[derive(Deserialize)]
+struct Response {
+    crates: Vec<Crate>,
+}
+
+#[derive(Deserialize)]
+struct Crate {
+    id: String,
+    downloads: u64,
+    /// The version published most recently, prereleases included.
+    newest_version: String,
+    /// The latest version that isn't a prerelease, or `null` if there is none.
+    max_stable_version: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct VersionResponse {
+    version: Version,
+}
+
+#[derive(Deserialize)]
+struct Version {
+    /// When the version was published, such as `2024-03-01T12:00:00.000000+00:00`.
+    created_at: String,
+}
+
+/// What crates.io knows about a crate.
+pub struct Stats {
+    pub downloads: u64,
+    /// The date of the latest release, if crates.io's timestamp could be read.
+    pub last_release: Option<Date>,
+    /// The latest version that isn't a prerelease.
+    pub latest: Option<String>,
+}
+
+/// What crates.io knows about the packages in `report` that come from it, by crate name. The date
+/// of each crate's latest release is only looked up if `releases` is true.
+pub async fn fetch(
+    client: &reqwest::Client,
+    responses: &Responses,
+    metadata: &Metadata,
+    report: &FundReport,
+    releases: bool,
+) -> Result<HashMap<String, Stats>, Error> {
+    let names = report
+        .packages
+        .iter()
+        .filter(|pkg| {
+            metadata[&pkg.id]
+                .source
+                .as_ref()
+                .is_some_and(|source| from_crates_io(&source.to_string()))
+        })
+        .map(|pkg| pkg.name.as_str())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+    let mut stats = HashMap::new();
+    for batch in names.chunks(BATCH_SIZE) {
+        let mut query = vec![("per_page", BATCH_SIZE.to_string())];
+        query.extend(batch.iter().map(|&name| ("ids[]", name.to_string())));
+        let url = reqwest::Url::parse_with_params("https://crates.io/api/v1/crates", &query)?;
+        let response = responses
+            .get(client, url.as_str(), |body| body)
+            .await
+            .context("error looking up crates on crates.io")?;
+        if !response.status.is_success() {
+            anyhow::bail!("error looking up crates on crates.io: {}", response.status);
+        }
+        let response = serde_json::from_str::<Response>(&response.body)
+            .context("error reading crates from crates.io")?;
+        for krate in response.crates {
+            // one at a time, as crates.io asks of crawlers
+            let last_release = match releases {
+                true => released(client, responses, &krate.id, &krate.newest_version).await,
+                false => None,
+            };
+            stats.insert(
+                krate.id,
+                Stats {
+                    downloads: krate.downloads,
+                    last_release,
+                    latest: krate.max_stable_version,
+                },
+            );
+        }
+    }
+    Ok(stats)
+}
+
+/// When `version` of the crate `name` was published, if it could be looked up. Failing to is only
+/// a warning, since the rest of the crate's stats are still worth showing.
+async fn released(
+    client: &reqwest::Client,
+    responses: &Responses,
+    name: &str,
+    version: &str,
+) -> Option<Date> {
+    let url = format!("https://crates.io/api/v1/crates/{}/{}", name, version);
+    let keep = |body: String| published(&body).unwrap_or_default();
+    match responses.get(client, &url, keep).await {
+        Ok(response) if response.status.is_success() => response.body.parse().ok(),
+        Ok(response) => {
+            tracing::warn!("error looking up {} {}: {}", name, version, response.status);
+            None
+        }
+        Err(e) => {
+            tracing::warn!("error looking up {} {}: {}", name, version, e.without_url());
+            None
+        }
+    }
+}
+
+/// The date a version was published, such as `2024-03-01`, from crates.io's answer about it.
+fn published(body: &str) -> Option<String> {
+    let response = serde_json::from_str::<VersionResponse>(body).ok()?;
+    let date = response.version.created_at.get(..10)?;
+    Some(date.to_string())
+}
+
+/// Whether a package source is crates.io, through either its git or its sparse index.
+fn from_crates_io(source: &str) -> bool {
+    source == "registry+https://github.com/rust-lang/crates.io-index"
+        || source == "sparse+https://index.crates.io/"
+}
+
+/// A download count rounded for reading at a glance, such as `12k` or `3.4M`.
+pub fn abbreviate(count: u64) -> String {
+    let (scaled, suffix) = match count {
+        0..=999 => return count.to_string(),
+        1_000..=999_999 => (count as f64 / 1e3, "k"),
+        1_000_000..=999_999_999 => (count as f64 / 1e6, "M"),
+        _ => (count as f64 / 1e9, "B"),
+    };
+    if scaled < 10.0 {
+        format!("{:.1}{}", (scaled * 10.0).floor() / 10.0, suffix)
+    } else {
+        format!("{}{}", scaled.floor(), suffix)
+    }
+}
+
+/// Whether `latest` is a breaking change from `current` by semver: a new major version, or for
+/// versions before 1.0.0, a new minor version, or for versions before 0.1.0, a new patch version.
+/// Versions that can't be read aren't outdated.
+pub fn is_outdated(current: &str, latest: &str) -> bool {
+    let parse = |version: &str| -> Option<[u64; 3]> {
+        let version = version.split(['-', '+']).next()?;
+        let mut parts = version.split('.').map(|part| part.parse().ok());
+        Some([parts.next()??, parts.next()??, parts.next()??])
+    };
+    // the leading components up to the first that isn't zero, which semver-compatible versions
+    // share
+    let breaking = |[major, minor, patch]: [u64; 3]| match (major, minor) {
+        (0, 0) => [0, 0, patch],
+        (0, _) => [0, minor, 0],
+        _ => [major, 0, 0],
+    };
+    match (parse(current), parse(latest)) {
+        (Some(current), Some(latest)) => breaking(latest) > breaking(current),
+        _ => false,
+    }
+}
+
+/// How long ago a release was, such as `3 months ago`.
+pub fn ago(release: Date, today: Date) -> String {
+    let plural = |n: i64, unit: &str| match n {
+        1 => format!("1 {} ago", unit),
+        n => format!("{} {}s ago", n, unit),
+    };
+    match today.days() - release.days() {
+        days if days < 1 => "today".to_string(),
+        days if days < 60 => plural(days, "day"),
+        days if days < 730 => plural(days / 30, "month"),
+        days => plural(days / 365, "year"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn abbreviates() {
+        assert_eq!(abbreviate(999), "999");
+        assert_eq!(abbreviate(1_000), "1.0k");
+        assert_eq!(abbreviate(12_345), "12k");
+        assert_eq!(abbreviate(3_456_789), "3.4M");
+        assert_eq!(abbreviate(999_999_999), "999M");
+        assert_eq!(abbreviate(1_200_000_000), "1.2B");
+        assert!(from_crates_io("sparse+https://index.crates.io/"));
+    }
+
+    #[test]
+    fn reads_release_dates() {
+        let body = r#"{"version":{"num":"1.0.0","created_at":"2024-03-01T12:00:00.000000+00:00"}}"#;
+        assert_eq!(published(body).as_deref(), Some("2024-03-01"));
+        assert_eq!(published(r#"{"errors":[{"detail":"Not Found"}]}"#), None);
+        let response = serde_json::from_str::<Response>(
+            r#"{"crates":[{"id":"anyhow","downloads":1,"newest_version":"1.0.1",
+                "max_stable_version":"1.0.1","updated_at":"2025-01-01T00:00:00+00:00"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(response.crates[0].newest_version, "1.0.1");
+    }
+
+    #[test]
+    fn describes_age() {
+        let today: Date = "2024-03-01".parse().unwrap();
+        let ago = |date: &str| ago(date.parse().unwrap(), today);
+        assert_eq!(ago("2024-03-01"), "today");
+        assert_eq!(ago("2024-02-29"), "1 day ago");
+        assert_eq!(ago("2023-12-01"), "3 months ago");
+        assert_eq!(ago("2021-01-15"), "3 years ago");
+    }
+
+    #[test]
+    fn finds_outdated_versions() {
+        assert!(is_outdated("1.4.2", "3.0.0"));
+        assert!(!is_outdated("1.4.2", "1.9.0"));
+        assert!(is_outdated("0.3.9", "0.4.0"));
+        assert!(!is_outdated("0.3.1", "0.3.9"));
+        assert!(is_outdated("0.0.1", "0.0.2"));
+        assert!(!is_outdated("2.0.0-rc.1", "2.0.0"));
+        assert!(!is_outdated("1.0.0", "not a version"));
+    }
+}
@@ -0,0 +1,171 @@
+//! Rendering errors as diagnostics: the message, then what it's about and its causes, and a
+//! suggested fix where there is one.
+//!
+//! ```text
+//! Error: could not resolve funding links for https://github.com/owner/repo: ...
+//!   --> https://github.com/owner/repo
+//!   = help: pass --lenient to skip sources that fail, with a warning
+//! ```
+//!
+//! Errors from the library are explained by their kind, and point to `cargo fund explain` for
+//! their code. Errors from the command line, such as a flag naming something that doesn't exist,
+//! are marked with [`with_help`] where they're made.
+
+use anyhow::Error;
+use std::fmt::{self, Write};
+
+/// An error marked with what it's about and how to fix it. It displays exactly like the error it
+/// wraps, which is its source, so that the wrapped error can still be found in the chain.
+#[derive(Debug)]
+struct Diagnosed {
+    error: Error,
+    subject: String,
+    help: String,
+}
+
+impl fmt::Display for Diagnosed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.error, f)
+    }
+}
+
+impl std::error::Error for Diagnosed {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(self.error.as_ref())
+    }
+}
+
+/// Mark an error with what it's about, such as the flag or environment variable involved, and a
+/// suggested fix.
+pub fn with_help(error: Error, subject: impl Into<String>, help: impl Into<String>) -> Error {
+    Error::new(Diagnosed {
+        error,
+        subject: subject.into(),
+        help: help.into(),
+    })
+}
+
+/// What a library error is about, and how to fix it.
+fn explain(error: &cargo_fund::Error) -> (Option<String>, Option<&'static str>) {
+    use cargo_fund::Error::*;
+    match error {
+        Network(_) => (
+            None,
+            Some(
+                "check the network connection; behind a proxy, pass --proxy and perhaps \
+                 --cacert, or pass --offline to use only the cache",
+            ),
+        ),
+        Status(_) | MalformedResponse | UnparseableResponse { .. } => (
+            None,
+            Some(
+                "Github may be having trouble, so try again later; if a proxy answered \
+                 instead, check --proxy",
+            ),
+        ),
+        Api(_) => (
+            None,
+            Some("run with -vvv to log the query the error is about"),
+        ),
+        InvalidLink { url, .. } => (
+            Some(url.clone()),
+            Some(
+                "fix the link in the repository's .github/FUNDING.yml, or give the package's \
+                 links under [package.NAME] in .cargo-fund.toml",
+            ),
+        ),
+        SourceFailed { source_url, .. } => (
+            Some(source_url.clone()),
+            Some("pass --lenient to skip sources that fail, with a warning"),
+        ),
+        InvalidRepository { url, .. } => (
+            Some(url.clone()),
+            Some(
+                "fix the `repository` field in the package's Cargo.toml, or give the \
+                 package's links under [package.NAME] in .cargo-fund.toml",
+            ),
+        ),
+        Cache { path, .. } => (
+            Some(path.display().to_string()),
+            Some("pass --cache-dir to keep the cache elsewhere, or --no-cache to skip it"),
+        ),
+        Fixture { path, .. } => (
+            Some(path.display().to_string()),
+            Some("record the responses again with --record"),
+        ),
+        // the messages of the rest already say how to fix them
+        _ => (None, None),
+    }
+}
+
+/// Render `error` for printing to stderr, ending with a newline.
+pub fn render(error: &Error) -> String {
+    let mut out = format!("Error: {}\n", error);
+    let mut subject = None;
+    let mut help = None;
+    let mut code = None;
+    let mut previous = error.to_string();
+    for cause in error.chain() {
+        if let Some(diagnosed) = cause.downcast_ref::<Diagnosed>() {
+            subject = subject.or_else(|| Some(diagnosed.subject.clone()));
+            help = help.or_else(|| Some(diagnosed.help.clone()));
+        } else if let Some(error) = cause.downcast_ref::<cargo_fund::Error>() {
+            code = code.or(Some(error.code()));
+            let (about, fix) = explain(error);
+            subject = subject.or(about);
+            help = help.or_else(|| fix.map(str::to_string));
+        }
+    }
+    if let Some(subject) = subject {
+        writeln!(out, "  --> {}", subject).unwrap();
+    }
+    for cause in error.chain().skip(1) {
+        // wrappers such as `Diagnosed` repeat the message of the error they wrap
+        let message = cause.to_string();
+        if message != previous {
+            writeln!(out, "  = caused by: {}", message.replace('\n', "\n    ")).unwrap();
+        }
+        previous = message;
+    }
+    if let Some(help) = help {
+        writeln!(out, "  = help: {}", help).unwrap();
+    }
+    if let Some(code) = code {
+        writeln!(out, "  = note: for more, run `cargo fund explain {}`", code).unwrap();
+    }
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn renders() {
+        let error = with_help(
+            anyhow::anyhow!("unknown registry `internal`"),
+            "--registry",
+            "give the registry's index URL",
+        );
+        assert_eq!(
+            render(&error),
+            "Error: unknown registry `internal`\n  --> --registry\n  = help: give the \
+             registry's index URL\n"
+        );
+
+        let error = Err::<(), _>(cargo_fund::Error::SourceFailed {
+            source_url: "https://github.com/owner/repo".to_string(),
+            reason: "not found".to_string(),
+        })
+        .context("error looking up funding links")
+        .unwrap_err();
+        assert_eq!(
+            render(&error),
+            "Error: error looking up funding links\n  --> https://github.com/owner/repo\n  = \
+             caused by: could not resolve funding links for https://github.com/owner/repo: not \
+             found\n  = help: pass --lenient to skip sources that fail, with a warning\n  = note: \
+             for more, run `cargo fund explain source-failed`\n"
+        );
+    }
+}
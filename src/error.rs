@@ -0,0 +1,141 @@
+//! The errors that can occur while looking up funding links.
+
+use http::StatusCode;
+use std::path::PathBuf;
+use web_time::SystemTime;
+
+/// An error from looking up funding links.
+///
+/// Each kind of error has a stable, machine-readable [code](Error::code) alongside its message.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// Github rejected the API token.
+    #[error(
+        "Invalid Github API token. Create a token with the `public_repo` and `user` scopes at \
+         https://github.com/settings/tokens."
+    )]
+    InvalidToken,
+    /// The Github API token is missing scopes that the queries need.
+    #[error(
+        "Insufficient Github API token scopes. Modify your token to include the `public_repo` \
+         and `user` scopes at https://github.com/settings/tokens."
+    )]
+    InsufficientScopes,
+    /// The Github API token has characters that can't be sent in a request header, such as a
+    /// stray newline.
+    #[error(
+        "the Github API token contains {what}, so it can't be sent to Github: {redacted}. Check \
+         it for stray line breaks or other characters copied along with it."
+    )]
+    MalformedToken {
+        what: String,
+        /// The token with most of it masked, but the offending characters visible.
+        redacted: String,
+    },
+    /// An organization requires SAML single sign-on, and the Github API token hasn't been
+    /// authorized for it.
+    #[error(
+        "the Github API token isn't authorized for the organization's SAML single sign-on. \
+         Authorize it with \"Configure SSO\" at https://github.com/settings/tokens."
+    )]
+    SsoRequired,
+    /// A query had to be sent, but there is no Github API token to send it with.
+    #[error("a Github API token is required to query the Github API")]
+    MissingToken,
+    /// The Github API rate limit ran out.
+    #[error(
+        "Github API rate limit exhausted: {why}. The limit {}; try again then, or pass \
+         --wait-for-rate-limit to wait automatically.",
+        describe_reset(*.reset)
+    )]
+    RateLimited {
+        why: String,
+        /// When the limit resets, if Github said.
+        reset: Option<SystemTime>,
+    },
+    /// The Github API couldn't be reached, or the connection failed.
+    #[error("error communicating with the Github API: {0}")]
+    Network(#[from] reqwest::Error),
+    /// The Github API responded with an unexpected status.
+    #[error("Github API returned unexpected status: {0}")]
+    Status(StatusCode),
+    /// The Github API reported an error with a query.
+    #[error("Github API response contained error: {0}")]
+    Api(String),
+    /// The Github API's response didn't have the expected shape.
+    #[error("Malformed Github API response")]
+    MalformedResponse,
+    /// The Github API's response wasn't JSON, such as an error page from a proxy.
+    #[error(
+        "could not parse Github API response as JSON ({reason}); the response began {snippet:?}"
+    )]
+    UnparseableResponse { reason: String, snippet: String },
+    /// A funding link couldn't be understood.
+    #[error("invalid funding link {url:?}: {reason}")]
+    InvalidLink { url: String, reason: String },
+    /// A source couldn't be resolved, which is only an error when resolving strictly.
+    #[error("could not resolve funding links for {source_url}: {reason}")]
+    SourceFailed { source_url: String, reason: String },
+    /// A package's repository URL couldn't be understood.
+    #[error("invalid repository URL {url:?}: {reason}")]
+    InvalidRepository { url: String, reason: String },
+    /// The cache couldn't be written.
+    #[error("error writing cache {}", .path.display())]
+    Cache {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    /// A recorded Github API response couldn't be replayed, or a response couldn't be recorded.
+    #[error("error with fixture {}: {reason}", .path.display())]
+    Fixture { path: PathBuf, reason: String },
+    /// An error from a resolver outside this crate.
+    #[error(transparent)]
+    Other(Box<dyn std::error::Error + Send + Sync>),
+}
+
+impl Error {
+    /// A short, stable identifier for the kind of error, such as `invalid-token`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::InvalidToken => "invalid-token",
+            Error::InsufficientScopes => "insufficient-scopes",
+            Error::MalformedToken { .. } => "malformed-token",
+            Error::SsoRequired => "sso-required",
+            Error::MissingToken => "missing-token",
+            Error::RateLimited { .. } => "rate-limited",
+            Error::Network(_) => "network",
+            Error::Status(_) => "unexpected-status",
+            Error::Api(_) => "api-error",
+            Error::MalformedResponse => "malformed-response",
+            Error::UnparseableResponse { .. } => "unparseable-response",
+            Error::InvalidLink { .. } => "invalid-link",
+            Error::InvalidRepository { .. } => "invalid-repository",
+            Error::SourceFailed { .. } => "source-failed",
+            Error::Cache { .. } => "cache",
+            Error::Fixture { .. } => "fixture",
+            Error::Other(_) => "other",
+        }
+    }
+
+    /// Whether every other query would fail the same way, so that there is no point in carrying
+    /// on after this error.
+    pub fn is_fatal(&self) -> bool {
+        matches!(
+            self,
+            Error::InvalidToken
+                | Error::InsufficientScopes
+                | Error::MissingToken
+                | Error::MalformedToken { .. }
+        )
+    }
+}
+
+/// A description of when the rate limit resets, for messages.
+pub fn describe_reset(reset: Option<SystemTime>) -> String {
+    match reset.and_then(|reset| reset.duration_since(SystemTime::now()).ok()) {
+        Some(wait) => format!("resets in {} minutes", wait.as_secs() / 60 + 1),
+        None => "resets soon".to_string(),
+    }
+}
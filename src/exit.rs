@@ -0,0 +1,79 @@
+//! Exit statuses.
+//!
+//! The exit status tells scripts what kind of failure stopped `cargo fund`, and is a stable
+//! interface that must not change between versions:
+//!
+//! | Status | Meaning                                                          |
+//! |--------|------------------------------------------------------------------|
+//! | 0      | Success                                                          |
+//! | 1      | Any failure not listed below, including invalid arguments        |
+//! | 2      | The Github API token is missing, invalid, or lacks scopes        |
+//! | 3      | The Github API rate limit ran out                                |
+//! | 4      | `cargo metadata` failed, or its output couldn't be read          |
+//! | 5      | A policy failed, such as a maintainer without a decision         |
+//!
+//! New statuses may be added for new kinds of failure, but existing ones keep their meaning.
+
+use anyhow::Error;
+use std::fmt;
+use std::process::ExitCode;
+
+/// The kind of failure that stopped the program.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Status {
+    Failure = 1,
+    Auth = 2,
+    RateLimited = 3,
+    Metadata = 4,
+    Policy = 5,
+}
+
+impl From<Status> for ExitCode {
+    fn from(status: Status) -> Self {
+        ExitCode::from(status as u8)
+    }
+}
+
+/// An error marked with the status to exit with. It displays exactly like the error it wraps.
+#[derive(Debug)]
+struct Classified {
+    status: Status,
+    error: Error,
+}
+
+impl fmt::Display for Classified {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.error, f)
+    }
+}
+
+impl std::error::Error for Classified {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.error.source()
+    }
+}
+
+/// Mark an error with the status to exit with.
+pub fn with_status(status: Status, error: Error) -> Error {
+    Error::new(Classified { status, error })
+}
+
+/// The status to exit with because of `error`.
+pub fn status(error: &Error) -> Status {
+    for cause in error.chain() {
+        if let Some(classified) = cause.downcast_ref::<Classified>() {
+            return classified.status;
+        }
+        match cause.downcast_ref::<cargo_fund::Error>() {
+            Some(
+                cargo_fund::Error::InvalidToken
+                | cargo_fund::Error::InsufficientScopes
+                | cargo_fund::Error::MissingToken
+                | cargo_fund::Error::MalformedToken { .. },
+            ) => return Status::Auth,
+            Some(cargo_fund::Error::RateLimited { .. }) => return Status::RateLimited,
+            _ => (),
+        }
+    }
+    Status::Failure
+}
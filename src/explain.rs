@@ -0,0 +1,202 @@
+//! `cargo fund explain`: the long form of what each error code means and how to fix it, like
+//! `rustc --explain`. The codes are those of [`cargo_fund::Error::code`], which are also returned
+//! by `cargo fund serve`.
+
+/// Each error code, with a summary and the long-form explanation.
+const CODES: &[(&str, &str, &str)] = &[
+    (
+        "invalid-token",
+        "Github rejected the API token",
+        "Github answered with `401 Unauthorized`, so the token has expired, been revoked, or was \
+         copied incorrectly.\n\n\
+         Create a new token at https://github.com/settings/tokens with the `public_repo` and \
+         `user` scopes, and pass it with --github-api-token or CARGO_FUND_GITHUB_API_TOKEN, or \
+         keep it with `cargo fund login`. A token given on the command line or in the \
+         environment takes precedence over a `token-file` or one kept with `cargo fund login`, \
+         so check for a stale one there too.",
+    ),
+    (
+        "insufficient-scopes",
+        "The API token lacks scopes the queries need",
+        "Github accepted the token, but refused a query because the token doesn't have the scopes \
+         it needs. Looking up funding links reads repositories and Sponsors listings, which need \
+         the `public_repo` and `user` scopes.\n\n\
+         Edit the token at https://github.com/settings/tokens to add them.",
+    ),
+    (
+        "malformed-token",
+        "The API token has characters that can't be sent",
+        "The token contains characters that aren't allowed in an HTTP header, most often a line \
+         break or space copied along with it, or a `token-file` that ends in a blank line.\n\n\
+         The error shows the token with most of it masked and the offending characters visible. \
+         Copy the token again, or remove the stray characters from wherever it's kept.",
+    ),
+    (
+        "missing-token",
+        "No API token to send a query with",
+        "Looking up funding links queries the Github API, which needs a token, and none was \
+         given.\n\n\
+         Pass one with --github-api-token or CARGO_FUND_GITHUB_API_TOKEN, or keep one with \
+         `cargo fund login`. No token is needed with --offline, which only uses the cache, or \
+         with --replay, which answers queries from recorded responses.",
+    ),
+    (
+        "sso-required",
+        "An organization requires SAML single sign-on for the token",
+        "The repository belongs to an organization that enforces SAML single sign-on, and the \
+         token hasn't been authorized for it, so Github won't answer queries about its \
+         repositories with it.\n\n\
+         At https://github.com/settings/tokens, choose \"Configure SSO\" next to the token and \
+         authorize it for the organization. Only that organization's repositories are affected; \
+         without --strict, the rest of the results are still shown.",
+    ),
+    (
+        "rate-limited",
+        "The Github API rate limit ran out",
+        "Github limits how many points of queries a token can spend each hour, and how quickly it \
+         can send them. The limit ran out, and the error says when it resets.\n\n\
+         Pass --wait-for-rate-limit to wait for the reset and carry on, or run again later; the \
+         links already found are cached, so the next run queries only the rest. A smaller \
+         --batch-size or fewer --jobs make the secondary rate limit less likely. Without \
+         --strict, running out partway through isn't a failure: the links found so far are \
+         printed, with a warning.",
+    ),
+    (
+        "network",
+        "The Github API couldn't be reached",
+        "The connection to the Github API failed or timed out.\n\n\
+         Check the network connection. Behind a proxy, pass its URL with --proxy, and its \
+         certificate with --cacert if it intercepts TLS. A slow connection may need a longer \
+         --timeout. With --offline, only the cache is used, and nothing is sent.",
+    ),
+    (
+        "unexpected-status",
+        "The Github API answered with an unexpected status",
+        "Github answered with an HTTP status that `cargo fund` doesn't expect, most often a \
+         server error that lasted through every retry.\n\n\
+         Github may be having trouble: see https://www.githubstatus.com, and try again later. If \
+         a proxy sits in between, it may have answered instead.",
+    ),
+    (
+        "api-error",
+        "The Github API reported an error with a query",
+        "Github answered, but reported an error with the query itself. The error names the part \
+         of the query it's about, such as `_3.fundingLinks`; run with -vvv to log the queries \
+         sent, and find it there.\n\n\
+         If the error is about one repository or account, only that source fails, and without \
+         --strict the rest of the results are still shown. Please report errors that persist.",
+    ),
+    (
+        "malformed-response",
+        "The Github API's response didn't have the expected shape",
+        "Github's response was JSON, but lacked fields that `cargo fund` expects. The API may \
+         have changed, or a proxy may have answered instead.\n\n\
+         Try again, and please report the error if it persists, with the output of -vvv.",
+    ),
+    (
+        "unparseable-response",
+        "The Github API's response wasn't JSON",
+        "The response couldn't be parsed as JSON. The error shows how it began, which is usually \
+         an HTML error page from a proxy, captive portal, or firewall.\n\n\
+         Check --proxy, or whatever sits between this machine and api.github.com.",
+    ),
+    (
+        "invalid-link",
+        "A funding link couldn't be understood",
+        "A funding link, such as one in a repository's .github/FUNDING.yml, isn't a URL that can \
+         be opened. It's skipped with a warning, or fails the run with --strict.\n\n\
+         If you maintain the repository, fix the link in its FUNDING.yml. Otherwise, give the \
+         package's links in .cargo-fund.toml, under [package.NAME] with `links = [...]`.",
+    ),
+    (
+        "invalid-repository",
+        "A package's repository URL couldn't be understood",
+        "The `repository` field of a package's Cargo.toml isn't a URL that funding links can be \
+         looked up for, such as a Github URL without the repository's name.\n\n\
+         If you maintain the package, fix the field. Otherwise, give the package's links in \
+         .cargo-fund.toml, under [package.NAME] with `links = [...]`, or leave it out with \
+         `ignore`.",
+    ),
+    (
+        "source-failed",
+        "A repository or account couldn't be looked up",
+        "Looking up the funding links of one repository or account failed, and --strict, or \
+         `strict = true` in the configuration, makes that stop the run. The error gives the \
+         reason, which may have a code of its own.\n\n\
+         Pass --lenient to skip sources that fail with a warning, and still show the rest.",
+    ),
+    (
+        "cache",
+        "The cache couldn't be written",
+        "Funding links are cached between runs, and the cache file couldn't be written, most \
+         often because its directory is read-only or the disk is full.\n\n\
+         Pass --cache-dir, or set `cache-dir` in the configuration, to keep the cache elsewhere, \
+         or pass --no-cache to do without it. `cargo fund cache path` prints where it's kept.",
+    ),
+    (
+        "fixture",
+        "A recorded response couldn't be replayed or recorded",
+        "With --replay, each query is answered with the response recorded for the same query, \
+         and none was recorded for this one; or a fixture couldn't be read or written.\n\n\
+         Queries change when the dependencies do, so record the responses again with --record \
+         after changing them.",
+    ),
+    (
+        "other",
+        "An error from a resolver outside cargo fund",
+        "A resolver that isn't part of `cargo fund` failed, and the error is its own. See the \
+         documentation of the resolver.",
+    ),
+];
+
+/// The long-form explanation of the error `code`, such as `rate-limited`.
+pub fn explanation(code: &str) -> Option<&'static str> {
+    CODES
+        .iter()
+        .find(|(known, _, _)| *known == code)
+        .map(|(_, _, explanation)| *explanation)
+}
+
+/// Write every error code, with a summary of each.
+pub fn write_codes(out: &mut String) {
+    use std::fmt::Write;
+    let width = CODES
+        .iter()
+        .map(|(code, _, _)| code.len())
+        .max()
+        .unwrap_or(0);
+    for (code, summary, _) in CODES {
+        writeln!(out, "{:width$}  {}", code, summary, width = width).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cargo_fund::Error;
+
+    #[test]
+    fn explains_every_code() {
+        let errors = [
+            Error::InvalidToken,
+            Error::InsufficientScopes,
+            Error::MissingToken,
+            Error::SsoRequired,
+            Error::MalformedResponse,
+            Error::Api(String::new()),
+            Error::Status(http::StatusCode::BAD_GATEWAY),
+            Error::RateLimited {
+                why: String::new(),
+                reset: None,
+            },
+            Error::SourceFailed {
+                source_url: String::new(),
+                reason: String::new(),
+            },
+        ];
+        for error in errors {
+            assert!(explanation(error.code()).is_some(), "{}", error.code());
+        }
+        assert_eq!(explanation("no-such-code"), None);
+    }
+}
@@ -0,0 +1,96 @@
+//! Recording Github API responses to disk and replaying them later, for tests that run without a
+//! token or network access and for reproducing a user's run exactly.
+//!
+//! Each response is kept in a file of its own, named for a hash of the query that was sent, so a
+//! replayed run answers every query with the response recorded for the same query. Queries are
+//! built from the sources in sorted order, so the same dependencies make the same queries.
+
+use crate::error::Error;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Whether to record Github API responses to a directory, or replay them from one instead of
+/// querying Github.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Fixtures {
+    Record(PathBuf),
+    Replay(PathBuf),
+}
+
+/// A recorded response, with the query it answers so that fixtures can be read and edited by hand.
+#[derive(Serialize, Deserialize)]
+struct Fixture {
+    query: serde_json::Value,
+    response: serde_json::Value,
+}
+
+impl Fixtures {
+    /// The directory the fixtures are kept in.
+    pub fn dir(&self) -> &Path {
+        match self {
+            Fixtures::Record(dir) | Fixtures::Replay(dir) => dir,
+        }
+    }
+
+    /// Where the response to `query` is kept.
+    pub fn path(&self, query: &serde_json::Value) -> PathBuf {
+        let hash = crate::cache::fnv1a(query.to_string().as_bytes());
+        self.dir().join(format!("{:016x}.json", hash))
+    }
+
+    /// The response recorded for `query`.
+    pub(crate) fn replay(&self, query: &serde_json::Value) -> Result<serde_json::Value, Error> {
+        let path = self.path(query);
+        let error = |reason: String| Error::Fixture {
+            path: path.clone(),
+            reason,
+        };
+        let contents = std::fs::read_to_string(&path).map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => error("no response was recorded for this query".into()),
+            _ => error(e.to_string()),
+        })?;
+        let fixture: Fixture = serde_json::from_str(&contents).map_err(|e| error(e.to_string()))?;
+        Ok(fixture.response)
+    }
+
+    /// Record `response` as the response to `query`, replacing any recorded before.
+    pub(crate) fn record(
+        &self,
+        query: &serde_json::Value,
+        response: &serde_json::Value,
+    ) -> Result<(), Error> {
+        let path = self.path(query);
+        let error = |e: std::io::Error| Error::Fixture {
+            path: path.clone(),
+            reason: e.to_string(),
+        };
+        std::fs::create_dir_all(self.dir()).map_err(error)?;
+        let fixture = Fixture {
+            query: query.clone(),
+            response: response.clone(),
+        };
+        let contents = serde_json::to_string_pretty(&fixture).expect("fixtures serialize");
+        std::fs::write(&path, contents + "\n").map_err(error)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn records_and_replays() {
+        let dir = std::env::temp_dir().join(format!("cargo-fund-fixtures-{}", std::process::id()));
+        let query = serde_json::json!({ "query": "query FundingLinks { rateLimit { cost } }" });
+        let response = serde_json::json!({ "data": { "rateLimit": { "cost": 1 } } });
+        Fixtures::Record(dir.clone())
+            .record(&query, &response)
+            .unwrap();
+        let replayed = Fixtures::Replay(dir.clone()).replay(&query);
+        let other = serde_json::json!({ "query": "query FundingLinks { viewer { login } }" });
+        let missing = Fixtures::Replay(dir.clone()).replay(&other);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(replayed.unwrap(), response);
+        assert_eq!(missing.unwrap_err().code(), "fixture");
+    }
+}
@@ -0,0 +1,99 @@
+//! `cargo fund generate`: files written from the funding links found, meant to be committed and
+//! regenerated when dependencies change.
+//!
+//! The output depends only on the dependencies and their links, so regenerating it in CI only
+//! shows a change when something worth committing changed.
+
+use cargo_fund::report::{FundReport, FundingLink};
+use cargo_fund::LinkSource;
+use cargo_metadata::{Metadata, PackageId};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt::Write;
+
+/// The name of the acknowledgments file written by default.
+pub const ACKNOWLEDGMENTS: &str = "ACKNOWLEDGMENTS.md";
+
+/// The name of the file of funding links for other tools written by default.
+pub const INTEROP: &str = "funding.json";
+
+/// Write a Markdown file crediting each maintainer of the workspace's dependencies, with their
+/// funding links and the packages they maintain.
+pub fn write_acknowledgments(
+    out: &mut String,
+    metadata: &Metadata,
+    source_map: &HashMap<LinkSource, HashSet<PackageId>>,
+    report: &FundReport,
+) {
+    let mut groups = cargo_fund::group_by_owner(source_map, &report.allowed());
+    groups.sort_by_cached_key(|group| group.name());
+    writeln!(out, "# Acknowledgments\n").unwrap();
+    writeln!(
+        out,
+        "This project is built on the work of the maintainers of its dependencies. If it is useful \
+         to you, please consider supporting them.\n"
+    )
+    .unwrap();
+    for group in &groups {
+        let pkgs = group
+            .pkgs
+            .iter()
+            .map(|id| metadata[id].name.as_str())
+            .collect::<BTreeSet<_>>();
+        let pkgs = pkgs.into_iter().collect::<Vec<_>>();
+        let heading = match &group.heading {
+            Some(owner) => owner.clone(),
+            None => pkgs.join(", "),
+        };
+        writeln!(out, "## {}\n", heading).unwrap();
+        for link in &group.links {
+            writeln!(out, "- <{}>", link.url).unwrap();
+        }
+        if group.heading.is_some() {
+            let pkgs = pkgs
+                .iter()
+                .map(|pkg| format!("`{}`", pkg))
+                .collect::<Vec<_>>();
+            writeln!(out, "\nMaintainer of {}.", pkgs.join(", ")).unwrap();
+        }
+        writeln!(out).unwrap();
+    }
+    writeln!(out, "---\n").unwrap();
+    writeln!(out, "Generated by `cargo fund generate acknowledgments`.").unwrap();
+}
+
+/// The funding links of one version of a crate, for other tools.
+#[derive(Serialize)]
+struct InteropCrate<'a> {
+    /// The crate as cargo-deny names it, such as `serde@1.0.200`.
+    spec: String,
+    /// Where the crate comes from, as `cargo metadata` writes it, or nothing for a path
+    /// dependency.
+    source: Option<String>,
+    funding: &'a [FundingLink],
+}
+
+/// Write the funding links of each dependency as JSON keyed by crate name and then version, the
+/// way cargo-about's templates and cargo-deny's configuration name crates, so that license
+/// tooling can look up a crate's funding links without joining on anything.
+///
+/// In a cargo-about template, a crate's links are
+/// `(lookup (lookup @root.funding.crates crate.name) crate.version)`, given this file as `funding`.
+pub fn write_interop(out: &mut String, metadata: &Metadata, report: &FundReport) {
+    let report = report.allowed();
+    let mut crates: BTreeMap<&str, BTreeMap<&str, InteropCrate>> = BTreeMap::new();
+    for pkg in &report.packages {
+        let source = metadata[&pkg.id].source.as_ref();
+        crates.entry(&pkg.name).or_default().insert(
+            &pkg.version,
+            InteropCrate {
+                spec: format!("{}@{}", pkg.name, pkg.version),
+                source: source.map(|source| source.to_string()),
+                funding: &pkg.links,
+            },
+        );
+    }
+    let json = serde_json::json!({ "crates": crates });
+    *out += &serde_json::to_string_pretty(&json).expect("crates serialize");
+    out.push('\n');
+}
@@ -1,26 +1,25 @@
-use super::{globals, Link, LinkSource, Platform};
-use anyhow::{anyhow, bail, Error};
-use cargo_metadata::PackageId;
-use http::{StatusCode, Uri};
-use std::collections::{HashMap, HashSet};
+use super::{Context, Event, Events, Link, LinkSource, Outcome, Platform, Report, Resolver};
+use crate::error::{describe_reset, Error};
+use crate::fixtures::Fixtures;
+use crate::report::SponsorsGoal;
+use async_trait::async_trait;
+use http::{HeaderMap, StatusCode, Uri};
+use std::collections::{BTreeSet, HashMap};
 use std::convert::TryFrom;
 use std::fmt::Write;
+use std::time::Duration;
 use tracing::{debug, info, trace, warn};
+use web_time::SystemTime;
 
-const GITHUB_TOKEN_HELP: &str = "Invalid Github API token. \
-Create a token with the `public_repo` and `user` scopes at https://github.com/settings/tokens.";
-
-const GITHUB_TOKEN_SCOPES_HELP: &str = "Insufficient Github API token scopes. \
-Modify your token to include the `public_repo` and `user` scopes at https://github.com/settings/tokens.";
-
+/// A Github repository or account to look up funding links for.
 #[derive(Clone, Debug, Hash, Eq, PartialEq, Ord, PartialOrd)]
-pub(crate) enum GithubLinkSource {
+pub enum GithubLinkSource {
     Repo { owner: String, name: String },
     Owner { owner: String },
 }
 
 impl GithubLinkSource {
-    fn owner(&self) -> &str {
+    pub fn owner(&self) -> &str {
         match self {
             GithubLinkSource::Repo { owner, .. } => owner,
             GithubLinkSource::Owner { owner, .. } => owner,
@@ -28,6 +27,147 @@ impl GithubLinkSource {
     }
 }
 
+impl std::fmt::Display for GithubLinkSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GithubLinkSource::Repo { owner, name } => {
+                write!(f, "https://github.com/{}/{}", owner, name)
+            }
+            GithubLinkSource::Owner { owner } => write!(f, "https://github.com/{}", owner),
+        }
+    }
+}
+
+/// The account that a `GITHUB` link from a repository's funding links names, which Github gives
+/// as the account's profile, such as `https://github.com/someone`, or as its Sponsors listing.
+/// Links on other platforms, and links already known to be listings, name none.
+fn funding_login(link: &Link) -> Option<&str> {
+    if link.platform != Platform::Github || link.login.is_some() {
+        return None;
+    }
+    let login = link.uri.path().trim_matches('/').rsplit('/').next()?;
+    Some(login).filter(|login| !login.is_empty())
+}
+
+/// Add a query for the Sponsors listing of the account `login` to `query`, under `alias`.
+fn query_listing(query: &mut String, alias: &str, login: &str) {
+    writeln!(
+        query,
+        "
+{}: repositoryOwner(login: {:?}) {{
+  ... on Organization {{
+    sponsorsListing {{
+      url
+    }}
+  }}
+  ... on User {{
+    sponsorsListing {{
+      url
+    }}
+  }}
+}}
+",
+        alias, login
+    )
+    .unwrap();
+}
+
+/// The Sponsors listing of the account `login`, from the answer to a query made with
+/// [`query_listing`], if it has one. The listing's own URL is used, which is right for
+/// organizations and other hosts.
+fn parse_listing(
+    cx: &Context,
+    login: &str,
+    owner: &serde_json::Value,
+) -> Result<Option<Link>, Error> {
+    let listing = &owner["sponsorsListing"];
+    if listing.is_null() {
+        return Ok(None);
+    }
+    let Some(url) = listing["url"].as_str() else {
+        cx.malformed(Error::MalformedResponse)?;
+        return Ok(None);
+    };
+    match Link::try_from(("GITHUB", url)) {
+        Ok(link) => Ok(Some(Link {
+            login: Some(login.to_string()),
+            ..link
+        })),
+        Err(e) => {
+            cx.malformed(e)?;
+            Ok(None)
+        }
+    }
+}
+
+/// Look up the Sponsors listings of `logins` with a single GraphQL query, returning them with the
+/// state of the rate limit afterwards. Accounts that don't exist or have no listing are left out.
+async fn listings(
+    cx: &Context,
+    logins: &[&str],
+) -> Result<(HashMap<String, Link>, RateLimit), Error> {
+    let mut query = "query SponsorsListings {".to_string();
+    for (gensym, login) in logins.iter().enumerate() {
+        query_listing(&mut query, &format!("_{}", gensym), login);
+    }
+    query_rate_limit(&mut query);
+    let (res, reset) = send_query(cx, &serde_json::json!({ "query": query })).await?;
+    if res["errors"].is_array() {
+        // accounts that don't exist are errors, but shouldn't lose the rest
+        debug!("errors looking up Sponsors listings: {}", res["errors"]);
+    }
+    let mut listings = HashMap::new();
+    for (gensym, login) in logins.iter().enumerate() {
+        if let Some(link) = parse_listing(cx, login, &res["data"][format!("_{}", gensym)])? {
+            listings.insert(login.to_string(), link);
+        }
+    }
+    Ok((listings, parse_rate_limit(&res, reset)))
+}
+
+/// Ask for the state of the rate limit at the end of a query, closing it.
+fn query_rate_limit(query: &mut String) {
+    writeln!(
+        query,
+        "
+  rateLimit {{
+    cost
+    remaining
+  }}
+}}"
+    )
+    .unwrap();
+}
+
+/// The state of the rate limit in the answer to a query made with [`query_rate_limit`].
+fn parse_rate_limit(res: &serde_json::Value, reset: Option<SystemTime>) -> RateLimit {
+    let rate_limit = &res["data"]["rateLimit"];
+    let rate_limit = RateLimit {
+        cost: rate_limit["cost"].as_u64().unwrap_or(1),
+        remaining: rate_limit["remaining"].as_u64().unwrap_or(u64::MAX),
+        reset,
+    };
+    trace!("rate limit after query: {:?}", rate_limit);
+    rate_limit
+}
+
+/// A GraphQL error's message, with the part of the query it's about, such as `_3.fundingLinks`,
+/// so that it can be found in the query logged with `-vvv`.
+fn describe_api_error(message: &str, path: &serde_json::Value) -> String {
+    let path = path.as_array().map_or_else(Vec::new, |path| {
+        path.iter()
+            .map(|part| match part {
+                serde_json::Value::String(part) => part.clone(),
+                part => part.to_string(),
+            })
+            .collect()
+    });
+    match path.is_empty() {
+        true => message.to_string(),
+        false => format!("{} (at `{}` in the query)", message, path.join(".")),
+    }
+}
+
 pub(crate) fn try_get_sources(uri: Uri) -> Result<Vec<LinkSource>, Error> {
     let mut path_components = uri.path().split("/").skip(1).take(2);
     let owner = path_components.next();
@@ -44,33 +184,445 @@ pub(crate) fn try_get_sources(uri: Uri) -> Result<Vec<LinkSource>, Error> {
             }),
         ])
     } else {
-        bail!("not a full Github URI: {}", uri)
+        Err(Error::InvalidRepository {
+            url: uri.to_string(),
+            reason: "not a full Github URI".to_string(),
+        })
     }
 }
 
-pub(crate) async fn resolve_github_links(
-    source_map: &HashMap<LinkSource, HashSet<PackageId>>,
-    resolved: &mut HashMap<PackageId, HashSet<Link>>,
+/// The default number of repositories and owners to look up in each GraphQL query.
+///
+/// Github rejects queries that request too many nodes, so large dependency graphs have to be
+/// split across several queries.
+pub const DEFAULT_BATCH_SIZE: usize = 100;
+
+/// The number of queries sent at once by default.
+///
+/// Github discourages many concurrent requests with secondary rate limits, so this is modest.
+pub const DEFAULT_JOBS: usize = 4;
+
+/// The number of times to try sending a query before giving up.
+const MAX_ATTEMPTS: u32 = 5;
+
+/// The delay before the first retry of a failed query. Each further retry waits twice as long.
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+
+/// The longest `Retry-After` delay to sleep through before retrying. Longer ones are treated like
+/// an exhausted rate limit, and only waited for with `--wait-for-rate-limit`.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(120);
+
+/// The number of times to wait for an exhausted rate limit to reset before sending a query, after
+/// which Github is taken not to be resetting it when it says.
+const MAX_WAITS: u32 = 3;
+
+/// The state of the Github API rate limit after a query.
+#[derive(Clone, Copy, Debug)]
+struct RateLimit {
+    /// The number of points the query cost.
+    cost: u64,
+    /// The number of points left before the limit resets.
+    remaining: u64,
+    /// When the limit resets, if Github told us.
+    reset: Option<SystemTime>,
+}
+
+/// Wait for the rate limit to reset, or fail with an explanation if waiting isn't allowed.
+async fn wait_for_reset(cx: &Context, reset: Option<SystemTime>, why: &str) -> Result<(), Error> {
+    if !cx.wait_for_rate_limit {
+        return Err(Error::RateLimited {
+            why: why.to_string(),
+            reset,
+        });
+    }
+    if !cx.quiet {
+        warn!(
+            "Github API rate limit exhausted: {}. Waiting until the limit {}...",
+            why,
+            describe_reset(reset)
+        );
+    }
+    let wait = reset
+        .and_then(|reset| reset.duration_since(SystemTime::now()).ok())
+        .unwrap_or(Duration::from_secs(60));
+    // give Github a moment past the advertised reset time
+    crate::sleep(wait + Duration::from_secs(1)).await;
+    Ok(())
+}
+
+/// When the rate limit resets, according to the `x-ratelimit-reset` response header.
+fn rate_limit_reset(headers: &HeaderMap) -> Option<SystemTime> {
+    let secs = headers
+        .get("x-ratelimit-reset")?
+        .to_str()
+        .ok()?
+        .parse()
+        .ok()?;
+    Some(SystemTime::UNIX_EPOCH + Duration::from_secs(secs))
+}
+
+/// How long to wait before retrying, according to the `retry-after` response header. Github
+/// gives this as a number of seconds.
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let secs = headers
+        .get("retry-after")?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()?;
+    Some(Duration::from_secs(secs))
+}
+
+/// Looks up funding links with the Github GraphQL API: the `FUNDING.yml` links of repositories,
+/// and the sponsors listings of their owners.
+pub struct GithubResolver;
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl Resolver for GithubResolver {
+    fn name(&self) -> &str {
+        "github"
+    }
+
+    fn handles(&self, source: &LinkSource) -> bool {
+        matches!(source, LinkSource::Github(_))
+    }
+
+    async fn resolve(
+        &self,
+        cx: &Context,
+        sources: Vec<LinkSource>,
+        events: Events<'_>,
+        report: Report<'_>,
+    ) -> Result<(), Error> {
+        let mut sources = sources
+            .iter()
+            .filter_map(|source| match source {
+                LinkSource::Github(source) => Some(source),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        // keep batches deterministic from run to run
+        sources.sort();
+        let batches = sources.chunks(cx.batch_size.max(1)).collect::<Vec<_>>();
+        let num_batches = batches.len();
+        let mut rate_limit: Option<RateLimit> = None;
+        // when the rate limit ran out, and when it resets if Github said
+        let mut exhausted = None;
+        // send up to `--jobs` queries at once, checking the rate limit between each wave of them
+        for (wave_ix, wave) in batches.chunks(cx.jobs.max(1)).enumerate() {
+            let ix = wave_ix * cx.jobs.max(1);
+            if let (Some(limit), None) = (rate_limit, exhausted) {
+                match check_rate_limit(cx, limit, num_batches - ix, wave.len()).await {
+                    Ok(()) => (),
+                    Err(e) if cx.strict => return Err(e),
+                    Err(Error::RateLimited { reset, .. }) => exhausted = Some(reset),
+                    Err(e) => return Err(e),
+                }
+            }
+            if let Some(reset) = exhausted {
+                // none of the remaining queries can be sent, but what was found so far is kept
+                for &source in batches[ix..].iter().flat_map(|batch| batch.iter()) {
+                    report(
+                        &LinkSource::Github(source.clone()),
+                        Outcome::RateLimited(reset),
+                    );
+                }
+                break;
+            }
+            debug!(
+                "resolving Github batches {} to {} of {}",
+                ix + 1,
+                ix + wave.len(),
+                num_batches
+            );
+            let wave_sources = wave
+                .iter()
+                .map(|batch| {
+                    let sources = batch
+                        .iter()
+                        .map(|&source| LinkSource::Github(source.clone()));
+                    sources.collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>();
+            for sources in &wave_sources {
+                events(&Event::BatchSent { sources });
+            }
+            let results =
+                futures_util::future::join_all(wave.iter().map(|batch| resolve_batch(cx, batch)))
+                    .await;
+            let mut limits = vec![];
+            for ((batch, result), sources) in wave.iter().zip(results).zip(&wave_sources) {
+                events(&Event::BatchResolved { sources });
+                let mut outcomes = match result {
+                    Ok((outcomes, limit)) => {
+                        limits.push(limit);
+                        outcomes
+                    }
+                    Err(e) if cx.strict || e.is_fatal() => return Err(e),
+                    Err(Error::RateLimited { reset, .. }) => {
+                        exhausted = Some(reset);
+                        batch
+                            .iter()
+                            .map(|&source| (source, Outcome::RateLimited(reset)))
+                            .collect()
+                    }
+                    Err(e) => {
+                        warn!("Github query failed; skipping its sources: {:#}", e);
+                        let message = format!("{:#}", e);
+                        batch
+                            .iter()
+                            .map(|&source| (source, Outcome::Failed(message.clone())))
+                            .collect()
+                    }
+                };
+                for (&github, source) in batch.iter().zip(sources) {
+                    let outcome = outcomes.remove(github).unwrap_or(Outcome::NotFound);
+                    report(source, outcome);
+                }
+            }
+            // the queries ran concurrently, so the lowest remaining count is the latest
+            rate_limit = limits.into_iter().min_by_key(|limit| limit.remaining);
+        }
+
+        debug!("finished resolving Github links");
+
+        Ok(())
+    }
+}
+
+/// Check that the rate limit left after the last query leaves room for the next `wave` queries,
+/// waiting for it to reset or failing if not.
+///
+/// Unless `--wait-for-rate-limit` is given, this also fails early if the `remaining` queries
+/// can't all be sent before the limit resets, rather than partway through them.
+async fn check_rate_limit(
+    cx: &Context,
+    limit: RateLimit,
+    remaining: usize,
+    wave: usize,
 ) -> Result<(), Error> {
-    #[derive(Clone, Debug, Eq, PartialEq, Hash)]
-    enum Alias {
-        Repo(String),
-        Owner(String),
+    // assume the rest of the batches cost about as much as the last one
+    let cost = limit.cost.max(1);
+    let needed = cost * remaining as u64;
+    let wave_cost = cost * wave as u64;
+    if limit.remaining < wave_cost {
+        let why = format!(
+            "{} points remain, but the next queries need about {}",
+            limit.remaining, wave_cost
+        );
+        wait_for_reset(cx, limit.reset, &why).await?;
+    } else if limit.remaining < needed && !cx.wait_for_rate_limit {
+        let why = format!(
+            "{} points remain, but the remaining {} queries need about {}",
+            limit.remaining, remaining, needed
+        );
+        wait_for_reset(cx, limit.reset, &why).await?;
+    }
+    Ok(())
+}
+
+/// Check that `token` can be sent in a request header. Otherwise, building the request fails
+/// with nothing more than `invalid format`, which doesn't say that the token is to blame.
+fn check_token(token: &str) -> Result<(), Error> {
+    if http::HeaderValue::from_str(&format!("Bearer {}", token)).is_ok() {
+        return Ok(());
+    }
+    let bad = token
+        .chars()
+        .find(|&c| !(c == ' ' || c == '\t' || c.is_ascii_graphic()));
+    let what = match bad {
+        Some('\n' | '\r') => "a line break".to_string(),
+        Some(c) if c.is_control() => format!("a control character ({:?})", c),
+        Some(c) => format!("a non-ASCII character ({:?})", c),
+        None => "characters that aren't allowed in headers".to_string(),
+    };
+    // keep enough to recognize the token by, and anything that isn't a letter or digit
+    let redacted = token
+        .chars()
+        .enumerate()
+        .map(|(ix, c)| match c {
+            c if ix < 4 || !c.is_ascii_alphanumeric() => c,
+            _ => '*',
+        })
+        .collect::<String>();
+    Err(Error::MalformedToken {
+        what,
+        redacted: format!("{:?}", redacted),
+    })
+}
+
+/// Whether a request that failed to send is worth trying again.
+fn is_transient(e: &reqwest::Error) -> bool {
+    // browsers don't tell us whether the connection failed
+    #[cfg(not(target_arch = "wasm32"))]
+    if e.is_connect() {
+        return true;
+    }
+    e.is_timeout() || e.is_request()
+}
+
+/// Send a GraphQL query, retrying transient failures with exponential backoff.
+///
+/// Server errors, dropped connections, timeouts, and secondary rate limits are retried up to
+/// `MAX_ATTEMPTS` times in total; other failures are returned immediately. A secondary rate limit
+/// is retried after the delay given by its `Retry-After` header, if any.
+///
+/// If the primary rate limit is exhausted, this waits for it to reset when
+/// `--wait-for-rate-limit` is given, up to `MAX_WAITS` times, and fails otherwise. Along with the response, this returns
+/// when the rate limit resets.
+async fn send_query(
+    cx: &Context,
+    query: &serde_json::Value,
+) -> Result<(serde_json::Value, Option<SystemTime>), Error> {
+    if let Some(fixtures @ Fixtures::Replay(_)) = &cx.fixtures {
+        trace!("replaying Github GraphQL query response");
+        return Ok((fixtures.replay(query)?, None));
+    }
+    let mut attempt = 1;
+    let mut waits = 0;
+    loop {
+        let token = cx.github_api_token.clone().ok_or(Error::MissingToken)?;
+        check_token(&token)?;
+        let req = cx
+            .client
+            .post("https://api.github.com/graphql")
+            .bearer_auth(token)
+            .json(query);
+
+        trace!("sending Github GraphQL query");
+
+        let resp = match req.send().await {
+            Ok(resp) => resp,
+            Err(e) if attempt < MAX_ATTEMPTS && is_transient(&e) => {
+                backoff(attempt, &e).await;
+                attempt += 1;
+                continue;
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        trace!("received Github GraphQL query response");
+
+        match resp.status() {
+            StatusCode::OK => (),
+            StatusCode::UNAUTHORIZED => return Err(Error::InvalidToken),
+            status if status.is_server_error() && attempt < MAX_ATTEMPTS => {
+                backoff(attempt, &status).await;
+                attempt += 1;
+                continue;
+            }
+            status @ (StatusCode::FORBIDDEN | StatusCode::TOO_MANY_REQUESTS) => {
+                let reset = rate_limit_reset(resp.headers());
+                let exhausted = resp
+                    .headers()
+                    .get("x-ratelimit-remaining")
+                    .is_some_and(|remaining| remaining == "0");
+                if exhausted && waits == MAX_WAITS {
+                    return Err(Error::RateLimited {
+                        why: format!(
+                            "no points remain after waiting for the limit to reset {} times",
+                            MAX_WAITS
+                        ),
+                        reset,
+                    });
+                }
+                if exhausted {
+                    wait_for_reset(cx, reset, "no points remain").await?;
+                    waits += 1;
+                    continue;
+                }
+                let retry_after = retry_after(resp.headers());
+                let body = resp.text().await.unwrap_or_default();
+                // older responses call the secondary rate limit an abuse detection mechanism
+                let secondary = retry_after.is_some()
+                    || body.contains("secondary rate limit")
+                    || body.contains("abuse detection");
+                if attempt < MAX_ATTEMPTS && secondary {
+                    match retry_after {
+                        Some(wait) if wait > MAX_RETRY_AFTER => {
+                            let why = format!("Github asked to retry after {:?}", wait);
+                            wait_for_reset(cx, Some(SystemTime::now() + wait), &why).await?;
+                        }
+                        Some(wait) => {
+                            if !cx.quiet {
+                                warn!(
+                                    "Github API secondary rate limit hit. Retrying in {:?}...",
+                                    wait
+                                );
+                            }
+                            crate::sleep(wait).await;
+                        }
+                        None => backoff(attempt, &"secondary rate limit").await,
+                    }
+                    attempt += 1;
+                    continue;
+                }
+                return Err(Error::Status(status));
+            }
+            status => return Err(Error::Status(status)),
+        }
+
+        trace!("deserializing Github response JSON");
+
+        let reset = rate_limit_reset(resp.headers());
+        let body = match resp.text().await {
+            Ok(body) => body,
+            Err(e) if attempt < MAX_ATTEMPTS && (e.is_body() || e.is_timeout()) => {
+                backoff(attempt, &e).await;
+                attempt += 1;
+                continue;
+            }
+            Err(e) => return Err(e.into()),
+        };
+        return match serde_json::from_str(&body) {
+            Ok(res) => {
+                if let Some(fixtures @ Fixtures::Record(_)) = &cx.fixtures {
+                    fixtures.record(query, &res)?;
+                }
+                Ok((res, reset))
+            }
+            Err(e) => Err(Error::UnparseableResponse {
+                reason: e.to_string(),
+                snippet: body.chars().take(200).collect(),
+            }),
+        };
     }
+}
+
+/// Wait before retrying after a failed attempt, doubling the delay after each attempt.
+///
+/// The delay is jittered so that concurrent runs don't retry in lockstep.
+async fn backoff(attempt: u32, cause: &(dyn std::fmt::Display + Sync)) {
+    use std::hash::{BuildHasher, Hasher};
+    let base = INITIAL_BACKOFF * 2u32.pow(attempt - 1);
+    // a fresh `RandomState` is randomly seeded, which is all the randomness we need here
+    let random = std::collections::hash_map::RandomState::new()
+        .build_hasher()
+        .finish();
+    let delay = base / 2 + base.mul_f64((random % 1000) as f64 / 2000.0);
+    debug!(
+        "Github API request failed ({}); retrying in {:?} (attempt {} of {})",
+        cause,
+        delay,
+        attempt + 1,
+        MAX_ATTEMPTS
+    );
+    crate::sleep(delay).await;
+}
+
+/// Look up the funding links for one batch of sources with a single GraphQL query, returning
+/// what was found for each source and the state of the rate limit afterwards.
+async fn resolve_batch<'a>(
+    cx: &Context,
+    batch: &[&'a GithubLinkSource],
+) -> Result<(HashMap<&'a GithubLinkSource, Outcome>, RateLimit), Error> {
     let mut query_map = HashMap::new();
-    let mut gensym = 0usize;
     let mut query = "query FundingLinks {".to_string();
-    for (source, pkgs) in source_map {
+    for (gensym, &source) in batch.iter().enumerate() {
         let alias = format!("_{}", gensym);
-        gensym += 1;
-        // allow this pattern even though we have no other `LinkSource` variants yet
-        #[allow(irrefutable_let_patterns)]
-        let source = if let LinkSource::Github(source) = source {
-            source
-        } else {
-            continue;
-        };
-        match &source {
+        match source {
             GithubLinkSource::Repo { owner, name } => {
                 writeln!(
                     &mut query,
@@ -84,160 +636,451 @@ pub(crate) async fn resolve_github_links(
                     alias, owner, name,
                 )
                 .unwrap();
-                query_map.insert(Alias::Repo(alias), (source, pkgs));
+                query_map.insert(Alias::Repo(alias), source);
             }
             GithubLinkSource::Owner { owner } => {
-                writeln!(
-                    &mut query,
-                    "
-{}: repositoryOwner(login: {:?}) {{
-  ... on Organization {{
-    sponsorsListing {{
-      id
-    }}
-  }}
-  ... on User {{
-    sponsorsListing {{
-      id
-    }}
-  }}
-}}
-",
-                    alias, owner
-                )
-                .unwrap();
-                query_map.insert(Alias::Owner(alias), (source, pkgs));
+                query_listing(&mut query, &alias, owner);
+                query_map.insert(Alias::Owner(alias), source);
             }
         }
     }
-    writeln!(
-        &mut query,
-        "
-  rateLimit {{
-    cost
-    remaining
-  }}
-}}"
-    )
-    .unwrap();
+    query_rate_limit(&mut query);
 
     let query = serde_json::json!({ "query": query });
 
-    let req = globals()
-        .client
-        .post("https://api.github.com/graphql")
-        .bearer_auth(&globals().github_api_token)
-        .json(&query);
-
-    trace!("sending Github GraphQL query");
-
-    let resp = req.send().await?;
+    let (res, reset) = send_query(cx, &query).await?;
 
-    trace!("received Github GraphQL query response");
-
-    match resp.status() {
-        StatusCode::OK => (),
-        StatusCode::UNAUTHORIZED => bail!(GITHUB_TOKEN_HELP),
-        status => bail!("Github API returned unexpected status: {}", status),
-    }
-
-    trace!("deserializing Github response JSON");
+    trace!("deserialized Github response JSON");
 
-    let res: serde_json::Value = resp.json().await?;
+    let mut rate_limit = parse_rate_limit(&res, reset);
 
-    trace!("deserialized Github response JSON");
+    let mut outcomes = parse_errors(cx, &res, reset, &query_map)?;
 
-    if let serde_json::Value::Array(errors) = &res["errors"] {
-        for error in errors {
-            let message = error["message"]
-                .as_str()
-                .ok_or_else(|| anyhow!("Malformed Github API response"))?;
-            if let serde_json::Value::String(ty) = &error["type"] {
-                match ty.as_str() {
-                    "INSUFFICIENT_SCOPES" => bail!(GITHUB_TOKEN_SCOPES_HELP),
-                    "NOT_FOUND" => {
-                        info!("{}", message);
-                        continue;
-                    }
-                    _ => {
-                        eprintln!("{}", error);
-                        bail!("Github API response contained error: {}", message)
-                    }
-                }
-            } else {
-                bail!("Malformed Github API response");
-            }
+    for (alias, source) in query_map {
+        if outcomes.contains_key(source) {
+            continue;
         }
-    }
-
-    for (alias, (source, pkgs)) in query_map {
         trace!("processing {:?}, {:?}", alias, source);
         match alias {
             Alias::Repo(alias) => {
                 if let serde_json::Value::Array(links) = &res["data"][alias]["fundingLinks"] {
+                    let mut parsed = vec![];
                     for link in links {
                         trace!("processing {:?}", link);
-                        let platform = link["platform"]
-                            .as_str()
-                            .ok_or_else(|| anyhow!("Malformed Github API response"))?;
-                        let uri = link["url"]
-                            .as_str()
-                            .ok_or_else(|| anyhow!("Malformed Github API response"))?;
-                        let link = match Link::try_from((platform, uri)) {
-                            Ok(link) => link,
-                            Err(e) => {
-                                warn!(
-                                    platform = %platform,
-                                    uri = %uri,
-                                    "could not parse Github funding links; skipping: {}",
-                                    e
-                                );
-                                continue;
-                            }
+                        let (Some(platform), Some(uri)) =
+                            (link["platform"].as_str(), link["url"].as_str())
+                        else {
+                            cx.malformed(Error::MalformedResponse)?;
+                            continue;
                         };
-                        for pkg in pkgs.iter() {
-                            resolved
-                                .entry(pkg.clone())
-                                .or_insert_with(HashSet::new)
-                                .insert(link.clone());
+                        match Link::try_from((platform, uri)) {
+                            Ok(link) => parsed.push(link),
+                            Err(e) => cx.malformed(e)?,
                         }
                     }
+                    outcomes.insert(source, Outcome::Found(parsed));
                 } else {
                     // no result, probably indicates an invalid or private repo
-                    continue;
+                    outcomes.insert(source, Outcome::NotFound);
                 }
             }
             Alias::Owner(alias) => {
-                if let serde_json::Value::Null = res["data"][alias]["sponsorsListing"] {
-                    continue;
+                let owner = &res["data"][&alias];
+                let outcome = if owner.is_object() {
+                    let listing = parse_listing(cx, source.owner(), owner)?;
+                    Outcome::Found(listing.into_iter().collect())
                 } else {
-                    let uri: http::Uri =
-                        match format!("https://github.com/sponsors/{}", source.owner()).parse() {
-                            Ok(link) => link,
-                            Err(e) => {
-                                warn!(
-                                    owner = %source.owner(),
-                                    "could not create valid owner sponsor link; skipping: {}",
-                                    e
-                                );
-                                continue;
-                            }
-                        };
-                    for pkg in pkgs {
-                        resolved
-                            .entry(pkg.clone())
-                            .or_insert_with(HashSet::new)
-                            .insert(Link {
-                                platform: Platform::Github,
-                                uri: uri.clone(),
-                            });
+                    Outcome::NotFound
+                };
+                outcomes.insert(source, outcome);
+            }
+        }
+    }
+
+    // a `GITHUB` funding link names an account, so it's replaced with the account's Sponsors
+    // listing, if it has one
+    let found = || {
+        let links = outcomes.values().filter_map(|outcome| match outcome {
+            Outcome::Found(links) => Some(links),
+            _ => None,
+        });
+        links.flatten()
+    };
+    let logins = found()
+        .filter_map(funding_login)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+    if !logins.is_empty() {
+        // the listings take another query, which has to fit in the rate limit like the batches
+        check_rate_limit(cx, rate_limit, 1, 1).await?;
+        let (listings, limit) = listings(cx, &logins).await?;
+        // the batch cost both queries, and the rate limit is as the second left it
+        rate_limit = RateLimit {
+            cost: rate_limit.cost + limit.cost,
+            ..limit
+        };
+        for outcome in outcomes.values_mut() {
+            let Outcome::Found(links) = outcome else {
+                continue;
+            };
+            for link in links {
+                if let Some(listing) = funding_login(link).and_then(|login| listings.get(login)) {
+                    *link = listing.clone();
+                }
+            }
+        }
+    }
+
+    Ok((outcomes, rate_limit))
+}
+
+/// What an alias in a `FundingLinks` query looks up.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+enum Alias {
+    Repo(String),
+    Owner(String),
+}
+
+/// What the `errors` in the response to a `FundingLinks` query mean for the sources in
+/// `query_map`. Errors that spoil the whole query, such as a token without the needed scopes, are
+/// returned instead. An error without a message or type is malformed: it fails when strict, and
+/// otherwise fails only the source it's about, if any.
+fn parse_errors<'a>(
+    cx: &Context,
+    res: &serde_json::Value,
+    reset: Option<SystemTime>,
+    query_map: &HashMap<Alias, &'a GithubLinkSource>,
+) -> Result<HashMap<&'a GithubLinkSource, Outcome>, Error> {
+    let mut outcomes = HashMap::new();
+    let serde_json::Value::Array(errors) = &res["errors"] else {
+        return Ok(outcomes);
+    };
+    for error in errors {
+        // blame the source the error is about, if it says which
+        let source = error["path"][0].as_str().and_then(|alias| {
+            query_map
+                .get(&Alias::Repo(alias.to_string()))
+                .or_else(|| query_map.get(&Alias::Owner(alias.to_string())))
+        });
+        let (Some(message), Some(ty)) = (error["message"].as_str(), error["type"].as_str()) else {
+            cx.malformed(Error::MalformedResponse)?;
+            if let Some(&source) = source {
+                let reason = Error::MalformedResponse.to_string();
+                outcomes.insert(source, Outcome::Failed(reason));
+            }
+            continue;
+        };
+        match ty {
+            "INSUFFICIENT_SCOPES" => return Err(Error::InsufficientScopes),
+            "RATE_LIMITED" => {
+                return Err(Error::RateLimited {
+                    why: "Github rejected the query".to_string(),
+                    reset,
+                })
+            }
+            "NOT_FOUND" => info!("{}", message),
+            _ => {
+                let error = if ty == "FORBIDDEN" && message.contains("SAML") {
+                    Error::SsoRequired
+                } else {
+                    Error::Api(describe_api_error(message, &error["path"]))
+                };
+                match source {
+                    Some(&source) => {
+                        outcomes.insert(source, Outcome::Failed(error.to_string()));
                     }
+                    None => return Err(error),
                 }
             }
         }
     }
+    Ok(outcomes)
+}
 
-    debug!("finished resolving Github links");
+/// What the viewer, whose API token the queries are sent with, can learn about a maintainer's
+/// Github Sponsors listing.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Sponsorable {
+    /// The goal the maintainer has set, if any.
+    pub goal: Option<SponsorsGoal>,
+    /// Whether the viewer already sponsors the maintainer.
+    pub viewer_is_sponsoring: bool,
+    /// How many sponsors the maintainer has.
+    pub sponsors: Option<u64>,
+}
 
-    Ok(())
+/// Look up the Github Sponsors listing of each of `owners`, by login. Owners that don't exist
+/// are left out.
+pub async fn sponsorables(
+    cx: &Context,
+    owners: &[String],
+) -> Result<HashMap<String, Sponsorable>, Error> {
+    let mut sponsorables = HashMap::new();
+    for batch in owners.chunks(cx.batch_size.max(1)) {
+        let mut query = "query Sponsorables {".to_string();
+        for (gensym, owner) in batch.iter().enumerate() {
+            writeln!(
+                &mut query,
+                "
+_{}: repositoryOwner(login: {:?}) {{
+  ... on Organization {{
+    viewerIsSponsoring
+    sponsors {{
+      totalCount
+    }}
+    sponsorsListing {{
+      activeGoal {{
+        title
+        percentComplete
+      }}
+    }}
+  }}
+  ... on User {{
+    viewerIsSponsoring
+    sponsors {{
+      totalCount
+    }}
+    sponsorsListing {{
+      activeGoal {{
+        title
+        percentComplete
+      }}
+    }}
+  }}
+}}",
+                gensym, owner
+            )
+            .unwrap();
+        }
+        query.push_str("}\n");
+        let (res, _) = send_query(cx, &serde_json::json!({ "query": query })).await?;
+        if res["errors"].is_array() {
+            // an error about one owner shouldn't lose the rest
+            debug!("errors looking up Sponsors listings: {}", res["errors"]);
+        }
+        sponsorables.extend(parse_sponsorables(batch, &res));
+    }
+    Ok(sponsorables)
+}
+
+/// The listings in a response to a `Sponsorables` query about `owners`.
+fn parse_sponsorables<'a>(
+    owners: &'a [String],
+    res: &'a serde_json::Value,
+) -> impl Iterator<Item = (String, Sponsorable)> + 'a {
+    owners
+        .iter()
+        .enumerate()
+        .filter_map(move |(gensym, owner)| {
+            let data = &res["data"][format!("_{}", gensym)];
+            if !data.is_object() {
+                return None;
+            }
+            let goal = &data["sponsorsListing"]["activeGoal"];
+            let goal = match (goal["title"].as_str(), goal["percentComplete"].as_u64()) {
+                (Some(title), Some(percent_complete)) => Some(SponsorsGoal {
+                    title: title.to_string(),
+                    percent_complete,
+                }),
+                _ => None,
+            };
+            let sponsorable = Sponsorable {
+                goal,
+                viewer_is_sponsoring: data["viewerIsSponsoring"].as_bool().unwrap_or(false),
+                sponsors: data["sponsors"]["totalCount"].as_u64(),
+            };
+            Some((owner.clone(), sponsorable))
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn checks_token() {
+        assert!(check_token("ghp_abc123").is_ok());
+        match check_token("ghp_abc\n123") {
+            Err(Error::MalformedToken { what, redacted }) => {
+                assert_eq!(what, "a line break");
+                assert_eq!(redacted, r#""ghp_***\n***""#);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_retry_after() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers), None);
+        headers.insert("retry-after", "60".parse().unwrap());
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(60)));
+        headers.insert(
+            "retry-after",
+            "Wed, 21 Oct 2015 07:28:00 GMT".parse().unwrap(),
+        );
+        assert_eq!(retry_after(&headers), None);
+    }
+
+    #[test]
+    fn parses_sponsorables() {
+        let owners = ["dtolnay", "acfoltzer", "nobody"].map(str::to_string);
+        let res = serde_json::json!({ "data": {
+            "_0": {
+                "viewerIsSponsoring": true,
+                "sponsors": { "totalCount": 14 },
+                "sponsorsListing": { "activeGoal": {
+                    "title": "Reach 100 sponsors", "percentComplete": 20
+                } },
+            },
+            "_1": { "viewerIsSponsoring": false, "sponsorsListing": { "activeGoal": null } },
+            "_2": null,
+        } });
+        let sponsorables = parse_sponsorables(&owners, &res).collect::<Vec<_>>();
+        assert_eq!(
+            sponsorables,
+            [
+                (
+                    "dtolnay".to_string(),
+                    Sponsorable {
+                        goal: Some(SponsorsGoal {
+                            title: "Reach 100 sponsors".to_string(),
+                            percent_complete: 20
+                        }),
+                        viewer_is_sponsoring: true,
+                        sponsors: Some(14),
+                    }
+                ),
+                ("acfoltzer".to_string(), Sponsorable::default()),
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_funding_logins() {
+        let login = |platform: &str, url: &str| {
+            let link = Link::try_from((platform, url)).unwrap();
+            funding_login(&link).map(str::to_string)
+        };
+        for url in [
+            "https://github.com/someone",
+            "https://github.com/sponsors/someone",
+            "https://github.example.com/someone/",
+        ] {
+            assert_eq!(login("GITHUB", url).as_deref(), Some("someone"), "{}", url);
+        }
+        assert_eq!(login("GITHUB", "https://github.com/"), None);
+        assert_eq!(login("KO_FI", "https://ko-fi.com/someone"), None);
+    }
+
+    #[test]
+    fn parses_listings() {
+        let cx = crate::test::context(true);
+        let owner = serde_json::json!({ "sponsorsListing": {
+            "url": "https://github.com/sponsors/SomeOrg"
+        } });
+        let listing = parse_listing(&cx, "someorg", &owner).unwrap().unwrap();
+        assert_eq!(listing.platform, Platform::Github);
+        assert_eq!(listing.uri, "https://github.com/sponsors/SomeOrg");
+        assert_eq!(listing.login.as_deref(), Some("someorg"));
+        let owner = serde_json::json!({ "sponsorsListing": null });
+        assert_eq!(parse_listing(&cx, "someone", &owner).unwrap(), None);
+        let owner = serde_json::json!({ "sponsorsListing": {} });
+        assert!(parse_listing(&cx, "someone", &owner).is_err());
+    }
+
+    #[test]
+    fn fails_sources_with_malformed_errors() {
+        let source = GithubLinkSource::Repo {
+            owner: "o".to_string(),
+            name: "r".to_string(),
+        };
+        let query_map = HashMap::from([(Alias::Repo("_0".to_string()), &source)]);
+        let res = serde_json::json!({ "errors": [
+            { "message": "Something went wrong", "path": ["_0"] },
+            { "type": "INTERNAL" },
+        ] });
+        let outcomes = parse_errors(&crate::test::context(false), &res, None, &query_map).unwrap();
+        assert_eq!(outcomes.len(), 1);
+        assert!(matches!(
+            &outcomes[&source],
+            Outcome::Failed(reason) if *reason == Error::MalformedResponse.to_string()
+        ));
+        assert!(matches!(
+            parse_errors(&crate::test::context(true), &res, None, &query_map),
+            Err(Error::MalformedResponse)
+        ));
+    }
+
+    /// Resolve `batch` from fixtures in `dir`, answering the queries with `responses` in the order
+    /// they're asked.
+    async fn resolve_replayed<'a>(
+        dir: &std::path::Path,
+        batch: &[&'a GithubLinkSource],
+        responses: &[serde_json::Value],
+    ) -> Result<(HashMap<&'a GithubLinkSource, Outcome>, RateLimit), Error> {
+        std::fs::create_dir_all(dir).unwrap();
+        let mut cx = crate::test::context(false);
+        cx.fixtures = Some(Fixtures::Replay(dir.to_path_buf()));
+        let mut responses = responses.iter();
+        loop {
+            match resolve_batch(&cx, batch).await {
+                Err(Error::Fixture { path, .. }) => {
+                    let response = responses.next().expect("no more queries are answered");
+                    let fixture = serde_json::json!({ "query": null, "response": response });
+                    std::fs::write(path, fixture.to_string()).unwrap();
+                }
+                result => return result,
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn counts_listings_against_the_rate_limit() {
+        let source = GithubLinkSource::Repo {
+            owner: "o".to_string(),
+            name: "r".to_string(),
+        };
+        let funding_links = |remaining: u64| {
+            serde_json::json!({ "data": {
+                "_0": { "fundingLinks": [
+                    { "platform": "GITHUB", "url": "https://github.com/someone" },
+                ] },
+                "rateLimit": { "cost": 1, "remaining": remaining },
+            } })
+        };
+        let listings = serde_json::json!({ "data": {
+            "_0": { "sponsorsListing": { "url": "https://github.com/sponsors/someone" } },
+            "rateLimit": { "cost": 2, "remaining": 97 },
+        } });
+        let dir = std::env::temp_dir().join(format!("cargo-fund-listings-{}", std::process::id()));
+        let responses = [funding_links(99), listings];
+        let resolved = resolve_replayed(&dir.join("room"), &[&source], &responses).await;
+        // without room for the listings query, it isn't sent
+        let responses = [funding_links(0)];
+        let exhausted = resolve_replayed(&dir.join("exhausted"), &[&source], &responses).await;
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let (outcomes, limit) = resolved.unwrap();
+        assert_eq!((limit.cost, limit.remaining), (3, 97));
+        assert!(matches!(
+            &outcomes[&source],
+            Outcome::Found(links) if links[0].uri == "https://github.com/sponsors/someone"
+        ));
+        assert!(matches!(exhausted, Err(Error::RateLimited { .. })));
+    }
+
+    #[test]
+    fn describes_api_errors() {
+        let path = serde_json::json!(["_3", "fundingLinks", 0]);
+        assert_eq!(
+            describe_api_error("Something went wrong", &path),
+            "Something went wrong (at `_3.fundingLinks.0` in the query)"
+        );
+        assert_eq!(
+            describe_api_error("Something went wrong", &serde_json::Value::Null),
+            "Something went wrong"
+        );
+    }
 }
@@ -0,0 +1,60 @@
+//! How much a workspace relies on each of its dependencies.
+//!
+//! A package's impact is the sum, over the workspace's own packages, of how directly each one
+//! depends on it: a direct dependency counts 1, and one n steps away counts 1/n. A maintainer's
+//! impact is the sum over their packages, so one whose crates are used directly by every member of
+//! a workspace outranks one whose single crate is pulled in deep in the graph.
+
+use cargo_fund::Group;
+use cargo_metadata::{Metadata, PackageId};
+use std::collections::{HashMap, VecDeque};
+
+/// How many steps each package is from the nearest of `roots`, where their direct dependencies
+/// are one step away.
+pub fn depths<'a>(
+    metadata: &'a Metadata,
+    roots: &[&'a PackageId],
+) -> HashMap<&'a PackageId, usize> {
+    let mut depths = HashMap::new();
+    let Some(resolve) = &metadata.resolve else {
+        return depths;
+    };
+    let nodes = resolve
+        .nodes
+        .iter()
+        .map(|node| (&node.id, node))
+        .collect::<HashMap<_, _>>();
+    let mut queue = VecDeque::new();
+    for &root in roots {
+        depths.insert(root, 0);
+        queue.push_back(root);
+    }
+    while let Some(id) = queue.pop_front() {
+        let depth = depths[id];
+        for dep in nodes.get(id).map_or(&[][..], |node| &node.dependencies) {
+            if !depths.contains_key(dep) {
+                depths.insert(dep, depth + 1);
+                queue.push_back(dep);
+            }
+        }
+    }
+    depths
+}
+
+/// The impact of each dependency of the workspace.
+pub fn scores(metadata: &Metadata) -> HashMap<PackageId, f64> {
+    let mut scores = HashMap::new();
+    for member in &metadata.workspace_members {
+        for (id, depth) in depths(metadata, &[member]) {
+            if depth > 0 {
+                *scores.entry(id.clone()).or_insert(0.0) += 1.0 / depth as f64;
+            }
+        }
+    }
+    scores
+}
+
+/// The impact of the packages in `group`.
+pub fn group_score(scores: &HashMap<PackageId, f64>, group: &Group) -> f64 {
+    group.pkgs.iter().filter_map(|id| scores.get(id)).sum()
+}
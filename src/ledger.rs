@@ -0,0 +1,348 @@
+//! A ledger of donations to maintainers, recorded with `cargo fund record`.
+//!
+//! The ledger is a TOML file, by default in the user's data directory, such as
+//! `~/.local/share/cargo-fund/ledger.toml` on Linux. A team can instead point `--ledger`, or
+//! `ledger` in the configuration, at a file committed with the project:
+//!
+//! ```toml
+//! [[donation]]
+//! maintainer = "dtolnay"
+//! amount = "10.00"
+//! date = "2024-03-01"
+//! note = "monthly sponsorship"
+//! ```
+
+use anyhow::{Context, Error};
+use cargo_fund::Group;
+use cargo_metadata::Metadata;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use web_time::SystemTime;
+
+/// The default location of the ledger, if the platform has a data directory.
+pub fn default_path() -> Option<PathBuf> {
+    Some(
+        dirs::data_local_dir()?
+            .join("cargo-fund")
+            .join("ledger.toml"),
+    )
+}
+
+/// A calendar date, written like `2024-03-01`.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Date {
+    year: i64,
+    month: u32,
+    day: u32,
+}
+
+impl Date {
+    /// Today's date in UTC.
+    pub fn today() -> Date {
+        let secs = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        Date::from_days((secs / 86400) as i64)
+    }
+
+    /// The date `days` days after the Unix epoch.
+    pub fn from_days(days: i64) -> Date {
+        // Howard Hinnant's `civil_from_days`
+        let z = days + 719468;
+        let era = z.div_euclid(146097);
+        let doe = z.rem_euclid(146097);
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = yoe + era * 400 + i64::from(month <= 2);
+        Date { year, month, day }
+    }
+
+    /// The number of days between the Unix epoch and this date.
+    pub fn days(&self) -> i64 {
+        // Howard Hinnant's `days_from_civil`
+        let year = self.year - i64::from(self.month <= 2);
+        let era = year.div_euclid(400);
+        let yoe = year.rem_euclid(400);
+        let month = i64::from(self.month);
+        let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5
+            + i64::from(self.day)
+            - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        era * 146097 + doe - 719468
+    }
+}
+
+impl std::str::FromStr for Date {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid date {:?}; expected e.g. `2024-03-01`", s);
+        let mut parts = s.trim().splitn(3, '-');
+        let mut part = || parts.next().ok_or_else(invalid);
+        let (year, month, day) = (part()?, part()?, part()?);
+        let date = Date {
+            year: year.parse().map_err(|_| invalid())?,
+            month: month.parse().map_err(|_| invalid())?,
+            day: day.parse().map_err(|_| invalid())?,
+        };
+        // a date that doesn't exist, like February 30th, doesn't survive the round trip
+        if year.len() != 4 || Date::from_days(date.days()) != date {
+            return Err(invalid());
+        }
+        Ok(date)
+    }
+}
+
+impl TryFrom<String> for Date {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Date> for String {
+    fn from(date: Date) -> Self {
+        date.to_string()
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+/// An amount of money in hundredths, written like `10.00`, or as a whole number like `10`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(try_from = "AmountRepr", into = "String")]
+pub struct Amount(pub u64);
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum AmountRepr {
+    Text(String),
+    Whole(u64),
+}
+
+impl TryFrom<AmountRepr> for Amount {
+    type Error = String;
+
+    fn try_from(repr: AmountRepr) -> Result<Self, Self::Error> {
+        match repr {
+            AmountRepr::Text(s) => crate::args::parse_amount(&s).map(Amount),
+            AmountRepr::Whole(whole) => whole
+                .checked_mul(100)
+                .map(Amount)
+                .ok_or_else(|| format!("amount {} is too large", whole)),
+        }
+    }
+}
+
+impl From<Amount> for String {
+    fn from(amount: Amount) -> Self {
+        amount.to_string()
+    }
+}
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{:02}", self.0 / 100, self.0 % 100)
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Ledger {
+    #[serde(default, rename = "donation")]
+    pub donations: Vec<Donation>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Donation {
+    /// The Github account or funding link donated to.
+    pub maintainer: String,
+    pub amount: Amount,
+    pub date: Date,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+/// What has been given to one maintainer.
+#[derive(Debug, Eq, PartialEq, Serialize)]
+pub struct Summary {
+    pub total: Amount,
+    pub last: Date,
+}
+
+impl Ledger {
+    /// Load the ledger at `path`. A missing ledger is empty.
+    pub fn load(path: &Path) -> Result<Ledger, Error> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Ledger::default()),
+            Err(e) => {
+                return Err(Error::from(e).context(format!("error reading {}", path.display())))
+            }
+        };
+        toml::from_str(&text).with_context(|| format!("error parsing {}", path.display()))
+    }
+
+    /// Write the ledger to `path`, creating its directory if needed.
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, toml::to_string(self)?)
+            .with_context(|| format!("error writing {}", path.display()))
+    }
+
+    /// The total given to `maintainer`, and when they were last given anything, if ever.
+    pub fn summary(&self, maintainer: &str) -> Option<Summary> {
+        let donations = self
+            .donations
+            .iter()
+            .filter(|donation| donation.maintainer.eq_ignore_ascii_case(maintainer));
+        donations.fold(None, |summary, donation| {
+            Some(match summary {
+                None => Summary {
+                    total: donation.amount,
+                    last: donation.date,
+                },
+                Some(Summary { total, last }) => Summary {
+                    total: Amount(total.0 + donation.amount.0),
+                    last: last.max(donation.date),
+                },
+            })
+        })
+    }
+
+    /// What has been given to the maintainer of `group`, recorded under its heading or one of its
+    /// funding links.
+    pub fn group_summary(&self, group: &Group) -> Option<Summary> {
+        let keys = group
+            .heading
+            .iter()
+            .chain(group.links.iter().map(|link| &link.url));
+        keys.filter_map(|key| self.summary(key)).next()
+    }
+}
+
+/// A maintainer who hasn't been funded recently.
+#[derive(Debug, Serialize)]
+pub struct Reminder {
+    pub maintainer: String,
+    /// What has been given to them before, if anything.
+    pub funded: Option<Summary>,
+    pub links: Vec<String>,
+    pub packages: Vec<String>,
+}
+
+/// The maintainers in `groups` who haven't been funded in the `days` up to `today`.
+pub fn reminders(
+    metadata: &Metadata,
+    groups: &[Group],
+    ledger: &Ledger,
+    days: i64,
+    today: Date,
+) -> Vec<Reminder> {
+    let mut reminders = vec![];
+    for group in groups {
+        let funded = ledger.group_summary(group);
+        if let Some(summary) = &funded {
+            if today.days() - summary.last.days() < days {
+                continue;
+            }
+        }
+        let packages = group
+            .pkgs
+            .iter()
+            .map(|id| metadata[id].name.clone())
+            .collect::<Vec<_>>();
+        reminders.push(Reminder {
+            maintainer: group.heading.clone().unwrap_or_else(|| packages.join(", ")),
+            funded,
+            links: group.links.iter().map(|link| link.url.clone()).collect(),
+            packages,
+        });
+    }
+    reminders
+}
+
+/// Write each reminder with when the maintainer was last funded and their funding links.
+pub fn write_reminders(out: &mut String, reminders: &[Reminder]) {
+    use std::fmt::Write;
+    for reminder in reminders {
+        match &reminder.funded {
+            Some(summary) => writeln!(
+                out,
+                "{} (last funded on {}, {} in total)",
+                reminder.maintainer, summary.last, summary.total
+            ),
+            None => writeln!(out, "{} (never funded)", reminder.maintainer),
+        }
+        .unwrap();
+        for link in &reminder.links {
+            writeln!(out, "  {}", link).unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn dates() {
+        let date: Date = "2024-03-01".parse().unwrap();
+        assert_eq!(date.to_string(), "2024-03-01");
+        assert_eq!(Date::from_days(0).to_string(), "1970-01-01");
+        assert_eq!(
+            date.days() - "2024-02-28".parse::<Date>().unwrap().days(),
+            2
+        );
+        assert!("2023-02-29".parse::<Date>().is_err());
+        assert!("2024-3".parse::<Date>().is_err());
+        assert!("24-03-01".parse::<Date>().is_err());
+    }
+
+    #[test]
+    fn summarizes() {
+        let ledger: Ledger = toml::from_str(
+            r#"
+            [[donation]]
+            maintainer = "dtolnay"
+            amount = "10.00"
+            date = "2024-03-01"
+
+            [[donation]]
+            maintainer = "DTolnay"
+            amount = "5.5"
+            date = "2024-01-15"
+            note = "one-off"
+
+            [[donation]]
+            maintainer = "dtolnay"
+            amount = 1
+            date = "2023-12-01"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            ledger.summary("dtolnay"),
+            Some(Summary {
+                total: Amount(1650),
+                last: "2024-03-01".parse().unwrap(),
+            })
+        );
+        assert_eq!(ledger.summary("someone"), None);
+        let saved = toml::to_string(&ledger).unwrap();
+        assert!(saved.contains("amount = \"5.50\""));
+    }
+}
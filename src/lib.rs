@@ -0,0 +1,1119 @@
+//! Discover funding links for the dependencies of a Cargo workspace.
+//!
+//! This is the library behind `cargo fund`. Collect the sources of a workspace's dependencies with
+//! [`collect_sources`], resolve their funding links with [`resolve_links`], and group the results
+//! for display with [`invert_mapping`] or [`group_by_owner`].
+use crate::cache::Cache;
+pub use crate::error::Error;
+use async_trait::async_trait;
+use cargo_metadata::{Metadata, Package, PackageId};
+use futures_util::StreamExt;
+use report::{FundReport, FundingLink};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::convert::TryFrom;
+use std::time::Duration;
+use tracing::{trace, warn};
+use web_time::SystemTime;
+
+pub mod cache;
+pub mod error;
+pub mod fixtures;
+pub mod github;
+pub mod progress;
+pub mod report;
+pub mod repository;
+
+/// Everything needed to resolve funding links: credentials, an HTTP client, and options
+/// controlling how queries are made.
+pub struct Context {
+    /// The Github API token, which is only optional when running offline.
+    pub github_api_token: Option<String>,
+    pub client: reqwest::Client,
+    /// The number of repositories and owners to look up in each Github API query.
+    pub batch_size: usize,
+    /// The number of Github API queries to send, and of resolvers to run, at once.
+    pub jobs: usize,
+    /// If the Github API rate limit runs out, wait for it to reset rather than failing.
+    pub wait_for_rate_limit: bool,
+    /// Fail at the first malformed funding link or response, or failed source, rather than
+    /// skipping it with a warning and carrying on.
+    pub strict: bool,
+    /// Answer only from the cache, without querying the network.
+    pub offline: bool,
+    /// Don't log notices, such as while waiting for the rate limit.
+    pub quiet: bool,
+    /// Record the Github API's responses, or replay recorded responses instead of querying it.
+    pub fixtures: Option<fixtures::Fixtures>,
+    /// The resolvers to look up funding links with, such as those from [`default_resolvers`].
+    pub resolvers: Vec<Box<dyn Resolver>>,
+}
+
+/// Wait for `duration` on whichever timer the target supports.
+pub(crate) async fn sleep(duration: Duration) {
+    #[cfg(feature = "native")]
+    tokio::time::sleep(duration).await;
+    #[cfg(not(feature = "native"))]
+    futures_timer::Delay::new(duration).await;
+}
+
+/// Somewhere funding links can be looked up for a package.
+#[derive(Clone, Debug, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub enum LinkSource {
+    Github(github::GithubLinkSource),
+    /// A repository that no built-in resolver understands, by its URL.
+    Repository(String),
+}
+
+impl std::fmt::Display for LinkSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LinkSource::Github(source) => source.fmt(f),
+            LinkSource::Repository(url) => url.fmt(f),
+        }
+    }
+}
+
+impl LinkSource {
+    /// Whether this source is a single repository, as opposed to e.g. an owner.
+    pub fn is_repo(&self) -> bool {
+        match self {
+            LinkSource::Github(source) => {
+                matches!(source, github::GithubLinkSource::Repo { .. })
+            }
+            LinkSource::Repository(_) => true,
+        }
+    }
+
+    /// Where links found in this source come from.
+    pub fn provenance(&self) -> Provenance {
+        match self {
+            LinkSource::Github(github::GithubLinkSource::Repo { .. }) => Provenance::Repository,
+            LinkSource::Github(github::GithubLinkSource::Owner { .. }) => Provenance::Owner,
+            LinkSource::Repository(_) => Provenance::Repository,
+        }
+    }
+
+    /// The account that owns this source, if known.
+    pub fn owner(&self) -> Option<&str> {
+        match self {
+            LinkSource::Github(source) => Some(source.owner()),
+            LinkSource::Repository(_) => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub enum Platform {
+    CommunityBridge,
+    Custom,
+    Github,
+    IssueHunt,
+    Kofi,
+    Liberapay,
+    OpenCollective,
+    Otechie,
+    Patreon,
+    Tidelift,
+    Other(String),
+}
+
+impl Platform {
+    /// The platform's name as it appears in Github's `FundingPlatform` enum.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::CommunityBridge => "COMMUNITY_BRIDGE",
+            Self::Custom => "CUSTOM",
+            Self::Github => "GITHUB",
+            Self::IssueHunt => "ISSUEHUNT",
+            Self::Kofi => "KO_FI",
+            Self::Liberapay => "LIBERAPAY",
+            Self::OpenCollective => "OPEN_COLLECTIVE",
+            Self::Otechie => "OTECHIE",
+            Self::Patreon => "PATREON",
+            Self::Tidelift => "TIDELIFT",
+            Self::Other(platform) => platform,
+        }
+    }
+}
+
+impl From<&str> for Platform {
+    fn from(platform: &str) -> Self {
+        match platform.to_ascii_uppercase().as_str() {
+            "COMMUNITY_BRIDGE" => Self::CommunityBridge,
+            "CUSTOM" => Self::Custom,
+            "GITHUB" => Self::Github,
+            "ISSUEHUNT" => Self::IssueHunt,
+            "KO_FI" => Self::Kofi,
+            "LIBERAPAY" => Self::Liberapay,
+            "OPEN_COLLECTIVE" => Self::OpenCollective,
+            "OTECHIE" => Self::Otechie,
+            "PATREON" => Self::Patreon,
+            "TIDELIFT" => Self::Tidelift,
+            _ => Self::Other(platform.to_string()),
+        }
+    }
+}
+
+/// Where a funding link was found.
+#[derive(Clone, Copy, Debug, Hash, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Provenance {
+    /// The funding links of a repository, i.e., its `.github/FUNDING.yml`.
+    Repository,
+    /// The sponsors listing of a repository's owner.
+    Owner,
+    /// Added by the project's own configuration.
+    Configuration,
+}
+
+impl std::fmt::Display for Provenance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Provenance::Repository => write!(f, "repository FUNDING.yml"),
+            Provenance::Owner => write!(f, "owner sponsors listing"),
+            Provenance::Configuration => write!(f, "configuration"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Hash, Eq, PartialEq)]
+pub struct Link {
+    pub platform: Platform,
+    pub uri: http::Uri,
+    /// The login of the maintainer whose Github Sponsors listing this is, if it is one.
+    pub login: Option<String>,
+}
+
+impl Link {
+    /// The link with its URI in a canonical form, for telling whether links written differently
+    /// lead to the same page: the scheme is `https`, the host is lowercase without a `www.`
+    /// prefix, and the path has no trailing slash. Paths on the known platforms, which name
+    /// accounts case-insensitively, are lowercased too; custom links may be case-sensitive.
+    ///
+    /// This is only a key to compare links by. Links are shown as they were written, since not
+    /// every site serves `https` or answers without its `www.`.
+    pub fn canonical(&self) -> Link {
+        let Some(host) = self.uri.host() else {
+            return self.clone();
+        };
+        let host = host.to_ascii_lowercase();
+        let host = host.strip_prefix("www.").unwrap_or(&host);
+        let port = self
+            .uri
+            .port()
+            .map_or_else(String::new, |port| format!(":{}", port));
+        let path = self.uri.path().trim_end_matches('/');
+        let path = match self.platform {
+            Platform::Custom | Platform::Other(_) => path.to_string(),
+            _ => path.to_lowercase(),
+        };
+        let query = self
+            .uri
+            .query()
+            .map_or_else(String::new, |query| format!("?{}", query));
+        match format!("https://{}{}{}{}", host, port, path, query).parse() {
+            Ok(uri) => Link {
+                platform: self.platform.clone(),
+                uri,
+                login: self.login.clone(),
+            },
+            Err(_) => self.clone(),
+        }
+    }
+}
+
+impl Ord for Link {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.platform
+            .cmp(&other.platform)
+            .then_with(|| self.uri.to_string().cmp(&other.uri.to_string()))
+            .then_with(|| self.login.cmp(&other.login))
+    }
+}
+
+impl PartialOrd for Link {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl TryFrom<(&str, &str)> for Link {
+    type Error = Error;
+
+    fn try_from((platform, url): (&str, &str)) -> Result<Self, Self::Error> {
+        let invalid = |reason: &dyn std::fmt::Display| Error::InvalidLink {
+            url: url.to_string(),
+            reason: reason.to_string(),
+        };
+        let platform = platform.into();
+        let uri: http::Uri = if url.starts_with("http") {
+            url.parse().map_err(|e| invalid(&e))?
+        } else {
+            // Try https if there's no scheme
+            format!("https://{}", url)
+                .parse()
+                .map_err(|e| invalid(&e))?
+        };
+        Ok(Link {
+            platform,
+            uri,
+            login: None,
+        })
+    }
+}
+
+/// Try to get sources for a single package.
+fn try_get_sources(package: &Package) -> Result<Vec<LinkSource>, Error> {
+    let uri: http::Uri = if let Some(repo) = package.repository.as_ref() {
+        repository::normalize(repo)
+            .parse()
+            .map_err(|e: http::uri::InvalidUri| Error::InvalidRepository {
+                url: repo.clone(),
+                reason: e.to_string(),
+            })?
+    } else {
+        return Ok(vec![]);
+    };
+    match uri.host().map(|host| host.to_ascii_lowercase()).as_deref() {
+        Some("github.com") | Some("www.github.com") => github::try_get_sources(uri),
+        // leave these to any custom resolvers
+        _ => Ok(vec![LinkSource::Repository(uri.to_string())]),
+    }
+}
+
+impl Context {
+    /// Deal with malformed data from a resolver, such as a funding link that can't be parsed or a
+    /// field missing from a response. When strict, it is an error; otherwise it is logged and
+    /// the data is skipped, so that every resolver treats malformed data the same way.
+    pub fn malformed(&self, error: Error) -> Result<(), Error> {
+        if self.strict {
+            return Err(error);
+        }
+        warn!("skipping malformed funding data: {}", error);
+        Ok(())
+    }
+}
+
+/// Get the sources for all dependencies in the workspace. A repository that can't be understood is
+/// malformed data: it fails when strict, and is otherwise skipped so that its package is counted
+/// as having no repository.
+pub fn collect_sources(
+    cx: &Context,
+    metadata: &Metadata,
+) -> Result<HashMap<LinkSource, HashSet<PackageId>>, Error> {
+    let mut source_map = HashMap::new();
+    for pkg in &metadata.packages {
+        if metadata.workspace_members.contains(&pkg.id) {
+            // skip packages within our own workspace
+            continue;
+        }
+        let sources = match try_get_sources(pkg) {
+            Ok(sources) => sources,
+            Err(e) => {
+                cx.malformed(e)?;
+                continue;
+            }
+        };
+        for source in sources {
+            source_map
+                .entry(source)
+                .or_insert_with(HashSet::new)
+                .insert(pkg.id.clone());
+        }
+    }
+    Ok(source_map)
+}
+
+/// What a resolver found for a single source.
+#[derive(Clone, Debug)]
+pub enum Outcome {
+    /// The source doesn't exist, or can't be seen with the credentials we have.
+    NotFound,
+    /// The source exists and has these funding links, possibly none.
+    Found(Vec<Link>),
+    /// The source couldn't be resolved because of this error.
+    Failed(String),
+    /// The source wasn't queried because the rate limit ran out. It resets at the given time, if
+    /// known.
+    RateLimited(Option<SystemTime>),
+}
+
+/// The results of resolving funding links for a set of sources.
+#[derive(Debug, Default)]
+pub struct Resolved {
+    /// The funding links for each package that has any.
+    pub links: HashMap<PackageId, HashSet<Link>>,
+    /// The sources that were successfully queried, whether or not they had funding links.
+    pub queried: HashSet<LinkSource>,
+    /// The sources that could not be queried, such as uncached sources when running offline.
+    pub unknown: HashSet<LinkSource>,
+    /// The sources whose queries failed, along with why.
+    pub failed: BTreeMap<LinkSource, String>,
+    /// Where each link was found.
+    pub provenance: HashMap<Link, BTreeSet<Provenance>>,
+    /// Every package with sources to resolve.
+    pub packages: HashSet<PackageId>,
+    /// The packages with sources that couldn't be resolved, because their queries failed, they
+    /// weren't cached while offline, or no resolver handles them.
+    pub incomplete: HashSet<PackageId>,
+    /// The packages with sources that weren't queried because the rate limit ran out.
+    pub rate_limited: HashSet<PackageId>,
+    /// When the rate limit resets, if it ran out and the resolver knows.
+    pub rate_limit_reset: Option<SystemTime>,
+    /// The first link found with each canonical form, which stands for the links found later that
+    /// lead to the same page.
+    written: HashMap<Link, Link>,
+}
+
+impl Resolved {
+    /// Record that `link` was found for `pkgs` in a source with the given provenance.
+    fn insert<'a>(
+        &mut self,
+        pkgs: impl IntoIterator<Item = &'a PackageId>,
+        link: Link,
+        provenance: Provenance,
+    ) {
+        let link = self.written.entry(link.canonical()).or_insert(link).clone();
+        for pkg in pkgs {
+            self.links
+                .entry(pkg.clone())
+                .or_default()
+                .insert(link.clone());
+        }
+        self.provenance.entry(link).or_default().insert(provenance);
+    }
+
+    /// Record what was found for a source shared by `pkgs`.
+    fn apply(&mut self, source: &LinkSource, pkgs: &HashSet<PackageId>, outcome: Outcome) {
+        match outcome {
+            Outcome::NotFound => (),
+            Outcome::Found(links) => {
+                self.queried.insert(source.clone());
+                for link in links {
+                    self.insert(pkgs, link, source.provenance());
+                }
+            }
+            Outcome::Failed(error) => {
+                self.failed.insert(source.clone(), error);
+            }
+            Outcome::RateLimited(reset) => {
+                self.rate_limited.extend(pkgs.iter().cloned());
+                self.incomplete.extend(pkgs.iter().cloned());
+                self.rate_limit_reset = self.rate_limit_reset.max(reset);
+            }
+        }
+    }
+}
+
+/// Something that happened while resolving funding links, such as for showing progress or
+/// printing links as soon as they are found.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Event<'a> {
+    /// Resolution started for the sources of `packages` packages, `repos` of which are
+    /// repositories.
+    SourcesCollected {
+        sources: usize,
+        repos: usize,
+        packages: usize,
+    },
+    /// A resolver started looking up `sources` sources that weren't cached.
+    ResolverStarted { resolver: &'a str, sources: usize },
+    /// A resolver finished looking up its sources.
+    ResolverFinished { resolver: &'a str },
+    /// A resolver sent a query for `sources`.
+    BatchSent { sources: &'a [LinkSource] },
+    /// A resolver's query for `sources` finished, whether or not it succeeded.
+    BatchResolved { sources: &'a [LinkSource] },
+    /// Funding links were found for a source, either in the cache or by a query, given the
+    /// packages sharing it.
+    LinksFound {
+        source: &'a LinkSource,
+        pkgs: &'a HashSet<PackageId>,
+        links: &'a [Link],
+    },
+    /// Nothing is left to do for a source, whatever the outcome.
+    SourceResolved { source: &'a LinkSource },
+    /// Every source has been resolved.
+    Finished,
+}
+
+/// A callback for the [`Event`]s of resolving funding links, as they happen.
+pub type Events<'a> = &'a (dyn Fn(&Event<'_>) + Sync);
+
+/// A callback for a resolver to report what it found for each source, as soon as it is found.
+pub type Report<'a> = &'a mut (dyn FnMut(&LinkSource, Outcome) + Send);
+
+/// A way of looking up funding links for some kinds of sources.
+///
+/// Resolvers are tried in the order they appear in [`Context::resolvers`], and each source is
+/// resolved by the first one that handles it. Add a resolver ahead of the built-in ones to
+/// support sources they don't, or to replace them. Resolvers with sources to look up run at the
+/// same time, up to [`Context::jobs`] of them.
+// futures in browsers can't be sent between threads
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+pub trait Resolver: Send + Sync {
+    /// A short name for the resolver, such as in [`Event::ResolverStarted`].
+    fn name(&self) -> &str {
+        "custom"
+    }
+
+    /// Whether this resolver can look up `source`.
+    fn handles(&self, source: &LinkSource) -> bool;
+
+    /// Look up the funding links for `sources`, all of which this resolver handles, passing the
+    /// outcome for each to `report`. Queries should be announced to `events` as they are sent
+    /// and finish, but the outcomes are announced by the caller.
+    ///
+    /// Sources that are never reported are treated as not found. An error stops resolution
+    /// entirely, so failures that only affect some sources should be reported as
+    /// [`Outcome::Failed`] instead; when [`Context::strict`] is set, the first of them stops
+    /// resolution anyway. Malformed data should be passed to [`Context::malformed`]. Errors of
+    /// the resolver's own can be returned as [`Error::Other`].
+    async fn resolve(
+        &self,
+        cx: &Context,
+        sources: Vec<LinkSource>,
+        events: Events<'_>,
+        report: Report<'_>,
+    ) -> Result<(), Error>;
+}
+
+/// The resolvers that `cargo fund` uses out of the box.
+pub fn default_resolvers() -> Vec<Box<dyn Resolver>> {
+    vec![Box::new(github::GithubResolver)]
+}
+
+/// Turn the sources into a mapping between packages and sets of funding links.
+///
+/// Cached results are used if they are no older than `ttl`, and new results are added to the
+/// cache. Progress is announced to `events` along the way. Resolvers run concurrently, up to
+/// [`Context::jobs`] at once.
+pub async fn resolve_links(
+    cx: &Context,
+    source_map: &HashMap<LinkSource, HashSet<PackageId>>,
+    cache: &mut Cache,
+    ttl: Duration,
+    events: Events<'_>,
+) -> Result<Resolved, Error> {
+    events(&Event::SourcesCollected {
+        sources: source_map.len(),
+        repos: source_map.keys().filter(|source| source.is_repo()).count(),
+        packages: source_map.values().flatten().collect::<HashSet<_>>().len(),
+    });
+    let mut resolved = Resolved::default();
+    let mut pending = vec![vec![]; cx.resolvers.len()];
+    for (source, pkgs) in source_map {
+        if let Some(outcome) = cache.get(&source.to_string(), ttl) {
+            trace!("using cached result for {}", source);
+            if let Outcome::Found(links) = &outcome {
+                events(&Event::LinksFound {
+                    source,
+                    pkgs,
+                    links,
+                });
+            }
+            resolved.apply(source, pkgs, outcome);
+        } else if let Some(ix) = cx.resolvers.iter().position(|r| r.handles(source)) {
+            if cx.offline {
+                resolved.unknown.insert(source.clone());
+            } else {
+                pending[ix].push(source.clone());
+                continue;
+            }
+        } else {
+            resolved.incomplete.extend(pkgs.iter().cloned());
+        }
+        // either way, there is nothing left to do for this source
+        events(&Event::SourceResolved { source });
+    }
+    // independent resolvers run at the same time
+    let mut runs = vec![];
+    for (resolver, sources) in cx.resolvers.iter().zip(pending) {
+        if !sources.is_empty() {
+            runs.push(run_resolver(cx, &**resolver, sources, source_map, events));
+        }
+    }
+    let runs = futures_util::stream::iter(runs)
+        .buffered(cx.jobs.max(1))
+        .collect::<Vec<_>>()
+        .await;
+    // merge in the order of the resolvers, so that the same failure is reported every time
+    for (outcomes, result) in runs {
+        let mut first_failure = None;
+        for (source, outcome) in outcomes {
+            let reason = match &outcome {
+                Outcome::Failed(reason) => Some(reason.clone()),
+                Outcome::RateLimited(_) => Some("the rate limit ran out".to_string()),
+                _ => None,
+            };
+            if let Some(reason) = reason {
+                first_failure.get_or_insert_with(|| Error::SourceFailed {
+                    source_url: source.to_string(),
+                    reason,
+                });
+            }
+            cache.insert(&source.to_string(), &outcome);
+            resolved.apply(&source, &source_map[&source], outcome);
+        }
+        result?;
+        if let Some(error) = first_failure.filter(|_| cx.strict) {
+            return Err(error);
+        }
+    }
+    for (source, pkgs) in source_map {
+        resolved.packages.extend(pkgs.iter().cloned());
+        if resolved.unknown.contains(source) || resolved.failed.contains_key(source) {
+            resolved.incomplete.extend(pkgs.iter().cloned());
+        }
+    }
+    events(&Event::Finished);
+    Ok(resolved)
+}
+
+/// Run `resolver` on `sources`, returning what it found for each along with whether it failed
+/// outright. Found links are announced to `events` as soon as they are reported.
+async fn run_resolver(
+    cx: &Context,
+    resolver: &dyn Resolver,
+    sources: Vec<LinkSource>,
+    source_map: &HashMap<LinkSource, HashSet<PackageId>>,
+    events: Events<'_>,
+) -> (Vec<(LinkSource, Outcome)>, Result<(), Error>) {
+    let mut outcomes = vec![];
+    let mut report = |source: &LinkSource, outcome: Outcome| {
+        if let Outcome::Found(links) = &outcome {
+            events(&Event::LinksFound {
+                source,
+                pkgs: &source_map[source],
+                links,
+            });
+        }
+        events(&Event::SourceResolved { source });
+        outcomes.push((source.clone(), outcome));
+    };
+    let name = resolver.name();
+    events(&Event::ResolverStarted {
+        resolver: name,
+        sources: sources.len(),
+    });
+    let result = resolver.resolve(cx, sources, events, &mut report).await;
+    events(&Event::ResolverFinished { resolver: name });
+    (outcomes, result)
+}
+
+/// A group of packages displayed together in the output, along with their funding links.
+#[derive(Debug)]
+pub struct Group {
+    /// A line shown above the links, such as the repository owner when grouping by owner.
+    pub heading: Option<String>,
+    pub links: BTreeSet<FundingLink>,
+    pub pkgs: BTreeSet<PackageId>,
+}
+
+impl Group {
+    /// The name used when sorting groups: the heading if any, otherwise the first link.
+    pub fn name(&self) -> String {
+        match (&self.heading, self.links.iter().next()) {
+            (Some(heading), _) => heading.to_ascii_lowercase(),
+            (None, Some(link)) => link.url.to_ascii_lowercase(),
+            (None, None) => String::new(),
+        }
+    }
+}
+
+/// Invert the mapping between packages and sets of funding links.
+///
+/// This allows us to group the output by unique sets of funding links.
+pub fn invert_mapping(report: &FundReport) -> Vec<Group> {
+    let mut inverted = BTreeMap::new();
+    for pkg in &report.packages {
+        let links: BTreeSet<FundingLink> = pkg.links.iter().cloned().collect();
+        inverted
+            .entry(links)
+            .or_insert_with(BTreeSet::new)
+            .insert(pkg.id.clone());
+    }
+    inverted
+        .into_iter()
+        .map(|(links, pkgs)| Group {
+            heading: None,
+            links,
+            pkgs,
+        })
+        .collect()
+}
+
+/// Group packages by the owner of their repository, taking the union of the owners' links.
+///
+/// Github logins are case-insensitive, so owners are compared without regard to case. Packages
+/// whose owner is unknown fall back to being grouped by their set of funding links.
+pub fn group_by_owner(
+    source_map: &HashMap<LinkSource, HashSet<PackageId>>,
+    report: &FundReport,
+) -> Vec<Group> {
+    let mut owners = HashMap::new();
+    for (source, pkgs) in source_map {
+        if let Some(owner) = source.owner() {
+            for pkg in pkgs {
+                owners.insert(pkg, owner);
+            }
+        }
+    }
+    let mut by_owner = BTreeMap::new();
+    let mut unowned = vec![];
+    for pkg in &report.packages {
+        if let Some(owner) = owners.get(&pkg.id) {
+            let group = by_owner
+                .entry(owner.to_ascii_lowercase())
+                .or_insert_with(|| Group {
+                    heading: Some(owner.to_string()),
+                    links: BTreeSet::new(),
+                    pkgs: BTreeSet::new(),
+                });
+            group.links.extend(pkg.links.iter().cloned());
+            group.pkgs.insert(pkg.id.clone());
+        } else {
+            unowned.push(pkg.clone());
+        }
+    }
+    let unowned = FundReport {
+        packages: unowned,
+        ..report.clone()
+    };
+    by_owner
+        .into_values()
+        .chain(invert_mapping(&unowned))
+        .collect()
+}
+
+/// Group the packages of repositories that were successfully queried but had no funding links.
+pub fn missing_groups(
+    source_map: &HashMap<LinkSource, HashSet<PackageId>>,
+    resolved: &Resolved,
+) -> Vec<Group> {
+    let missing = resolved.queried.iter().filter(|source| {
+        !source_map[*source]
+            .iter()
+            .any(|pkg| resolved.links.contains_key(pkg))
+    });
+    repo_groups(source_map, missing)
+}
+
+/// Group packages under the repositories among `sources`, with one group per repository.
+pub fn repo_groups<'a>(
+    source_map: &HashMap<LinkSource, HashSet<PackageId>>,
+    sources: impl IntoIterator<Item = &'a LinkSource>,
+) -> Vec<Group> {
+    let mut repos = BTreeMap::new();
+    for source in sources {
+        if source.is_repo() {
+            repos.insert(
+                source.to_string(),
+                source_map[source].iter().cloned().collect(),
+            );
+        }
+    }
+    repos
+        .into_iter()
+        .map(|(heading, pkgs)| Group {
+            heading: Some(heading),
+            links: BTreeSet::new(),
+            pkgs,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// A context for resolving without network access.
+    pub(crate) fn context(strict: bool) -> Context {
+        Context {
+            github_api_token: None,
+            client: reqwest::Client::new(),
+            batch_size: 1,
+            jobs: 1,
+            wait_for_rate_limit: false,
+            strict,
+            offline: true,
+            quiet: true,
+            fixtures: None,
+            resolvers: vec![],
+        }
+    }
+
+    /// Metadata for a workspace whose one member depends on `packages`, given as name, version,
+    /// and repository.
+    pub(crate) fn metadata(packages: &[(&str, &str, Option<&str>)]) -> Metadata {
+        let package = |name: &str, version: &str, repository: Option<&str>| {
+            serde_json::json!({
+                "name": name,
+                "version": version,
+                "id": format!("{} {}", name, version),
+                "repository": repository,
+                "dependencies": [],
+                "targets": [],
+                "features": {},
+                "manifest_path": format!("/{}/Cargo.toml", name),
+            })
+        };
+        let mut all = vec![package("ws", "0.1.0", None)];
+        all.extend(
+            packages
+                .iter()
+                .map(|(name, version, repository)| package(name, version, *repository)),
+        );
+        serde_json::from_value(serde_json::json!({
+            "packages": all,
+            "workspace_members": ["ws 0.1.0"],
+            "resolve": null,
+            "workspace_root": "/ws",
+            "target_directory": "/ws/target",
+            "version": 1,
+        }))
+        .unwrap()
+    }
+
+    /// A resolver for repositories that no built-in resolver understands, answering with the
+    /// outcome given for each by URL, and counting the sources it's asked about. Stubs sharing
+    /// `running` count how many of them are resolving at once, and the most that ever were.
+    struct Stub {
+        outcomes: HashMap<String, Outcome>,
+        asked: Arc<AtomicUsize>,
+        running: Arc<(AtomicUsize, AtomicUsize)>,
+    }
+
+    #[async_trait]
+    impl Resolver for Stub {
+        fn handles(&self, source: &LinkSource) -> bool {
+            matches!(source, LinkSource::Repository(_))
+                && self.outcomes.contains_key(&source.to_string())
+        }
+
+        async fn resolve(
+            &self,
+            _: &Context,
+            sources: Vec<LinkSource>,
+            _: Events<'_>,
+            report: Report<'_>,
+        ) -> Result<(), Error> {
+            self.asked.fetch_add(sources.len(), Ordering::SeqCst);
+            let running = self.running.0.fetch_add(1, Ordering::SeqCst) + 1;
+            self.running.1.fetch_max(running, Ordering::SeqCst);
+            // give any other resolvers a chance to start
+            tokio::task::yield_now().await;
+            for source in sources {
+                report(&source, self.outcomes[&source.to_string()].clone());
+            }
+            self.running.0.fetch_sub(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    /// A context that resolves repositories with a stub answering with `outcomes`, by URL, and
+    /// the count of sources the stub is asked about.
+    fn stub_context(strict: bool, outcomes: &[(&str, Outcome)]) -> (Context, Arc<AtomicUsize>) {
+        let outcomes = outcomes
+            .iter()
+            .map(|(url, outcome)| (url.to_string(), outcome.clone()))
+            .collect();
+        let asked = Arc::new(AtomicUsize::new(0));
+        let stub = Stub {
+            outcomes,
+            asked: asked.clone(),
+            running: Arc::default(),
+        };
+        let cx = Context {
+            offline: false,
+            resolvers: vec![Box::new(stub)],
+            ..context(strict)
+        };
+        (cx, asked)
+    }
+
+    fn link(url: &str) -> Link {
+        Link::try_from(("CUSTOM", url)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn resolves_from_the_cache_and_offline() {
+        let metadata = metadata(&[
+            ("cached", "1.0.0", Some("https://gitlab.com/o/cached")),
+            ("uncached", "1.0.0", Some("https://gitlab.com/o/uncached")),
+        ]);
+        let found = Outcome::Found(vec![link("https://a")]);
+        let (mut cx, asked) =
+            stub_context(false, &[("https://gitlab.com/o/uncached", found.clone())]);
+        let source_map = collect_sources(&cx, &metadata).unwrap();
+        let mut cache = Cache::default();
+        cache.insert("https://gitlab.com/o/cached", &found);
+        let ttl = Duration::from_secs(60);
+
+        cx.offline = true;
+        let resolved = resolve_links(&cx, &source_map, &mut cache, ttl, &|_| ())
+            .await
+            .unwrap();
+        assert_eq!(asked.load(Ordering::SeqCst), 0);
+        let cached = PackageId {
+            repr: "cached 1.0.0".to_string(),
+        };
+        let uncached = PackageId {
+            repr: "uncached 1.0.0".to_string(),
+        };
+        assert_eq!(resolved.links.keys().collect::<Vec<_>>(), [&cached]);
+        assert_eq!(resolved.unknown.len(), 1);
+        assert_eq!(resolved.incomplete, HashSet::from([uncached.clone()]));
+
+        cx.offline = false;
+        let resolved = resolve_links(&cx, &source_map, &mut cache, ttl, &|_| ())
+            .await
+            .unwrap();
+        // only the source that wasn't cached is looked up, and its outcome is cached after
+        assert_eq!(asked.load(Ordering::SeqCst), 1);
+        assert_eq!(resolved.links.len(), 2);
+        assert!(resolved.incomplete.is_empty());
+        assert!(cache.get("https://gitlab.com/o/uncached", ttl).is_some());
+    }
+
+    #[tokio::test]
+    async fn resolves_failures() {
+        let metadata = metadata(&[
+            ("found", "1.0.0", Some("https://gitlab.com/o/found")),
+            ("failed", "1.0.0", Some("https://gitlab.com/o/failed")),
+            ("limited", "1.0.0", Some("https://gitlab.com/o/limited")),
+        ]);
+        let reset = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let outcomes = [
+            (
+                "https://gitlab.com/o/found",
+                Outcome::Found(vec![link("https://a")]),
+            ),
+            (
+                "https://gitlab.com/o/failed",
+                Outcome::Failed("no such group".to_string()),
+            ),
+            (
+                "https://gitlab.com/o/limited",
+                Outcome::RateLimited(Some(reset)),
+            ),
+        ];
+        let (cx, _) = stub_context(false, &outcomes);
+        let source_map = collect_sources(&cx, &metadata).unwrap();
+        let ttl = Duration::from_secs(60);
+        let mut cache = Cache::default();
+        let resolved = resolve_links(&cx, &source_map, &mut cache, ttl, &|_| ())
+            .await
+            .unwrap();
+        let id = |repr: &str| PackageId {
+            repr: repr.to_string(),
+        };
+        assert_eq!(resolved.links.len(), 1);
+        assert_eq!(resolved.failed.len(), 1);
+        assert_eq!(
+            resolved.incomplete,
+            HashSet::from([id("failed 1.0.0"), id("limited 1.0.0")])
+        );
+        assert_eq!(resolved.rate_limited, HashSet::from([id("limited 1.0.0")]));
+        assert_eq!(resolved.rate_limit_reset, Some(reset));
+        // failures are looked up again next time
+        assert!(cache.get("https://gitlab.com/o/found", ttl).is_some());
+        assert!(cache.get("https://gitlab.com/o/failed", ttl).is_none());
+        assert!(cache.get("https://gitlab.com/o/limited", ttl).is_none());
+
+        let (cx, _) = stub_context(true, &outcomes);
+        let error = resolve_links(&cx, &source_map, &mut Cache::default(), ttl, &|_| ()).await;
+        assert!(matches!(error, Err(Error::SourceFailed { .. })));
+    }
+
+    #[tokio::test]
+    async fn runs_resolvers_concurrently() {
+        let metadata = metadata(&[
+            ("a", "1.0.0", Some("https://gitlab.com/o/a")),
+            ("b", "1.0.0", Some("https://codeberg.org/o/b")),
+        ]);
+        let running = Arc::<(AtomicUsize, AtomicUsize)>::default();
+        let stub = |url: &str| {
+            let outcomes = HashMap::from([(url.to_string(), Outcome::Found(vec![link(url)]))]);
+            Box::new(Stub {
+                outcomes,
+                asked: Arc::default(),
+                running: running.clone(),
+            }) as Box<dyn Resolver>
+        };
+        let cx = Context {
+            offline: false,
+            jobs: 2,
+            resolvers: vec![
+                stub("https://gitlab.com/o/a"),
+                stub("https://codeberg.org/o/b"),
+            ],
+            ..context(false)
+        };
+        let source_map = collect_sources(&cx, &metadata).unwrap();
+        let ttl = Duration::from_secs(60);
+        let resolved = resolve_links(&cx, &source_map, &mut Cache::default(), ttl, &|_| ())
+            .await
+            .unwrap();
+        assert_eq!(resolved.links.len(), 2);
+        assert_eq!(running.1.load(Ordering::SeqCst), 2);
+
+        // with one job, each resolver waits for the one before it
+        running.1.store(0, Ordering::SeqCst);
+        let cx = Context { jobs: 1, ..cx };
+        resolve_links(&cx, &source_map, &mut Cache::default(), ttl, &|_| ())
+            .await
+            .unwrap();
+        assert_eq!(running.1.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn reports_partly_rate_limited_batches() {
+        let metadata = metadata(&[
+            ("a", "1.0.0", Some("https://gitlab.com/o/a")),
+            ("b", "1.0.0", Some("https://gitlab.com/o/b")),
+            ("c", "1.0.0", Some("https://gitlab.com/o/c")),
+            ("d", "1.0.0", Some("https://gitlab.com/o/d")),
+        ]);
+        // the rate limit ran out after the first half of the batch was looked up
+        let reset = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let outcomes = [
+            (
+                "https://gitlab.com/o/a",
+                Outcome::Found(vec![link("https://a")]),
+            ),
+            ("https://gitlab.com/o/b", Outcome::Found(vec![])),
+            ("https://gitlab.com/o/c", Outcome::RateLimited(Some(reset))),
+            ("https://gitlab.com/o/d", Outcome::RateLimited(Some(reset))),
+        ];
+        let (cx, asked) = stub_context(false, &outcomes);
+        let source_map = collect_sources(&cx, &metadata).unwrap();
+        let ttl = Duration::from_secs(60);
+        let resolved = resolve_links(&cx, &source_map, &mut Cache::default(), ttl, &|_| ())
+            .await
+            .unwrap();
+        assert_eq!(asked.load(Ordering::SeqCst), 4);
+        let report = FundReport::new(&metadata, &resolved);
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["packages"].as_array().unwrap().len(), 1);
+        assert_eq!(json["skipped"]["no_links"], 1);
+        assert_eq!(json["skipped"]["rate_limited"], 2);
+        let unresolved = json["unresolved"].as_array().unwrap();
+        let names = unresolved
+            .iter()
+            .map(|pkg| &pkg["name"])
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["c", "d"]);
+        assert!(unresolved.iter().all(|pkg| pkg["reason"] == "rate-limited"));
+        assert_eq!(json["rate_limit_reset"], 1_700_000_000);
+    }
+
+    #[tokio::test]
+    async fn reports_round_trip() {
+        let metadata = metadata(&[
+            ("found", "1.0.0", Some("https://gitlab.com/o/found")),
+            ("no-repo", "1.0.0", None),
+            ("failed", "1.0.0", Some("https://gitlab.com/o/failed")),
+        ]);
+        let outcomes = [
+            (
+                "https://gitlab.com/o/found",
+                Outcome::Found(vec![link("https://a")]),
+            ),
+            (
+                "https://gitlab.com/o/failed",
+                Outcome::Failed("no such group".to_string()),
+            ),
+        ];
+        let (cx, _) = stub_context(false, &outcomes);
+        let source_map = collect_sources(&cx, &metadata).unwrap();
+        let ttl = Duration::from_secs(60);
+        let resolved = resolve_links(&cx, &source_map, &mut Cache::default(), ttl, &|_| ())
+            .await
+            .unwrap();
+        let report = FundReport::new(&metadata, &resolved);
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["dependencies"], 3);
+        assert_eq!(json["packages"][0]["links"][0]["url"], "https://a/");
+        assert_eq!(
+            json["packages"][0]["links"][0]["provenance"][0],
+            "repository"
+        );
+        assert_eq!(json["skipped"]["no_repository"], 1);
+        assert_eq!(json["skipped"]["not_looked_up"], 1);
+        assert_eq!(json["unresolved"][0]["name"], "failed");
+        assert_eq!(json["unresolved"][0]["reason"], "not-looked-up");
+        assert_eq!(serde_json::from_value::<FundReport>(json).unwrap(), report);
+    }
+
+    #[test]
+    fn skips_invalid_repositories() {
+        let metadata = metadata(&[
+            ("valid", "1.0.0", Some("https://github.com/o/valid")),
+            ("invalid", "1.0.0", Some("https://github.com/o")),
+        ]);
+        let sources = collect_sources(&context(false), &metadata).unwrap();
+        let pkgs = sources.values().flatten().collect::<HashSet<_>>();
+        assert_eq!(pkgs.len(), 1);
+        assert_eq!(pkgs.iter().next().unwrap().repr, "valid 1.0.0");
+        assert!(matches!(
+            collect_sources(&context(true), &metadata),
+            Err(Error::InvalidRepository { .. })
+        ));
+    }
+
+    #[test]
+    fn equivalent_links_are_shown_as_written() {
+        let pkg = PackageId {
+            repr: "pkg".to_string(),
+        };
+        let mut resolved = Resolved::default();
+        for url in [
+            "https://www.Example.com/donate/",
+            "http://example.com/donate",
+        ] {
+            let link = Link::try_from(("CUSTOM", url)).unwrap();
+            resolved.insert([&pkg], link, Provenance::Repository);
+        }
+        let links = &resolved.links[&pkg];
+        assert_eq!(links.len(), 1);
+        let link = links.iter().next().unwrap();
+        assert_eq!(link.uri.to_string(), "https://www.Example.com/donate/");
+    }
+
+    #[test]
+    fn links_differing_by_login_are_ordered_apart() {
+        let link = Link::try_from(("GITHUB", "https://github.com/sponsors/someone")).unwrap();
+        let listing = Link {
+            login: Some("someone".to_string()),
+            ..link.clone()
+        };
+        assert_ne!(link.cmp(&listing), std::cmp::Ordering::Equal);
+        assert_eq!(BTreeSet::from([link, listing]).len(), 2);
+    }
+
+    #[test]
+    fn canonical_links() {
+        let link = |url: &str| Link::try_from(("PATREON", url)).unwrap().canonical();
+        let canonical = link("https://patreon.com/x");
+        assert_eq!(canonical.uri.to_string(), "https://patreon.com/x");
+        for url in [
+            "https://www.patreon.com/x/",
+            "http://patreon.com/x",
+            "patreon.com/X",
+            "https://Patreon.com/x//",
+        ] {
+            assert_eq!(link(url), canonical, "canonicalizing {:?}", url);
+        }
+        let custom = Link::try_from(("CUSTOM", "https://www.Example.com/Donate/?id=AbC")).unwrap();
+        assert_eq!(
+            custom.canonical().uri.to_string(),
+            "https://example.com/Donate?id=AbC"
+        );
+    }
+}
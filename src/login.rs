@@ -0,0 +1,57 @@
+//! `cargo fund login`: keeping a Github API token on this machine, so that it needn't be given to
+//! every run.
+//!
+//! The token is kept in a file of its own in the user's configuration directory, readable only by
+//! the user on Unix. A token given with `--github-api-token`, its environment variable, or a
+//! configured `token-file` takes precedence over it.
+
+use anyhow::{Context, Error};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Where `cargo fund login` keeps the token, such as `~/.config/cargo-fund/token` on Linux.
+pub fn default_path() -> Option<PathBuf> {
+    Some(dirs::config_dir()?.join("cargo-fund").join("token"))
+}
+
+/// The token kept at `path`, if there is one.
+pub fn load(path: &Path) -> Result<Option<String>, Error> {
+    match std::fs::read_to_string(path) {
+        Ok(token) => Ok(Some(token.trim().to_string()).filter(|token| !token.is_empty())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(Error::from(e).context(format!("error reading {}", path.display()))),
+    }
+}
+
+/// Keep `token` at `path`, replacing any token kept there before.
+pub fn save(path: &Path, token: &str) -> Result<(), Error> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("error creating {}", dir.display()))?;
+    }
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options
+        .open(path)
+        .with_context(|| format!("error writing {}", path.display()))?;
+    writeln!(file, "{}", token).with_context(|| format!("error writing {}", path.display()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn saves_and_loads() {
+        let dir = std::env::temp_dir().join(format!("cargo-fund-login-{}", std::process::id()));
+        let path = dir.join("token");
+        assert_eq!(load(&path).unwrap(), None);
+        save(&path, "ghp_first").unwrap();
+        save(&path, "ghp_second").unwrap();
+        let loaded = load(&path).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(loaded.as_deref(), Some("ghp_second"));
+    }
+}
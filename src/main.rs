@@ -4,14 +4,14 @@
 //!
 //! ```text
 //! % CARGO_FUND_GITHUB_API_TOKEN=... cargo fund
-//! $HOME/cargo-fund (found funding links for 16 out of 138 dependencies)
-//! ├─┬─ https://www.buymeacoffee.com/dannyguo
+//! $HOME/cargo-fund (found funding links for 16 out of 138 dependencies; 31 have no repository, 91 have no funding links)
+//! ├─┬─ https://www.buymeacoffee.com/dannyguo (1 package)
 //! │ ├─ https://www.paypal.me/DannyGuo
 //! │ └─ https://ko-fi.com/dannyguo
 //! │    └─ strsim 0.8.0
-//! ├─── https://github.com/sponsors/XAMPPRocky
+//! ├─── https://github.com/sponsors/XAMPPRocky (1 package)
 //! │    └─ remove_dir_all 0.5.2
-//! ├─── https://github.com/sponsors/dtolnay
+//! ├─── https://github.com/sponsors/dtolnay (8 packages)
 //! │    ├─ anyhow 1.0.28
 //! │    ├─ dtoa 0.4.5
 //! │    ├─ itoa 0.4.5
@@ -20,7 +20,7 @@
 //! │    ├─ quote 1.0.3
 //! │    ├─ ryu 1.0.4
 //! │    └─ syn 1.0.18
-//! └─── https://github.com/sponsors/seanmonstar
+//! └─── https://github.com/sponsors/seanmonstar (6 packages)
 //!      ├─ httparse 1.3.4
 //!      ├─ num_cpus 1.13.0
 //!      ├─ reqwest 0.10.4
@@ -37,306 +37,1664 @@
 //! argument. To generate this token, go to <https://github.com/settings/tokens> and create a token
 //! with the `public_repo` and `user` scopes.
 use crate::args::Opts;
-use anyhow::{anyhow, bail, Error};
-use cargo_metadata::{Metadata, Package, PackageId};
+use crate::config::{Config, DisallowedLinks};
+use crate::responses::Responses;
+use anyhow::{anyhow, bail, Context, Error};
+use cargo_fund::progress::Progress;
+use cargo_fund::report::{FundReport, FundedPackage, FundingLink, ReportDiff};
+use cargo_fund::{cache, Event, Group, Link, LinkSource, Platform, Provenance};
+use cargo_metadata::{Metadata, PackageId};
 use clap::Parser;
-use lazy_static::lazy_static;
-use parking_lot::{MappedRwLockReadGuard, RwLock, RwLockReadGuard};
-use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
-use std::convert::{TryFrom, TryInto};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
 
+mod all;
+mod annotations;
 mod args;
-mod github;
+mod assign;
+mod browser;
+mod check;
+mod config;
+mod crates_io;
+mod diagnostic;
+mod exit;
+mod explain;
+mod generate;
+mod impact;
+mod ledger;
+mod login;
+mod man;
 mod metadata;
+mod notify;
+mod outreach;
+mod pager;
+mod patreon;
+mod plan;
+mod prometheus;
+mod registry;
+mod remote;
+mod responses;
+mod serve;
+mod summary;
+mod theme;
+mod timings;
+mod tree;
+#[cfg(feature = "tui")]
+mod tui;
+mod verify;
+mod vet;
 
-lazy_static! {
-    static ref GLOBALS: RwLock<Option<Globals>> = RwLock::new(None);
+/// How long to wait for a Github API response by default. Batched GraphQL queries over many
+/// repositories can take a while to answer, so this is generous.
+const DEFAULT_TIMEOUT_SECS: u64 = 120;
+
+/// Log to stderr, so that log messages never end up in the report. `RUST_LOG` takes precedence
+/// over `-v` and `-q`, which only set the level for `cargo fund`'s own messages.
+fn initialize_tracing(args: &args::Args) {
+    let level = match args.verbose {
+        0 if args.quiet => "error",
+        0 => "warn",
+        1 => "info",
+        2 => "debug",
+        _ => "trace",
+    };
+    let filter = tracing_subscriber::EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| format!("warn,cargo_fund={}", level).into());
+    let subscriber = tracing_subscriber::fmt::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr);
+    match args.log_format {
+        args::LogFormat::Text => subscriber.init(),
+        args::LogFormat::Json => subscriber.json().init(),
+    }
 }
 
-struct Globals {
-    github_api_token: String,
-    client: reqwest::Client,
+/// Look up the Github Sponsors listing of the maintainer behind each Github Sponsors link, and add
+/// its goal, whether the viewer sponsors them, or both to the link. Failing to is only a warning,
+/// since the links are still worth showing.
+async fn add_sponsorables(
+    cx: &cargo_fund::Context,
+    report: &mut FundReport,
+    goals: bool,
+    sponsoring: bool,
+    sponsors: bool,
+) {
+    let owner = |link: &FundingLink| link.login.clone();
+    let owners = report
+        .packages
+        .iter()
+        .flat_map(|pkg| &pkg.links)
+        .filter_map(owner)
+        .collect::<BTreeSet<_>>();
+    let owners = owners.into_iter().collect::<Vec<_>>();
+    let sponsorables = match cargo_fund::github::sponsorables(cx, &owners).await {
+        Ok(sponsorables) => sponsorables,
+        Err(e) => {
+            tracing::warn!("could not look up Github Sponsors listings: {}", e);
+            return;
+        }
+    };
+    for link in report.packages.iter_mut().flat_map(|pkg| &mut pkg.links) {
+        let Some(sponsorable) = owner(link).and_then(|owner| sponsorables.get(&owner)) else {
+            continue;
+        };
+        if goals {
+            link.goal = sponsorable.goal.clone();
+        }
+        link.sponsoring = sponsoring && sponsorable.viewer_is_sponsoring;
+        if sponsors {
+            link.sponsors = sponsorable.sponsors;
+        }
+    }
 }
 
-fn initialize_globals(env: &args::Env, args: &args::Args) -> Result<(), Error> {
-    tracing_subscriber::fmt::fmt()
-        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
-        .with_writer(std::io::stderr)
-        .init();
-    let github_api_token = if let Some(token) = args
-        .github_api_token
-        .as_ref()
-        .or(env.github_api_token.as_ref())
-    {
-        token
-    } else {
-        bail!(
+/// Add each Patreon campaign's patron count to its links, and leave out the links of deleted
+/// campaigns, then the packages left without links.
+async fn add_patrons(client: &reqwest::Client, responses: &Responses, report: &mut FundReport) {
+    let campaigns = patreon::campaigns(client, responses, report).await;
+    responses.save();
+    patreon::apply(report, &campaigns);
+}
+
+/// Print the explanation of an error code, or list the codes.
+fn run_explain(code: Option<&str>) -> Result<(), Error> {
+    let mut out = String::new();
+    match code {
+        Some(code) => match explain::explanation(code) {
+            Some(explanation) => writeln!(out, "{}", explanation).unwrap(),
+            None => bail!(
+                "no error code named `{}`; run `cargo fund explain` to list them",
+                code
+            ),
+        },
+        None => explain::write_codes(&mut out),
+    }
+    print!("{}", out);
+    Ok(())
+}
+
+/// Build the context for resolving funding links from the arguments and configuration file. A
+/// token is required only if `queries_github`.
+fn context(
+    args: &args::Args,
+    config: &Config,
+    queries_github: bool,
+) -> Result<cargo_fund::Context, Error> {
+    let github_api_token = match &args.github_api_token {
+        // a token pasted into a variable or read with `$(cat ...)` often has stray whitespace
+        Some(token) => Some(token.trim().to_string()),
+        None => match config.token()? {
+            Some(token) => Some(token),
+            None => login::default_path()
+                .map(|path| login::load(&path))
+                .transpose()?
+                .flatten(),
+        },
+    };
+    if github_api_token.is_none() && !args.offline && args.replay.is_none() && queries_github {
+        let error = anyhow!(
             "Github API token must be provided through the CARGO_FUND_GITHUB_API_TOKEN environment \
-             variable or the --github-api-token flag."
+             variable or the --github-api-token flag, or kept with `cargo fund login`."
         );
+        return Err(exit::with_status(exit::Status::Auth, error));
     };
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(60))
+    let timeout = args
+        .timeout
+        .or(config.github.timeout)
+        .unwrap_or(DEFAULT_TIMEOUT_SECS);
+    let mut client = reqwest::Client::builder().timeout(std::time::Duration::from_secs(timeout));
+    // without an explicit proxy, reqwest uses the one from `HTTPS_PROXY`, `ALL_PROXY`, etc.
+    if let Some(proxy) = args.proxy.as_ref().or(config.github.proxy.as_ref()) {
+        let proxy = reqwest::Proxy::all(proxy.as_str())
+            .with_context(|| format!("invalid proxy URL {}", proxy))?;
+        client = client.proxy(proxy);
+    }
+    // the operating system's trust store is always used, so this only adds to it
+    if let Some(path) = args.cacert.as_ref().or(config.github.cacert.as_ref()) {
+        let pem = std::fs::read(path)
+            .with_context(|| format!("error reading CA certificates from {}", path.display()))?;
+        let certs = reqwest::Certificate::from_pem_bundle(&pem)
+            .with_context(|| format!("invalid CA certificates in {}", path.display()))?;
+        for cert in certs {
+            client = client.add_root_certificate(cert);
+        }
+    }
+    let client = client
         .user_agent(concat!(
             env!("CARGO_PKG_NAME"),
             "/",
             env!("CARGO_PKG_VERSION")
         ))
         .build()?;
-    *GLOBALS.write() = Some(Globals {
-        github_api_token: github_api_token.to_string(),
+    Ok(cargo_fund::Context {
+        github_api_token,
         client,
+        batch_size: args
+            .batch_size
+            .or(config.github.batch_size)
+            .unwrap_or(cargo_fund::github::DEFAULT_BATCH_SIZE),
+        jobs: args
+            .jobs
+            .or(config.github.jobs)
+            .unwrap_or(cargo_fund::github::DEFAULT_JOBS),
+        wait_for_rate_limit: args.wait_for_rate_limit
+            || config.github.wait_for_rate_limit.unwrap_or(false),
+        strict: args.strict || (!args.lenient && config.strict.unwrap_or(false)),
+        offline: args.offline,
+        quiet: args.quiet,
+        fixtures: args.fixtures(),
+        resolvers: cargo_fund::default_resolvers(),
+    })
+}
+
+/// Build the options for writing trees of results from the arguments and configuration file.
+fn tree_options(
+    args: &args::Args,
+    config: &Config,
+    metadata: &Metadata,
+) -> Result<tree::TreeOptions, Error> {
+    let style = args.style.or(config.style);
+    // like redirected output, a file written with `--output` sticks to ASCII
+    let charset = args
+        .charset
+        .or(config.charset)
+        .unwrap_or_else(|| tree::default_charset(style, args.output.is_some()));
+    let style = style.unwrap_or(match charset {
+        args::Charset::Unicode => args::Style::Unicode,
+        args::Charset::Ascii => args::Style::Ascii,
     });
+    let mut glyphs = tree::Glyphs::for_style(style, charset);
+    for spec in &args.glyphs {
+        glyphs.set(spec).map_err(|e| {
+            diagnostic::with_help(
+                e,
+                "--glyph",
+                "give a glyph and one character, such as corner=+",
+            )
+        })?;
+    }
+    if charset == args::Charset::Ascii && !glyphs.is_ascii() {
+        return Err(diagnostic::with_help(
+            anyhow!("a glyph isn't an ASCII character, but the charset is ascii"),
+            "--glyph",
+            "give an ASCII glyph, or pass --charset unicode",
+        ));
+    }
+    let color = args.color.or(config.color);
+    let theme = args
+        .theme
+        .or(config.theme)
+        .unwrap_or(args::ThemeName::Default);
+    Ok(tree::TreeOptions {
+        glyphs,
+        charset,
+        compact: style == args::Style::Compact,
+        show_descriptions: args.show_descriptions || config.show_descriptions.unwrap_or(false),
+        show_provenance: args.show_provenance || config.show_provenance.unwrap_or(false),
+        impact: Some(metadata)
+            .filter(|_| args.show_impact || config.show_impact.unwrap_or(false))
+            .map(impact::scores),
+        downloads: None,
+        releases: None,
+        outdated: None,
+        vet: None,
+        theme: Some(theme::Theme::for_name(theme)).filter(|_| match args.output {
+            Some(_) => color == Some(args::Color::Always),
+            None => theme::color_enabled(color),
+        }),
+        width: args
+            .width
+            .or(config.width)
+            .or_else(|| tree::terminal_width().filter(|_| args.output.is_none())),
+        icons: args.icons || config.icons.unwrap_or(false),
+    })
+}
+
+/// The registries to keep packages from, given on the command line or else in the configuration.
+fn registry_filter(args: &args::Args, config: &Config) -> Result<Option<registry::Filter>, Error> {
+    let include = match args.registries.is_empty() {
+        true => &config.registries,
+        false => &args.registries,
+    };
+    let exclude = [&args.exclude_registries[..], &config.exclude_registries[..]].concat();
+    registry::Filter::new(include, &exclude, &args.manifest_dir()?)
+}
+
+/// Leave out git dependencies unless they're included, and path dependencies outside the
+/// workspace if they're excluded, on the command line or in the configuration.
+fn retain_sources(metadata: &mut Metadata, args: &args::Args, config: &Config) {
+    metadata::retain_sources(
+        metadata,
+        args.include_git_deps || config.include_git_deps.unwrap_or(false),
+        args.exclude_path_deps || config.exclude_path_deps.unwrap_or(false),
+    );
+}
+
+/// Leave the packages that `--registry`, `--exclude-registry`, `--author`, and `--since` rule out
+/// of `metadata`.
+fn filter_packages(
+    metadata: &mut Metadata,
+    registries: Option<&registry::Filter>,
+    args: &args::Args,
+) -> Result<(), Error> {
+    if let Some(filter) = registries {
+        filter.apply(metadata);
+    }
+    metadata::retain_authors(metadata, &args.authors);
+    if let Some(rev) = &args.since {
+        let locked = metadata::locked_at(metadata, rev)?;
+        metadata::retain_unlocked(metadata, &locked);
+    }
     Ok(())
 }
 
-fn globals() -> MappedRwLockReadGuard<'static, Globals> {
-    RwLockReadGuard::map(GLOBALS.read(), |o| {
-        o.as_ref().expect("globals must be initialized first")
-    })
+/// Leave the packages and owners that the configuration file ignores out of the sources.
+fn ignore_sources(
+    source_map: &mut HashMap<LinkSource, HashSet<PackageId>>,
+    metadata: &Metadata,
+    config: &Config,
+) {
+    source_map.retain(|source, pkgs| {
+        let owner = source.owner();
+        if let Some(owner) = owner.filter(|owner| {
+            let mut ignored = config.ignore_owners.iter();
+            ignored.any(|ignored| ignored.eq_ignore_ascii_case(owner))
+        }) {
+            tracing::debug!("ignoring {} owned by {}", source, owner);
+            return false;
+        }
+        pkgs.retain(|pkg| !config.ignore.contains(&metadata[pkg].name));
+        !pkgs.is_empty()
+    });
+}
+
+/// Apply the configuration's overrides for individual packages: suppressed packages are left out
+/// of the report, and extra links are added to the others. What's done is logged with `-v`.
+fn apply_package_overrides(
+    report: &mut FundReport,
+    metadata: &Metadata,
+    config: &Config,
+) -> Result<(), Error> {
+    for (name, overrides) in &config.package {
+        let reason = match &overrides.reason {
+            Some(reason) => format!(": {}", reason),
+            None => String::new(),
+        };
+        if overrides.suppress {
+            tracing::info!("suppressing funding links for {}{}", name, reason);
+            report.packages.retain(|pkg| pkg.name != *name);
+            continue;
+        }
+        if overrides.links.is_empty() {
+            continue;
+        }
+        let mut links = vec![];
+        for url in &overrides.links {
+            let uri = url.parse().with_context(|| {
+                format!("invalid funding link {:?} configured for {}", url, name)
+            })?;
+            let link = Link {
+                platform: Platform::Custom,
+                uri,
+                login: None,
+            };
+            let provenance = BTreeSet::from([Provenance::Configuration]);
+            links.push(FundingLink::new(&link, provenance));
+        }
+        let pkgs = metadata
+            .packages
+            .iter()
+            .filter(|pkg| pkg.name == *name && !metadata.workspace_members.contains(&pkg.id))
+            .collect::<Vec<_>>();
+        if pkgs.is_empty() {
+            tracing::info!("no dependency named {} to add funding links to", name);
+        } else {
+            tracing::info!("adding configured funding links for {}{}", name, reason);
+        }
+        for pkg in pkgs {
+            match report
+                .packages
+                .iter_mut()
+                .find(|funded| funded.id == pkg.id)
+            {
+                Some(funded) => {
+                    for link in &links {
+                        let found = |found: &FundingLink| {
+                            // the same page, however either link was written
+                            let key = |url: &str| {
+                                let link = Link::try_from((found.platform.as_str(), url));
+                                link.map(|link| link.canonical())
+                                    .map_or_else(|_| url.to_string(), |link| link.uri.to_string())
+                            };
+                            key(&found.url) == key(&link.url)
+                        };
+                        if !funded.links.iter().any(found) {
+                            funded.links.push(link.clone());
+                        }
+                    }
+                    funded.links.sort();
+                }
+                None => report.packages.push(FundedPackage {
+                    id: pkg.id.clone(),
+                    name: pkg.name.clone(),
+                    version: pkg.version.to_string(),
+                    description: pkg.description.clone(),
+                    links: links.clone(),
+                    downloads: None,
+                    last_release: None,
+                    latest_version: None,
+                    outdated: false,
+                    vet_status: None,
+                }),
+            }
+        }
+    }
+    report.packages.sort_by(|a, b| {
+        let (a, b) = (&metadata[&a.id], &metadata[&b.id]);
+        (&a.name, &a.version, &a.id).cmp(&(&b.name, &b.version, &b.id))
+    });
+    Ok(())
+}
+
+/// Read a report saved with `--format json`, such as a baseline.
+fn load_report(path: &Path) -> Result<FundReport, Error> {
+    let report = std::fs::read_to_string(path)
+        .with_context(|| format!("error reading {}", path.display()))?;
+    serde_json::from_str(&report).with_context(|| format!("error parsing {}", path.display()))
+}
+
+/// Check the funding links in `report` and print what became of them, as JSON if that is the
+/// chosen format.
+async fn verify_links(
+    client: &reqwest::Client,
+    responses: &Responses,
+    report: &FundReport,
+    format: args::Format,
+) -> Result<(), Error> {
+    let checked = verify::verify(client, responses, report).await;
+    responses.save();
+    let mut out = String::new();
+    if format == args::Format::Json {
+        out = serde_json::to_string_pretty(&checked)? + "\n";
+    } else {
+        verify::write(&mut out, &checked);
+    }
+    print!("{}", out);
+    Ok(())
+}
+
+/// Check the committed funding plan against the fundable maintainers in `groups`, and fail if it
+/// is out of date.
+fn check_plan(
+    plan: &args::PlanArgs,
+    metadata: &Metadata,
+    groups: &[Group],
+    format: args::Format,
+) -> Result<(), Error> {
+    let path = match &plan.plan_file {
+        Some(path) => path.clone(),
+        None => metadata.workspace_root.join(plan::FILE_NAME),
+    };
+    let check = plan::check(&plan::PlanFile::load(&path)?, groups);
+    let mut out = String::new();
+    if format == args::Format::Json {
+        out = serde_json::to_string_pretty(&check)? + "\n";
+    } else {
+        plan::write_check(&mut out, &check);
+    }
+    print!("{}", out);
+    if check.is_outdated() {
+        return Err(exit::with_status(
+            exit::Status::Policy,
+            anyhow!("{} is out of date", path.display()),
+        ));
+    }
+    Ok(())
 }
 
-#[derive(Clone, Debug, Hash, Eq, PartialEq, Ord, PartialOrd)]
-enum LinkSource {
-    Github(github::GithubLinkSource),
+/// Write the changes since a baseline as text.
+fn write_diff(out: &mut String, diff: &ReportDiff) {
+    if diff.is_empty() {
+        writeln!(out, "no changes since the baseline").unwrap();
+        return;
+    }
+    let links = |links: &[FundingLink]| {
+        let urls = links.iter().map(|link| link.url.as_str());
+        urls.collect::<Vec<_>>().join(", ")
+    };
+    let mut section = |heading: &str, lines: Vec<String>| {
+        if !lines.is_empty() {
+            writeln!(out, "{}:", heading).unwrap();
+            for line in lines {
+                writeln!(out, "  {}", line).unwrap();
+            }
+        }
+    };
+    section(
+        "new fundable packages",
+        diff.new_packages
+            .iter()
+            .map(|pkg| format!("+ {} {} ({})", pkg.name, pkg.version, links(&pkg.links)))
+            .collect(),
+    );
+    section(
+        "no longer fundable",
+        diff.removed_packages
+            .iter()
+            .map(|pkg| format!("- {} {} ({})", pkg.name, pkg.version, links(&pkg.links)))
+            .collect(),
+    );
+    section(
+        "links added",
+        diff.added_links
+            .iter()
+            .map(|(name, added)| format!("+ {}: {}", name, links(added)))
+            .collect(),
+    );
+    section(
+        "links removed",
+        diff.removed_links
+            .iter()
+            .map(|(name, removed)| format!("- {}: {}", name, links(removed)))
+            .collect(),
+    );
+    section(
+        "new sponsor listings",
+        diff.new_sponsor_listings
+            .iter()
+            .map(|url| format!("+ {}", url))
+            .collect(),
+    );
 }
 
-#[derive(Clone, Debug, Hash, Eq, PartialEq, Ord, PartialOrd)]
-pub enum Platform {
-    CommunityBridge,
-    Custom,
-    Github,
-    IssueHunt,
-    Kofi,
-    Liberapay,
-    OpenCollective,
-    Otechie,
-    Patreon,
-    Tidelift,
-    Other(String),
+/// Hide or flag the links on platforms that the configuration doesn't allow.
+fn apply_platform_policy(report: &mut FundReport, config: &Config) {
+    let policy = config.disallowed_links.unwrap_or_default();
+    for pkg in &mut report.packages {
+        for link in &mut pkg.links {
+            link.disallowed = !config.platform_allowed(&link.platform);
+        }
+        if policy == DisallowedLinks::Hide {
+            pkg.links.retain(|link| !link.disallowed);
+        }
+    }
+    report.packages.retain(|pkg| !pkg.links.is_empty());
 }
 
-impl From<&str> for Platform {
-    fn from(platform: &str) -> Self {
-        match platform.to_ascii_uppercase().as_str() {
-            "COMMUNITY_BRIDGE" => Self::CommunityBridge,
-            "CUSTOM" => Self::Custom,
-            "GITHUB" => Self::Github,
-            "ISSUEHUNT" => Self::IssueHunt,
-            "KO_FI" => Self::Kofi,
-            "LIBERAPAY" => Self::Liberapay,
-            "OPEN_COLLECTIVE" => Self::OpenCollective,
-            "OTECHIE" => Self::Otechie,
-            "PATREON" => Self::Patreon,
-            "TIDELIFT" => Self::Tidelift,
-            _ => Self::Other(platform.to_string()),
+/// Note what has been given to each maintainer heading a group, according to the ledger, where
+/// donations are recorded by Github account or by one of the group's funding links.
+fn annotate_donations(groups: &mut [Group], ledger: &ledger::Ledger) {
+    for group in groups {
+        let summary = ledger.group_summary(group);
+        if let (Some(heading), Some(summary)) = (&mut group.heading, summary) {
+            write!(
+                heading,
+                " (funded {} in total, last on {})",
+                summary.total, summary.last
+            )
+            .unwrap();
         }
     }
 }
 
-#[derive(Clone, Debug, Hash, Eq, PartialEq)]
-pub struct Link {
-    platform: Platform,
-    uri: http::Uri,
+/// Sort the groups for display.
+fn sort_groups(groups: &mut [Group], key: args::SortKey, reverse: bool, metadata: &Metadata) {
+    match key {
+        args::SortKey::Impact => {
+            let scores = impact::scores(metadata);
+            let score = |group: &Group| impact::group_score(&scores, group);
+            groups.sort_by(|a, b| {
+                score(b)
+                    .total_cmp(&score(a))
+                    .then_with(|| a.links.cmp(&b.links))
+            })
+        }
+        args::SortKey::Name => groups.sort_by_cached_key(|g| g.name()),
+        args::SortKey::PackageCount => {
+            groups.sort_by_cached_key(|g| (std::cmp::Reverse(g.pkgs.len()), g.name()))
+        }
+        args::SortKey::Platform => groups.sort_by(|a, b| a.links.cmp(&b.links)),
+    }
+    if reverse {
+        groups.reverse();
+    }
 }
 
-impl Ord for Link {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        match self.platform.cmp(&other.platform) {
-            std::cmp::Ordering::Equal => self.uri.to_string().cmp(&other.uri.to_string()),
-            other => other,
+/// Write the results in the porcelain format.
+///
+/// This format is a stable interface for scripts, and must not change between versions. Each
+/// line contains four tab-separated fields: the package name, the package version, the funding
+/// platform, and the funding URL. Lines are sorted, and there is no summary line.
+fn write_porcelain(out: &mut String, report: &FundReport) {
+    let mut lines = BTreeSet::new();
+    for pkg in &report.packages {
+        for link in &pkg.links {
+            lines.insert(format!(
+                "{}\t{}\t{}\t{}",
+                pkg.name, pkg.version, link.platform, link.url
+            ));
         }
     }
+    for line in lines {
+        writeln!(out, "{}", line).unwrap();
+    }
+}
+
+/// Write each unique funding URL on its own line, in sorted order.
+fn write_links_only(out: &mut String, report: &FundReport) {
+    let urls: BTreeSet<&str> = report
+        .packages
+        .iter()
+        .flat_map(|pkg| &pkg.links)
+        .map(|link| link.url.as_str())
+        .collect();
+    for url in urls {
+        writeln!(out, "{}", url).unwrap();
+    }
 }
 
-impl PartialOrd for Link {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.cmp(&other))
+#[tokio::main]
+async fn main() -> ExitCode {
+    let Opts::Fund(args) = match Opts::try_parse() {
+        Ok(opts) => opts,
+        Err(e) => {
+            // clap exits with 2 for usage errors, which is taken by authentication failures
+            let _ = e.print();
+            return if e.use_stderr() {
+                exit::Status::Failure.into()
+            } else {
+                ExitCode::SUCCESS
+            };
+        }
+    };
+    match run(args).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprint!("{}", diagnostic::render(&e));
+            exit::status(&e).into()
+        }
     }
 }
 
-impl TryFrom<(&str, &str)> for Link {
-    type Error = Error;
+async fn run(mut args: args::Args) -> Result<(), Error> {
+    initialize_tracing(&args);
+    match args.command.take() {
+        Some(args::Command::Man) => {
+            man::write(&mut std::io::stdout().lock()).context("error writing the manual page")
+        }
+        Some(args::Command::Explain(explain)) => run_explain(explain.code.as_deref()),
+        Some(args::Command::Record(record)) => run_record(&args, &record),
+        Some(args::Command::Login) => run_login(),
+        Some(args::Command::Cache(command)) => run_cache(&args, &command),
+        Some(args::Command::Serve(serve)) => run_serve(&args, &serve).await,
+        Some(args::Command::Check(check)) => run_check(args, &check).await,
+        Some(args::Command::Diff(diff)) => run_diff(args, &diff).await,
+        Some(args::Command::Generate(generate)) => run_generate(args, &generate).await,
+        #[cfg(feature = "tui")]
+        Some(args::Command::Tui) => run_tui(args).await,
+        Some(args::Command::Plan(plan)) => run_plan(args, &plan).await,
+        Some(args::Command::Remind(remind)) => run_remind(args, &remind).await,
+        Some(args::Command::All(all)) => run_all(args, &all).await,
+        Some(args::Command::Assign(assign)) => run_assign(args, &assign).await,
+        Some(args::Command::Outreach(outreach)) => run_outreach(args, &outreach).await,
+        Some(args::Command::Verify(verify)) => run_verify(args, &verify).await,
+        Some(args::Command::Notify(notify)) => run_notify(args, &notify).await,
+        Some(args::Command::Links) => {
+            args.links_only = true;
+            run_report(args).await
+        }
+        Some(args::Command::Report) | None => run_report(args).await,
+    }
+}
 
-    fn try_from((platform, url): (&str, &str)) -> Result<Self, Self::Error> {
-        let platform = platform.try_into()?;
-        let mut uri: http::Uri = if url.starts_with("http") {
-            url.parse()?
+/// Find the configuration for the workspace, with the profile chosen by `--profile`.
+fn load_config(args: &args::Args) -> Result<Config, Error> {
+    Config::discover(&args.manifest_dir()?, args.profile.as_deref())
+}
+
+/// Where the ledger of donations is kept.
+fn ledger_path(args: &args::Args, config: &Config) -> Option<PathBuf> {
+    args.ledger
+        .clone()
+        .or_else(|| config.ledger.clone())
+        .or_else(ledger::default_path)
+}
+
+/// The directory given for the cache, if any.
+fn cache_dir<'a>(args: &'a args::Args, config: &'a Config) -> Option<&'a Path> {
+    args.cache_dir.as_deref().or(config.cache_dir.as_deref())
+}
+
+/// Where the cache of funding links is kept, unless it's bypassed.
+fn cache_path(args: &args::Args, config: &Config) -> Option<PathBuf> {
+    // fixtures are recorded and replayed for every query, which the cache would answer instead
+    cache::default_path(cache_dir(args, config))
+        .filter(|_| !args.no_cache && args.fixtures().is_none())
+}
+
+/// The responses kept beside the cache for conditional requests.
+fn load_responses(args: &args::Args, config: &Config) -> Responses {
+    Responses::load(
+        cache::default_path(cache_dir(args, config))
+            .filter(|_| !args.no_cache)
+            .map(|path| responses::path(&path)),
+    )
+}
+
+/// Record a donation to a maintainer in the ledger.
+fn run_record(args: &args::Args, record: &args::RecordArgs) -> Result<(), Error> {
+    let config = load_config(args)?;
+    let path = ledger_path(args, &config)
+        .context("no data directory to keep the ledger in; pass --ledger")?;
+    let mut ledger = ledger::Ledger::load(&path)?;
+    let donation = ledger::Donation {
+        maintainer: record.maintainer.clone(),
+        amount: ledger::Amount(record.amount),
+        date: record.date.unwrap_or_else(ledger::Date::today),
+        note: record.note.clone(),
+    };
+    eprintln!(
+        "recorded {} to {} on {} in {}",
+        donation.amount,
+        donation.maintainer,
+        donation.date,
+        path.display()
+    );
+    ledger.donations.push(donation);
+    ledger.save(&path)
+}
+
+/// Keep a Github API token read from stdin for later runs.
+fn run_login() -> Result<(), Error> {
+    let path = login::default_path().context("no configuration directory to keep the token in")?;
+    eprintln!(
+        "Paste a Github API token with the `public_repo` and `user` scopes, which can be created \
+         at https://github.com/settings/tokens:"
+    );
+    let mut token = String::new();
+    std::io::stdin()
+        .read_line(&mut token)
+        .context("error reading the token")?;
+    let token = token.trim();
+    if token.is_empty() {
+        bail!("no token was given");
+    }
+    login::save(&path, token)?;
+    eprintln!("saved the token to {}", path.display());
+    Ok(())
+}
+
+/// Print where the cache is, or clear it.
+fn run_cache(args: &args::Args, command: &args::CacheCommand) -> Result<(), Error> {
+    let config = load_config(args)?;
+    let path = cache::default_path(cache_dir(args, &config))
+        .context("no cache directory; pass --cache-dir")?;
+    match command {
+        args::CacheCommand::Path => println!("{}", path.display()),
+        args::CacheCommand::Clear => {
+            // the responses kept for conditional requests are cleared along with the links
+            if let Err(e) = std::fs::remove_file(responses::path(&path)) {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    tracing::warn!("could not remove the kept responses: {}", e);
+                }
+            }
+            match std::fs::remove_file(&path) {
+                Ok(()) => eprintln!("removed {}", path.display()),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    eprintln!("no cache at {}", path.display())
+                }
+                Err(e) => {
+                    return Err(Error::from(e).context(format!("error removing {}", path.display())))
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Answer queries for funding links over HTTP.
+async fn run_serve(args: &args::Args, serve: &args::ServeArgs) -> Result<(), Error> {
+    let config = load_config(args)?;
+    let cx = context(args, &config, true)?;
+    let cache_path = cache_path(args, &config);
+    let cache = match &cache_path {
+        Some(path) => cache::Cache::load(path),
+        None => cache::Cache::default(),
+    };
+    let server = serve::Server {
+        cx,
+        cache: parking_lot::Mutex::new(cache),
+        cache_path: cache_path.filter(|_| !args.offline),
+        ttl: if args.offline {
+            Duration::MAX
         } else {
-            // Try https if there's no scheme
-            format!("https://{}", url).parse()?
+            args.cache_ttl
+        },
+    };
+    serve::run(server, serve.listen).await
+}
+
+/// Check the report against the workspace's funding decisions, failing with the policy status if
+/// any fundable maintainer has no decision. The decisions that were made are logged with `-v`.
+async fn run_check(mut args: args::Args, check: &args::CheckArgs) -> Result<(), Error> {
+    let session = Session::start(&mut args, None).await?;
+    let path = match &check.decisions {
+        Some(path) => path.clone(),
+        None => session.metadata.workspace_root.join(check::FILE_NAME),
+    };
+    let decisions = check::Decisions::load(&path)?;
+    let outcome = check::check(&session.source_map, &session.report, &decisions);
+    for (_, name, decision) in &outcome.decided {
+        let verdict = match decision.decision {
+            check::Verdict::Sponsor => "sponsor",
+            check::Verdict::Decline => "decline",
         };
-        if let Platform::Github = platform {
-            // fix up the URI for github sponsors 🤷
-            let mut parts = uri.into_parts();
-            parts.path_and_query = Some(
-                format!(
-                    "/sponsors{}",
-                    parts
-                        .path_and_query
-                        .ok_or_else(|| anyhow!("Github URL missing path"))?
-                        .as_str()
-                )
-                .as_str()
-                .try_into()?,
-            );
-            uri = http::Uri::from_parts(parts)?;
+        match &decision.reason {
+            Some(reason) => tracing::info!("{}: {} ({})", name, verdict, reason),
+            None => tracing::info!("{}: {}", name, verdict),
         }
-        Ok(Link { platform, uri })
     }
+    if outcome.undecided.is_empty() {
+        println!(
+            "all {} fundable maintainers have funding decisions",
+            outcome.decided.len()
+        );
+        return session.check_results();
+    }
+    let mut out = String::new();
+    tree::write_tree(
+        &mut out,
+        &session.metadata,
+        &outcome.undecided,
+        &session.options,
+    );
+    print!("{}", out);
+    Err(exit::with_status(
+        exit::Status::Policy,
+        anyhow!(
+            "{} fundable maintainers have no funding decision in {}",
+            outcome.undecided.len(),
+            path.display()
+        ),
+    ))
 }
 
-/// Try to get sources for a single package.
-fn try_get_sources<'a>(package: &Package) -> Result<Vec<LinkSource>, Error> {
-    let uri: http::Uri = if let Some(repo) = package.repository.as_ref() {
-        repo.parse()?
+/// Print the changes between the baseline and the report, as JSON if that is the chosen format.
+async fn run_diff(mut args: args::Args, diff: &args::DiffArgs) -> Result<(), Error> {
+    let session = Session::start(&mut args, None).await?;
+    let diff = session.report.diff(&load_report(&diff.baseline)?);
+    let mut out = String::new();
+    if session.format == args::Format::Json {
+        out = serde_json::to_string_pretty(&diff)?;
+        out.push('\n');
     } else {
-        return Ok(vec![]);
-    };
-    match uri.authority().map(|a| a.as_str()) {
-        Some("github.com") | Some("www.github.com") => github::try_get_sources(uri),
-        _ => Ok(vec![]),
+        write_diff(&mut out, &diff);
     }
+    print!("{}", out);
+    session.check_results()
 }
 
-/// Get the sources for all dependencies in the workspace.
-fn collect_sources(metadata: &Metadata) -> Result<HashMap<LinkSource, HashSet<PackageId>>, Error> {
-    let mut source_map = HashMap::new();
-    for pkg in &metadata.packages {
-        if metadata.workspace_members.contains(&pkg.id) {
-            // skip packages within our own workspace
-            continue;
+/// Write a file generated from the report.
+async fn run_generate(mut args: args::Args, generate: &args::Generate) -> Result<(), Error> {
+    let session = Session::start(&mut args, None).await?;
+    let root = &session.metadata.workspace_root;
+    let mut out = String::new();
+    let path = match generate {
+        args::Generate::Acknowledgments(generate) => {
+            generate::write_acknowledgments(
+                &mut out,
+                &session.metadata,
+                &session.source_map,
+                &session.report,
+            );
+            generate
+                .output
+                .clone()
+                .unwrap_or_else(|| root.join(generate::ACKNOWLEDGMENTS))
         }
-        for source in try_get_sources(pkg)? {
-            source_map
-                .entry(source)
-                .or_insert_with(HashSet::new)
-                .insert(pkg.id.clone());
+        args::Generate::Interop(generate) => {
+            generate::write_interop(&mut out, &session.metadata, &session.report);
+            generate
+                .output
+                .clone()
+                .unwrap_or_else(|| root.join(generate::INTEROP))
         }
+    };
+    std::fs::write(&path, out).with_context(|| format!("error writing {}", path.display()))?;
+    eprintln!("wrote {}", path.display());
+    session.check_results()
+}
+
+/// Browse the funding links interactively.
+#[cfg(feature = "tui")]
+async fn run_tui(mut args: args::Args) -> Result<(), Error> {
+    let session = Session::start(&mut args, None).await?;
+    tui::run(&session.metadata, &session.source_map, &session.report)
+}
+
+/// Propose how to split a budget between the fundable maintainers, or without a budget, check the
+/// committed plan.
+async fn run_plan(mut args: args::Args, plan: &args::PlanArgs) -> Result<(), Error> {
+    let session = Session::start(&mut args, None).await?;
+    let mut groups = session.owner_groups();
+    groups.sort_by_cached_key(|group| group.name());
+    let Some(budget) = plan.budget else {
+        return check_plan(plan, &session.metadata, &groups, session.format);
+    };
+    let shares = plan::plan(&session.metadata, &groups, budget, plan.strategy);
+    let mut out = String::new();
+    if session.format == args::Format::Json {
+        out = serde_json::to_string_pretty(&shares)? + "\n";
+    } else {
+        plan::write_plan(&mut out, &shares);
     }
-    Ok(source_map)
+    print!("{}", out);
+    if let Some(path) = &plan.shopping_list {
+        let mut list = String::new();
+        plan::write_shopping_list(&mut list, &shares);
+        std::fs::write(path, list).with_context(|| format!("error writing {}", path.display()))?;
+        eprintln!("wrote {}", path.display());
+    }
+    session.check_results()
 }
 
-/// Turn the sources into a mapping between packages and sets of funding links.
-async fn resolve_links(
-    source_map: &HashMap<LinkSource, HashSet<PackageId>>,
-) -> Result<HashMap<PackageId, HashSet<Link>>, Error> {
-    // only one source for now, but other resolvers can add to this mapping later
-    let mut resolved = HashMap::new();
-    github::resolve_github_links(source_map, &mut resolved).await?;
-    Ok(resolved)
+/// List the fundable maintainers who are due another donation according to the ledger.
+async fn run_remind(mut args: args::Args, remind: &args::RemindArgs) -> Result<(), Error> {
+    let session = Session::start(&mut args, None).await?;
+    let path = session
+        .ledger_path
+        .as_ref()
+        .context("no data directory to find the ledger in; pass --ledger")?;
+    let ledger = ledger::Ledger::load(path)?;
+    let mut groups = session.owner_groups();
+    groups.sort_by_cached_key(|group| group.name());
+    let days = (remind.interval.as_secs() / 86400) as i64;
+    let today = ledger::Date::today();
+    let reminders = ledger::reminders(&session.metadata, &groups, &ledger, days, today);
+    let mut out = String::new();
+    if session.format == args::Format::Json {
+        out = serde_json::to_string_pretty(&reminders)? + "\n";
+    } else {
+        ledger::write_reminders(&mut out, &reminders);
+    }
+    print!("{}", out);
+    session.check_results()
 }
 
-/// Invert the mapping between packages and sets of funding links.
-///
-/// This allows us to group the output by unique sets of funding links.
-fn invert_mapping(
-    resolved: HashMap<PackageId, HashSet<Link>>,
-) -> BTreeMap<BTreeSet<Link>, BTreeSet<PackageId>> {
-    let mut inverted = BTreeMap::new();
-    for (pkg, links) in resolved {
-        let links: BTreeSet<Link> = links.into_iter().collect();
-        inverted
-            .entry(links)
-            .or_insert_with(BTreeSet::new)
-            .insert(pkg);
-    }
-    inverted
-}
-
-/// Print the results in a pretty tree.
-///
-/// TODO: support non-Unicode, perhaps add colors?
-fn print_results(
-    metadata: &Metadata,
-    inverted: &BTreeMap<BTreeSet<Link>, BTreeSet<PackageId>>,
-    num_found: usize,
-) {
-    println!(
-        "{} (found funding links for {} out of {} dependencies)",
-        metadata.workspace_root.display(),
-        num_found,
-        metadata.packages.len() - metadata.workspace_members.len()
-    );
-    let last_mapping_ix = if let Some(ix) = inverted.len().checked_sub(1) {
-        ix
+/// Report on the maintainers that the projects in `all` depend on together.
+async fn run_all(mut args: args::Args, all: &args::AllArgs) -> Result<(), Error> {
+    let session = Session::start(&mut args, Some(all)).await?;
+    let projects = session
+        .projects
+        .as_ref()
+        .expect("the projects are found for `all`");
+    let groups = session.owner_groups();
+    let usage = projects.usage(&session.metadata, &groups);
+    let mut out = String::new();
+    if session.format == args::Format::Json {
+        out = serde_json::to_string_pretty(&usage)? + "\n";
     } else {
-        return;
+        eprintln!(
+            "found funding links for {} maintainers across {} projects",
+            usage.len(),
+            projects.roots.len()
+        );
+        all::write_usage(&mut out, &usage, projects.roots.len());
+    }
+    print!("{}", out);
+    session.check_results()
+}
+
+/// Share out the fundable maintainers between the members of a team.
+async fn run_assign(mut args: args::Args, assign: &args::AssignArgs) -> Result<(), Error> {
+    let session = Session::start(&mut args, None).await?;
+    let team = assign::Team::load(&assign.team_file)?;
+    let groups = session.owner_groups();
+    let assignments = assign::assign(&team, &groups, assign.strategy, assign.per_member);
+    let mut out = String::new();
+    if session.format == args::Format::Json {
+        out = serde_json::to_string_pretty(&assignments)? + "\n";
+    } else {
+        assign::write_assignments(&mut out, &assignments);
+    }
+    print!("{}", out);
+    session.check_results()
+}
+
+/// Draft messages asking the maintainers of repositories without funding links to add them.
+async fn run_outreach(mut args: args::Args, outreach: &args::OutreachArgs) -> Result<(), Error> {
+    let session = Session::start(&mut args, None).await?;
+    let template = match &outreach.template {
+        Some(path) => std::fs::read_to_string(path)
+            .with_context(|| format!("error reading {}", path.display()))?,
+        None => outreach::template(outreach.kind).to_string(),
     };
-    for (mapping_ix, (links, pkgs)) in inverted.into_iter().enumerate() {
-        let last_link_ix = links.len() - 1;
-        for (link_ix, link) in links.into_iter().enumerate() {
-            // first two characters of each link line
-            match (mapping_ix, link_ix) {
-                (0, 0) if last_mapping_ix == 0 => {
-                    // first line of first and only link section
-                    print!("──");
+    let groups = cargo_fund::missing_groups(&session.source_map, &session.resolved);
+    let messages = outreach::messages(&session.metadata, &groups, &template);
+    if messages.is_empty() {
+        eprintln!("found no repositories without funding links");
+    }
+    if session.format == args::Format::Json {
+        println!("{}", serde_json::to_string_pretty(&messages)?);
+    } else {
+        print!("{}", messages.join("\n---\n\n"));
+    }
+    Ok(())
+}
+
+/// Check that the funding links still work, either those of a saved report or those found now.
+async fn run_verify(mut args: args::Args, verify: &args::VerifyArgs) -> Result<(), Error> {
+    if let Some(path) = &verify.report {
+        let config = load_config(&args)?;
+        // checking the links of a saved report doesn't query Github
+        let cx = context(&args, &config, false)?;
+        let responses = load_responses(&args, &config);
+        let report = load_report(path)?;
+        return verify_links(&cx.client, &responses, &report, args.format(&config)).await;
+    }
+    let session = Session::start(&mut args, None).await?;
+    verify_links(
+        &session.cx.client,
+        &session.responses,
+        &session.report,
+        session.format,
+    )
+    .await?;
+    session.check_results()
+}
+
+/// Post a summary of the report, or the changes since the baseline if there is one, to the
+/// webhook.
+async fn run_notify(mut args: args::Args, notify: &args::NotifyArgs) -> Result<(), Error> {
+    let session = Session::start(&mut args, None).await?;
+    let report = &session.report;
+    let message = match &notify.baseline {
+        Some(path) => {
+            let diff = report.diff(&load_report(path)?);
+            if diff.is_empty() {
+                eprintln!("no changes since the baseline; nothing was posted");
+                return session.check_results();
+            }
+            let mut changes = String::new();
+            write_diff(&mut changes, &diff);
+            notify::changes(report, &changes)
+        }
+        None => notify::summary(report),
+    };
+    notify::post(&session.cx.client, &notify.webhook, &message).await?;
+    eprintln!("posted to the webhook");
+    session.check_results()
+}
+
+/// Print the report, which is what `cargo fund` does without a subcommand.
+async fn run_report(mut args: args::Args) -> Result<(), Error> {
+    let env = envy::from_env::<args::Env>()?;
+    let mut session = Session::start(&mut args, None).await?;
+    let config = &session.config;
+    let cx = &session.cx;
+    let metadata = &session.metadata;
+    let source_map = &session.source_map;
+    let resolved = &session.resolved;
+    let report = &mut session.report;
+    let options = &mut session.options;
+    let format = session.format;
+    let show_downloads = args.show_downloads || config.show_downloads.unwrap_or(false);
+    let show_activity = args.show_activity || config.show_activity.unwrap_or(false);
+    let show_outdated = args.show_outdated || config.show_outdated.unwrap_or(false);
+    let lookup = show_downloads || show_activity || show_outdated;
+    if lookup && (args.offline || args.replay.is_some()) {
+        tracing::warn!("not looking up crates on crates.io without network access");
+    } else if lookup {
+        let responses = &session.responses;
+        let stats = crates_io::fetch(&cx.client, responses, metadata, report, show_activity).await;
+        responses.save();
+        match stats {
+            Ok(stats) => {
+                for pkg in &mut report.packages {
+                    let stats = stats.get(&pkg.name);
+                    if show_downloads {
+                        pkg.downloads = stats.map(|stats| stats.downloads);
+                    }
+                    if show_activity {
+                        pkg.last_release = stats
+                            .and_then(|stats| stats.last_release)
+                            .map(|date| date.to_string());
+                    }
+                    if show_outdated {
+                        pkg.latest_version = stats.and_then(|stats| stats.latest.clone());
+                        pkg.outdated = pkg
+                            .latest_version
+                            .as_ref()
+                            .is_some_and(|latest| crates_io::is_outdated(&pkg.version, latest));
+                    }
                 }
-                (mapping_ix, 0) if mapping_ix < last_mapping_ix => {
-                    // first line of a link section
-                    print!("├─");
+                if show_outdated {
+                    let outdated = report
+                        .packages
+                        .iter()
+                        .filter(|pkg| pkg.outdated)
+                        .filter_map(|pkg| Some((pkg.id.clone(), pkg.latest_version.clone()?)));
+                    options.outdated = Some(outdated.collect());
                 }
-                (mapping_ix, _) if mapping_ix < last_mapping_ix => {
-                    // non-first line of non-final link section
-                    print!("│ ");
+                let stats = stats.iter();
+                if show_downloads {
+                    let downloads = stats
+                        .clone()
+                        .map(|(name, stats)| (name.clone(), stats.downloads));
+                    options.downloads = Some(downloads.collect());
                 }
-                (mapping_ix, 0) if mapping_ix == last_mapping_ix => {
-                    // first line of last link section of many
-                    print!("└─");
+                if show_activity {
+                    let releases =
+                        stats.filter_map(|(name, stats)| Some((name.clone(), stats.last_release?)));
+                    options.releases = Some(releases.collect());
                 }
-                // non-first line of final link section
-                _ => print!("  "),
             }
-            // second two characters of each link line
-            match link_ix {
-                0 if last_link_ix > 0 => {
-                    // first link line of many
-                    print!("┬─");
-                }
-                0 if last_link_ix == 0 => {
-                    // first and only link line
-                    print!("──");
-                }
-                link_ix if link_ix < last_link_ix => {
-                    // non-first, non-final link line
-                    print!("├─");
+            Err(e) => tracing::warn!("{:#}", e),
+        }
+    }
+    let show_goals = args.show_goals || config.show_goals.unwrap_or(false);
+    let hide_sponsored = args.hide_sponsored || config.hide_sponsored.unwrap_or(false);
+    let only_unsponsored = args.only_unsponsored || config.only_unsponsored.unwrap_or(false);
+    let show_sponsoring = hide_sponsored
+        || only_unsponsored
+        || args.show_sponsoring
+        || config.show_sponsoring.unwrap_or(false);
+    let show_sponsors = args.show_sponsors || config.show_sponsors.unwrap_or(false);
+    let lookup = show_goals || show_sponsoring || show_sponsors;
+    // the checks are of the dependencies' funding, so they're made before the options below leave
+    // packages out of the view, and the report is counted again after so that its totals add up
+    let results = check_results(report, session.fail_if_empty, session.min_coverage);
+    let mut recount = false;
+    if lookup && (args.offline || args.replay.is_some()) {
+        tracing::warn!("not looking up Github Sponsors listings without network access");
+    } else if lookup {
+        add_sponsorables(cx, report, show_goals, show_sponsoring, show_sponsors).await;
+    }
+    if only_unsponsored {
+        report.retain_unsponsored();
+        recount = true;
+    }
+    if hide_sponsored {
+        report.retain_links(|link| !link.sponsoring);
+        recount = true;
+    }
+    if args.show_patrons || config.show_patrons.unwrap_or(false) {
+        if args.offline || args.replay.is_some() {
+            tracing::warn!("not looking up Patreon campaigns without network access");
+        } else {
+            add_patrons(&cx.client, &session.responses, report).await;
+            recount = true;
+        }
+    }
+    if recount {
+        report.count(metadata, resolved);
+    }
+    if args.show_vet || config.show_vet.unwrap_or(false) {
+        let store = vet::Store::load(&metadata.workspace_root.join(vet::STORE))?;
+        let mut statuses = HashMap::new();
+        for pkg in &mut report.packages {
+            let status = store.status(&pkg.name, &pkg.version);
+            pkg.vet_status = Some(status.as_str().to_string());
+            statuses.insert(pkg.id.clone(), status);
+        }
+        options.vet = Some(statuses);
+    }
+    let started = Instant::now();
+    let mut out = String::new();
+    match format {
+        args::Format::Json => {
+            out = serde_json::to_string_pretty(&report)?;
+            out.push('\n');
+        }
+        args::Format::Porcelain => write_porcelain(&mut out, report),
+        args::Format::LinksOnly => write_links_only(&mut out, report),
+        args::Format::Prometheus => prometheus::write_prometheus(&mut out, report),
+        args::Format::GithubAnnotations => {
+            let path = metadata.workspace_root.join(check::FILE_NAME);
+            let decisions = check::Decisions::load(path.as_ref())?;
+            // without a decisions file, there is no sponsorship list to warn about
+            let warn = path.exists();
+            annotations::write_github_annotations(
+                &mut out, metadata, source_map, report, &decisions, warn,
+            );
+        }
+        args::Format::Tree => {
+            let missing = if args.show_missing || config.show_missing.unwrap_or(false) {
+                cargo_fund::missing_groups(source_map, resolved)
+            } else {
+                vec![]
+            };
+            let unknown = cargo_fund::repo_groups(source_map, &resolved.unknown);
+            let mut groups = match args
+                .group_by
+                .or(config.group_by)
+                .unwrap_or(args::GroupBy::Links)
+            {
+                args::GroupBy::Links => cargo_fund::invert_mapping(report),
+                args::GroupBy::Owner => cargo_fund::group_by_owner(source_map, report),
+            };
+            let sort = args.sort.or(config.sort).unwrap_or(args::SortKey::Platform);
+            let reverse = args.reverse || config.reverse.unwrap_or(false);
+            sort_groups(&mut groups, sort, reverse, metadata);
+            if let Some(path) = &session.ledger_path {
+                annotate_donations(&mut groups, &ledger::Ledger::load(path)?);
+            }
+            if !args.stream || args.final_tree {
+                if args.stream {
+                    out.push('\n');
                 }
-                link_ix if link_ix == last_link_ix => {
-                    // final link line of many
-                    print!("└─");
+                tree::write_results(&mut out, metadata, report, &groups, options);
+            }
+            if !missing.is_empty() {
+                writeln!(
+                    out,
+                    "\nfound no funding links for {} repositories",
+                    missing.len()
+                )
+                .unwrap();
+                tree::write_tree(&mut out, metadata, &missing, options);
+            }
+            if !unknown.is_empty() {
+                writeln!(
+                    out,
+                    "\nno cached funding links for {} repositories while offline",
+                    unknown.len()
+                )
+                .unwrap();
+                tree::write_tree(&mut out, metadata, &unknown, options);
+            }
+        }
+    }
+    let no_pager = args.no_pager || config.no_pager.unwrap_or(false);
+    let pager = if no_pager || args.watch || format != args::Format::Tree {
+        None
+    } else {
+        Some(env.pager())
+    };
+    session.timings.record("rendering", started.elapsed());
+    match &args.output {
+        Some(path) => {
+            write_output(path, &out)?;
+            if !args.quiet {
+                eprintln!("wrote {}", path.display());
+            }
+        }
+        None => pager::print(&out, pager)?,
+    }
+    if !resolved.rate_limited.is_empty() {
+        eprintln!(
+            "\nwarning: the Github API rate limit ran out before funding links were looked up for \
+             {} packages, so these results are incomplete. The limit {}; run again then, or pass \
+             --wait-for-rate-limit to wait for it.",
+            resolved.rate_limited.len(),
+            cargo_fund::error::describe_reset(resolved.rate_limit_reset)
+        );
+    }
+    if !resolved.failed.is_empty() {
+        eprintln!(
+            "\nwarning: could not resolve funding links for {} sources:",
+            resolved.failed.len()
+        );
+        for (source, error) in &resolved.failed {
+            eprintln!("  {}: {}", source, error);
+        }
+    }
+    if args.timings {
+        let mut out = String::new();
+        session.timings.write(&mut out, args.verbose > 0);
+        eprint!("\n{}", out);
+    }
+    if args.open {
+        let mut groups = cargo_fund::group_by_owner(source_map, &report.allowed());
+        groups.sort_by_cached_key(|group| group.name());
+        browser::prompt_and_open(metadata, &groups, args.open_limit)?;
+    }
+    if args.job_summary || config.job_summary.unwrap_or(false) {
+        match &env.step_summary {
+            Some(path) => {
+                let mut summary = String::new();
+                summary::write_job_summary(&mut summary, metadata, source_map, report);
+                let mut file = std::fs::OpenOptions::new()
+                    .append(true)
+                    .create(true)
+                    .open(path)
+                    .with_context(|| format!("error opening {}", path.display()))?;
+                std::io::Write::write_all(&mut file, summary.as_bytes())
+                    .with_context(|| format!("error writing {}", path.display()))?;
+            }
+            None => tracing::debug!("not writing a job summary outside of GitHub Actions"),
+        }
+    }
+    if args.watch {
+        return watch(
+            &args,
+            config,
+            cx,
+            session.cache_path.as_deref(),
+            &session.lockfile,
+            report.clone(),
+        )
+        .await;
+    }
+    results
+}
+
+/// What every subcommand that reports on dependencies starts from: the configuration, the
+/// dependencies, and the funding links resolved for them.
+struct Session {
+    config: Config,
+    timings: timings::Timings,
+    cx: cargo_fund::Context,
+    responses: Responses,
+    cache_path: Option<PathBuf>,
+    ledger_path: Option<PathBuf>,
+    metadata: Metadata,
+    /// The projects reported on together by `cargo fund all`.
+    projects: Option<all::Projects>,
+    source_map: HashMap<LinkSource, HashSet<PackageId>>,
+    resolved: cargo_fund::Resolved,
+    report: FundReport,
+    options: tree::TreeOptions,
+    format: args::Format,
+    lockfile: PathBuf,
+    fail_if_empty: bool,
+    min_coverage: Option<u8>,
+    /// The clone of the repository given with `--git`, which is removed once the session ends.
+    _clone: Option<remote::Clone>,
+}
+
+impl Session {
+    /// Find the dependencies of the workspace, or with `all`, of the projects it names, resolve
+    /// their funding links, and build the report. With `--git`, the repository is cloned first
+    /// and `args` point at the clone.
+    async fn start(args: &mut args::Args, all: Option<&args::AllArgs>) -> Result<Session, Error> {
+        let timings = timings::Timings::default();
+        let config = load_config(args)?;
+        // a remote repository's own configuration isn't trusted, so it's cloned only after the
+        // configuration is read
+        let clone = match &args.git {
+            Some(url) => {
+                let clone = remote::Clone::shallow(url)?;
+                args.manifest_path = Some(clone.manifest_path());
+                Some(clone)
+            }
+            None => None,
+        };
+        let args = &*args;
+        let cx = context(args, &config, true)?;
+        let responses = load_responses(args, &config);
+        let cache_path = cache_path(args, &config);
+        let mut cache = match &cache_path {
+            Some(path) => cache::Cache::load(path),
+            None => cache::Cache::default(),
+        };
+        let started = Instant::now();
+        let registries = registry_filter(args, &config)?;
+        let (mut metadata, projects) = match all {
+            Some(all) => {
+                let roots = all.roots.iter().chain(&config.roots).cloned();
+                let (metadata, projects) = all::metadata(args, &roots.collect::<Vec<_>>())?;
+                (metadata, Some(projects))
+            }
+            None => {
+                let metadata = metadata::get(args)
+                    .map_err(|e| exit::with_status(exit::Status::Metadata, e))?;
+                if let Err(e) = all::remember(&metadata.workspace_root) {
+                    tracing::debug!("could not remember workspace: {:#}", e);
                 }
-                _ => print!("  "),
+                (metadata, None)
             }
-            println!(" {:?}", link.uri);
+        };
+        filter_packages(&mut metadata, registries.as_ref(), args)?;
+        retain_sources(&mut metadata, args, &config);
+        timings.record("metadata", started.elapsed());
+        let started = Instant::now();
+        let mut source_map = cargo_fund::collect_sources(&cx, &metadata)?;
+        ignore_sources(&mut source_map, &metadata, &config);
+        timings.record("collecting sources", started.elapsed());
+        // streamed results show progress well enough, and the spinner would garble them
+        let progress = Progress::new(!args.quiet && !args.stream);
+        let format = args.format(&config);
+        if args.stream && format != args::Format::Tree {
+            bail!("--stream can only be used with the tree format");
         }
-        let last_pkg_ix = pkgs.len() - 1;
-        for (pkg_ix, pkg) in pkgs.into_iter().enumerate() {
-            if mapping_ix < last_mapping_ix {
-                print!("│    ");
-            } else {
-                print!("     ");
+        let options = tree_options(args, &config, &metadata)?;
+        let stream = |source: &LinkSource, pkgs: &HashSet<PackageId>, links: &[Link]| {
+            let provenance = BTreeSet::from([source.provenance()]);
+            let policy = config.disallowed_links.unwrap_or_default();
+            let links = links
+                .iter()
+                .map(|link| {
+                    let mut link = FundingLink::new(link, provenance.clone());
+                    link.disallowed = !config.platform_allowed(&link.platform);
+                    link
+                })
+                .filter(|link| !(link.disallowed && policy == DisallowedLinks::Hide))
+                .collect::<BTreeSet<_>>();
+            let pkgs = pkgs
+                .iter()
+                .filter(|&pkg| !config.suppressed(&metadata[pkg].name))
+                .cloned()
+                .collect::<BTreeSet<_>>();
+            if links.is_empty() || pkgs.is_empty() {
+                return;
             }
-            if pkg_ix == last_pkg_ix {
-                print!("└─");
-            } else {
-                print!("├─");
+            let group = Group {
+                heading: None,
+                links,
+                pkgs,
+            };
+            let mut out = String::new();
+            tree::write_tree(&mut out, &metadata, &[group], &options);
+            print!("{}", out);
+            let _ = std::io::Write::flush(&mut std::io::stdout());
+        };
+        let events = |event: &Event<'_>| {
+            progress.handle(event);
+            timings.handle(event);
+            match event {
+                Event::LinksFound {
+                    source,
+                    pkgs,
+                    links,
+                } if args.stream && !links.is_empty() => stream(source, pkgs, links),
+                _ => (),
+            }
+        };
+        let lockfile = metadata.workspace_root.join("Cargo.lock");
+        // when offline, stale results are better than none, and when the lockfile is unchanged,
+        // the last run's results are still good enough
+        // the lockfile of one workspace says nothing about the others when reporting on them all
+        let incremental = args.incremental && projects.is_none();
+        let ttl = if args.offline || (incremental && cache.lockfile_unchanged(&lockfile)) {
+            tracing::debug!("using cached results regardless of age");
+            Duration::MAX
+        } else {
+            args.cache_ttl
+        };
+        let started = Instant::now();
+        let resolved =
+            cargo_fund::resolve_links(&cx, &source_map, &mut cache, ttl, &events).await?;
+        timings.record("resolving", started.elapsed());
+        let complete = resolved.failed.is_empty()
+            && resolved.unknown.is_empty()
+            && resolved.rate_limited.is_empty();
+        if complete && projects.is_none() {
+            cache.insert_lockfile(&lockfile);
+        }
+        if let Some(path) = cache_path.as_ref().filter(|_| !args.offline) {
+            if let Err(e) = cache.save(path) {
+                tracing::warn!("could not save cache: {:#}", Error::from(e));
             }
-            let pkg = &metadata[&pkg];
-            println!(" {} {}", pkg.name, pkg.version);
         }
+        let mut report = FundReport::new(&metadata, &resolved);
+        apply_package_overrides(&mut report, &metadata, &config)?;
+        apply_platform_policy(&mut report, &config);
+        report.count(&metadata, &resolved);
+        if let Some(path) = &args.save_baseline {
+            let mut baseline = report.clone();
+            baseline.stamp(lockfile.as_ref());
+            let json = serde_json::to_string_pretty(&baseline)? + "\n";
+            std::fs::write(path, json)
+                .with_context(|| format!("error writing {}", path.display()))?;
+        }
+        Ok(Session {
+            timings,
+            cx,
+            responses,
+            cache_path,
+            ledger_path: ledger_path(args, &config),
+            metadata,
+            projects,
+            source_map,
+            resolved,
+            report,
+            options,
+            format,
+            lockfile,
+            fail_if_empty: args.fail_if_empty || config.fail_if_empty.unwrap_or(false),
+            min_coverage: args.min_coverage.or(config.min_coverage),
+            config,
+            _clone: clone,
+        })
+    }
+
+    /// Fail if the report breaks any of the limits that were asked for.
+    fn check_results(&self) -> Result<(), Error> {
+        check_results(&self.report, self.fail_if_empty, self.min_coverage)
+    }
+
+    /// The fundable maintainers on allowed platforms, each with the packages they maintain.
+    fn owner_groups(&self) -> Vec<Group> {
+        cargo_fund::group_by_owner(&self.source_map, &self.report.allowed())
     }
 }
 
-#[tokio::main]
-async fn main() -> Result<(), Error> {
-    let env = envy::from_env::<args::Env>()?;
-    let Opts::Fund(args) = Opts::parse();
-    initialize_globals(&env, &args)?;
-    let metadata = metadata::get(&args)?;
-    let source_map = collect_sources(&metadata)?;
-    let resolved = resolve_links(&source_map).await?;
-    let num_found = resolved.len();
-    let inverted = invert_mapping(resolved);
-    print_results(&metadata, &inverted, num_found);
-    Ok(())
+/// Resolve the funding links again whenever the lockfile changes, printing what changed since the
+/// last results. Queries are answered from the cache where possible, so only new dependencies are
+/// looked up. This only returns if something fails.
+async fn watch(
+    args: &args::Args,
+    config: &Config,
+    cx: &cargo_fund::Context,
+    cache_path: Option<&std::path::Path>,
+    lockfile: &std::path::Path,
+    mut report: FundReport,
+) -> Result<(), Error> {
+    let modified = || std::fs::metadata(lockfile).and_then(|m| m.modified()).ok();
+    let mut last_modified = modified();
+    eprintln!("\nwatching {} for changes", lockfile.display());
+    loop {
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        let current = modified();
+        if current == last_modified {
+            continue;
+        }
+        last_modified = current;
+        let mut metadata =
+            metadata::get(args).map_err(|e| exit::with_status(exit::Status::Metadata, e))?;
+        let registries = registry_filter(args, config)?;
+        filter_packages(&mut metadata, registries.as_ref(), args)?;
+        retain_sources(&mut metadata, args, config);
+        let mut source_map = cargo_fund::collect_sources(cx, &metadata)?;
+        ignore_sources(&mut source_map, &metadata, config);
+        let mut cache = match cache_path {
+            Some(path) => cache::Cache::load(path),
+            None => cache::Cache::default(),
+        };
+        let ttl = if args.offline {
+            Duration::MAX
+        } else {
+            args.cache_ttl
+        };
+        let resolved = cargo_fund::resolve_links(cx, &source_map, &mut cache, ttl, &|_| ()).await?;
+        if let Some(path) = cache_path.filter(|_| !args.offline) {
+            if let Err(e) = cache.save(path) {
+                tracing::warn!("could not save cache: {:#}", Error::from(e));
+            }
+        }
+        let mut next = FundReport::new(&metadata, &resolved);
+        apply_package_overrides(&mut next, &metadata, config)?;
+        apply_platform_policy(&mut next, config);
+        next.count(&metadata, &resolved);
+        let diff = next.diff(&report);
+        if diff.is_empty() {
+            eprintln!(
+                "\n{} changed, but the funding links didn't",
+                lockfile.display()
+            );
+        } else {
+            let mut out = String::new();
+            write_diff(&mut out, &diff);
+            println!("\n{} changed:", lockfile.display());
+            print!("{}", out);
+        }
+        for (source, error) in &resolved.failed {
+            eprintln!(
+                "warning: could not resolve funding links for {}: {}",
+                source, error
+            );
+        }
+        report = next;
+    }
+}
+
+/// Write `contents` to `path` by renaming a temporary file into place, so that nothing ever reads a
+/// partly written file. The file's directory is created if needed.
+fn write_output(path: &Path, contents: &str) -> Result<(), Error> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("error creating {}", dir.display()))?;
+    }
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(format!(".{}.tmp", std::process::id()));
+    let tmp = PathBuf::from(tmp);
+    std::fs::write(&tmp, contents).with_context(|| format!("error writing {}", tmp.display()))?;
+    std::fs::rename(&tmp, path).with_context(|| format!("error writing {}", path.display()))
+}
+
+/// Fail if the results break any of the limits that were asked for.
+fn check_results(
+    report: &FundReport,
+    fail_if_empty: bool,
+    min_coverage: Option<u8>,
+) -> Result<(), Error> {
+    if fail_if_empty && report.packages.is_empty() {
+        bail!(
+            "found no funding links for any of {} dependencies",
+            report.considered()
+        );
+    }
+    check_coverage(report, min_coverage)
+}
+
+/// The percentage of dependencies with funding links on allowed platforms.
+fn coverage(report: &FundReport) -> f64 {
+    if report.considered() == 0 {
+        return 100.0;
+    }
+    let funded = report
+        .packages
+        .iter()
+        .filter(|pkg| pkg.links.iter().any(|link| !link.disallowed))
+        .count();
+    funded as f64 * 100.0 / report.considered() as f64
+}
+
+/// Fail with the policy status if fewer than `min_coverage` percent of dependencies have funding
+/// links.
+fn check_coverage(report: &FundReport, min_coverage: Option<u8>) -> Result<(), Error> {
+    let coverage = coverage(report);
+    match min_coverage {
+        Some(min_coverage) if coverage < min_coverage as f64 => Err(exit::with_status(
+            exit::Status::Policy,
+            anyhow!(
+                "only {:.1}% of {} dependencies have funding links, below the minimum of {}%",
+                coverage,
+                report.considered(),
+                min_coverage
+            ),
+        )),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use cargo_metadata::Metadata;
+
+    /// Metadata for a workspace whose one member depends on `packages`, given as name, version,
+    /// and repository, like the library's own test helper.
+    pub(crate) fn metadata(packages: &[(&str, &str, Option<&str>)]) -> Metadata {
+        let package = |name: &str, version: &str, repository: Option<&str>| {
+            serde_json::json!({
+                "name": name,
+                "version": version,
+                "id": format!("{} {}", name, version),
+                "repository": repository,
+                "dependencies": [],
+                "targets": [],
+                "features": {},
+                "manifest_path": format!("/{}/Cargo.toml", name),
+            })
+        };
+        let mut all = vec![package("ws", "0.1.0", None)];
+        all.extend(
+            packages
+                .iter()
+                .map(|(name, version, repository)| package(name, version, *repository)),
+        );
+        serde_json::from_value(serde_json::json!({
+            "packages": all,
+            "workspace_members": ["ws 0.1.0"],
+            "resolve": null,
+            "workspace_root": "/ws",
+            "target_directory": "/ws/target",
+            "version": 1,
+        }))
+        .unwrap()
+    }
 }
@@ -0,0 +1,131 @@
+//! `cargo fund man`: a manual page for distributions to ship with `cargo fund`.
+//!
+//! The options and subcommands are rendered from their definitions, with the environment variable
+//! for each, so that the page can't fall behind the flags. The sections clap doesn't know about,
+//! such as the configuration files and exit statuses, are written out here in roff.
+
+use crate::args::Opts;
+use clap::CommandFactory;
+use std::io::Write;
+
+const ENVIRONMENT: &str = r#".SH ENVIRONMENT
+Most options can also be set with the environment variable listed with them.
+.TP
+\fBCARGO_FUND_PAGER\fR, \fBPAGER\fR
+The pager for long tree output on a terminal, preferring \fBCARGO_FUND_PAGER\fR.
+.TP
+\fBNO_COLOR\fR
+When set to anything but an empty string, the output isn't colored unless \fB\-\-color always\fR
+is given.
+.TP
+\fBGITHUB_STEP_SUMMARY\fR
+The file GitHub Actions shows as the job summary, written to with \fB\-\-job\-summary\fR.
+"#;
+
+const FILES: &str = r#".SH FILES
+.TP
+\fI.cargo\-fund.toml\fR
+The workspace's configuration, in its root directory. The same options can instead be kept in
+the \fB[workspace.metadata.fund]\fR table of the workspace's \fICargo.toml\fR.
+.TP
+\fI~/.config/cargo\-fund/config.toml\fR
+The user's configuration, which the workspace's takes precedence over. This is in the platform's
+configuration directory, so it differs on macOS and Windows.
+.TP
+\fI~/.config/cargo\-fund/token\fR
+The Github API token kept by \fBcargo fund login\fR.
+.TP
+\fI~/.cache/cargo\-fund/links.json\fR
+The cache of funding links, unless \fB\-\-cache\-dir\fR is given.
+.TP
+\fI~/.local/share/cargo\-fund/ledger.toml\fR
+The donations recorded with \fBcargo fund record\fR, unless \fB\-\-ledger\fR is given.
+.TP
+\fIfunding\-decisions.toml\fR
+The decisions checked by \fBcargo fund check\fR, in the workspace's root directory.
+.TP
+\fIfund\-plan.toml\fR
+The committed plan compared by \fBcargo fund plan \-\-check\fR, in the workspace's root directory.
+"#;
+
+const EXIT_STATUS: &str = r#".SH "EXIT STATUS"
+.TP
+\fB0\fR
+Success.
+.TP
+\fB1\fR
+Any failure not listed below, including invalid arguments.
+.TP
+\fB2\fR
+The Github API token is missing, invalid, or lacks scopes.
+.TP
+\fB3\fR
+The Github API rate limit ran out.
+.TP
+\fB4\fR
+\fBcargo metadata\fR failed, or its output couldn't be read.
+.TP
+\fB5\fR
+A policy failed, such as a maintainer without a decision.
+"#;
+
+/// Escape text for roff, where backslashes start escapes and plain hyphens may become dashes.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\e").replace('-', "\\-")
+}
+
+/// Write the manual page for `cargo fund`, in roff.
+pub fn write(out: &mut dyn Write) -> std::io::Result<()> {
+    let fund = Opts::command()
+        .find_subcommand("fund")
+        .expect("cargo fund is a subcommand of cargo")
+        .clone()
+        .name("cargo-fund")
+        .bin_name("cargo fund")
+        .version(env!("CARGO_PKG_VERSION"))
+        .disable_version_flag(true);
+    let man = clap_mangen::Man::new(fund.clone());
+    man.render_title(out)?;
+    man.render_name_section(out)?;
+    man.render_synopsis_section(out)?;
+    man.render_description_section(out)?;
+    man.render_options_section(out)?;
+    // clap_mangen would refer to a page for each subcommand, which aren't written; summarize them
+    // here instead
+    writeln!(out, ".SH SUBCOMMANDS")?;
+    for command in fund
+        .get_subcommands()
+        .filter(|command| !command.is_hide_set())
+    {
+        let about = command
+            .get_about()
+            .map_or_else(String::new, |about| about.to_string());
+        writeln!(
+            out,
+            ".TP\n\\fB{}\\fR\n{}",
+            escape(command.get_name()),
+            escape(&about)
+        )?;
+    }
+    for section in [ENVIRONMENT, FILES, EXIT_STATUS] {
+        out.write_all(section.as_bytes())?;
+    }
+    man.render_version_section(out)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn writes() {
+        let mut page = vec![];
+        write(&mut page).unwrap();
+        let page = String::from_utf8(page).unwrap();
+        assert!(page.contains(r"\fB\-\-wait\-for\-rate\-limit\fR"));
+        assert!(page.contains("CARGO_FUND_GITHUB_API_TOKEN"));
+        assert!(page.contains(".SH \"EXIT STATUS\""));
+        assert!(!page.contains("cargo\\-fund\\-man"));
+    }
+}
@@ -3,11 +3,134 @@
 use crate::args::Args;
 use anyhow::{anyhow, Context, Error};
 use cargo_metadata::Metadata;
+use std::collections::HashSet;
 use std::env;
 use std::ffi::OsString;
+use std::path::Path;
 use std::process::{Command, Stdio};
 
 pub fn get(args: &Args) -> Result<Metadata, Error> {
+    get_at(args, args.manifest_path.as_deref())
+}
+
+/// Leave git dependencies out of `metadata` unless `include_git`, and path dependencies outside
+/// the workspace if `exclude_path`, so that they are neither looked up nor counted. Workspace
+/// members are kept, as the roots of the dependency graph.
+pub fn retain_sources(metadata: &mut Metadata, include_git: bool, exclude_path: bool) {
+    let members = &metadata.workspace_members;
+    metadata.packages.retain(|pkg| {
+        let source = pkg.source.as_ref().map(|source| source.to_string());
+        members.contains(&pkg.id) || keeps_source(source.as_deref(), include_git, exclude_path)
+    });
+}
+
+/// Whether to keep a package from `source`, as `cargo metadata` writes it, or `None` for a path
+/// dependency.
+fn keeps_source(source: Option<&str>, include_git: bool, exclude_path: bool) -> bool {
+    match source {
+        None => !exclude_path,
+        Some(source) if source.starts_with("git+") => include_git,
+        Some(_) => true,
+    }
+}
+
+/// Leave out the dependencies that none of `patterns` match any author of, so that only crates by
+/// particular maintainers are looked up and counted. Workspace members are kept, as the roots of
+/// the dependency graph.
+pub fn retain_authors(metadata: &mut Metadata, patterns: &[String]) {
+    if patterns.is_empty() {
+        return;
+    }
+    let members = &metadata.workspace_members;
+    metadata.packages.retain(|pkg| {
+        members.contains(&pkg.id)
+            || pkg.authors.iter().any(|author| {
+                patterns
+                    .iter()
+                    .any(|pattern| author_matches(author, pattern))
+            })
+    });
+}
+
+/// Whether `pattern` matches an entry of a package's `authors`, such as
+/// `David Tolnay <dtolnay@gmail.com>`: whether it appears in it, ignoring case, so that a name,
+/// email address, or part of either will do.
+fn author_matches(author: &str, pattern: &str) -> bool {
+    author.to_lowercase().contains(&pattern.to_lowercase())
+}
+
+/// The name and version of every package in the workspace's `Cargo.lock` as of the git revision
+/// `rev`, or none if it had no lockfile then.
+pub fn locked_at(metadata: &Metadata, rev: &str) -> Result<HashSet<(String, String)>, Error> {
+    let root = &metadata.workspace_root;
+    output(
+        Command::new("git")
+            .arg("-C")
+            .arg(root)
+            .args(["rev-parse", "--verify", "--quiet"])
+            .arg(format!("{}^{{commit}}", rev)),
+        "git rev-parse",
+    )
+    .map_err(|_| {
+        crate::diagnostic::with_help(
+            anyhow!(
+                "`{}` is not a revision of the repository at {}",
+                rev,
+                root.display()
+            ),
+            "--since, or CARGO_FUND_SINCE",
+            "fetch it first, such as with `git fetch origin main`; in GitHub Actions, check out \
+             with `fetch-depth: 0`",
+        )
+    })?;
+    let lockfile = Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .arg("show")
+        .arg(format!("{}:./Cargo.lock", rev))
+        .stderr(Stdio::null())
+        .output()
+        .context("error running git show")?;
+    if !lockfile.status.success() {
+        tracing::info!("no Cargo.lock at {}, so every dependency is new", rev);
+        return Ok(HashSet::new());
+    }
+    locked_packages(&String::from_utf8_lossy(&lockfile.stdout))
+        .with_context(|| format!("error parsing Cargo.lock at {}", rev))
+}
+
+/// The name and version of every package in `lockfile`, the contents of a `Cargo.lock`.
+fn locked_packages(lockfile: &str) -> Result<HashSet<(String, String)>, toml::de::Error> {
+    #[derive(serde::Deserialize)]
+    struct Lockfile {
+        #[serde(default)]
+        package: Vec<Locked>,
+    }
+    #[derive(serde::Deserialize)]
+    struct Locked {
+        name: String,
+        version: String,
+    }
+    let lockfile: Lockfile = toml::from_str(lockfile)?;
+    Ok(lockfile
+        .package
+        .into_iter()
+        .map(|pkg| (pkg.name, pkg.version))
+        .collect())
+}
+
+/// Leave out the dependencies in `locked`, so that only those added since are looked up and
+/// counted. Workspace members are kept, as the roots of the dependency graph.
+pub fn retain_unlocked(metadata: &mut Metadata, locked: &HashSet<(String, String)>) {
+    let members = &metadata.workspace_members;
+    metadata.packages.retain(|pkg| {
+        members.contains(&pkg.id) || !locked.contains(&(pkg.name.clone(), pkg.version.to_string()))
+    });
+}
+
+/// Get the metadata for the workspace of the manifest at `manifest_path`, or of the current
+/// directory.
+pub fn get_at(args: &Args, manifest_path: Option<&Path>) -> Result<Metadata, Error> {
     let cargo = env::var_os("CARGO").unwrap_or_else(|| OsString::from("cargo"));
 
     let mut command = Command::new(cargo);
@@ -17,7 +140,7 @@ pub fn get(args: &Args) -> Result<Metadata, Error> {
         command.arg("-q");
     }
 
-    if let Some(path) = &args.manifest_path {
+    if let Some(path) = manifest_path {
         command.arg("--manifest-path").arg(path);
     }
 
@@ -25,8 +148,12 @@ pub fn get(args: &Args) -> Result<Metadata, Error> {
         command.arg("-v");
     }
 
-    if let Some(color) = &args.color {
-        command.arg("--color").arg(color);
+    if let Some(color) = args.color {
+        command.arg("--color").arg(color.as_str());
+    }
+
+    if args.offline {
+        command.arg("--offline");
     }
 
     for flag in &args.unstable_flags {
@@ -50,3 +177,64 @@ fn output(command: &mut Command, job: &str) -> Result<String, Error> {
 
     String::from_utf8(output.stdout).with_context(|| format!("error parsing {} output", job))
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn keeps_sources() {
+        let registry = Some("registry+https://github.com/rust-lang/crates.io-index");
+        let git = Some("git+https://github.com/example/repo#abc123");
+        assert!(keeps_source(registry, false, true));
+        assert!(!keeps_source(git, false, false));
+        assert!(keeps_source(git, true, false));
+        assert!(keeps_source(None, false, false));
+        assert!(!keeps_source(None, false, true));
+    }
+
+    #[test]
+    fn matches_authors() {
+        let author = "David Tolnay <dtolnay@gmail.com>";
+        assert!(author_matches(author, "dtolnay"));
+        assert!(author_matches(author, "david tolnay"));
+        assert!(!author_matches(author, "seanmonstar"));
+    }
+
+    #[test]
+    fn leaves_out_locked_packages() {
+        let lockfile = r#"
+            version = 3
+
+            [[package]]
+            name = "ws"
+            version = "0.1.0"
+            dependencies = ["old"]
+
+            [[package]]
+            name = "old"
+            version = "1.0.0"
+            source = "registry+https://github.com/rust-lang/crates.io-index"
+            checksum = "0000000000000000000000000000000000000000000000000000000000000000"
+        "#;
+        let locked = locked_packages(lockfile).unwrap();
+        assert_eq!(locked.len(), 2);
+        assert!(locked.contains(&("old".to_string(), "1.0.0".to_string())));
+        assert!(locked_packages("version = 3").unwrap().is_empty());
+        assert!(locked_packages("[[package]]\nname = 1").is_err());
+
+        let mut metadata = crate::test::metadata(&[
+            ("old", "1.0.0", None),
+            ("old", "2.0.0", None),
+            ("new", "1.0.0", None),
+        ]);
+        retain_unlocked(&mut metadata, &locked);
+        // the workspace's own packages are kept even if they were locked, and a crate upgraded
+        // since counts as new
+        let ids = metadata.packages.iter().map(|pkg| pkg.id.repr.as_str());
+        assert_eq!(
+            ids.collect::<Vec<_>>(),
+            ["ws 0.1.0", "old 2.0.0", "new 1.0.0"]
+        );
+    }
+}
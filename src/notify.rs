@@ -0,0 +1,141 @@
+//! `cargo fund notify`: posting the results to a Slack or Discord webhook, so that a scheduled run
+//! can tell a team's channel about dependencies that became fundable.
+//!
+//! Slack and Discord both render a little Markdown and link bare URLs, so one message suits both;
+//! only the field the text goes in differs. Discord rejects messages over 2000 characters, so long
+//! messages are cut short.
+
+use anyhow::{Context, Error};
+use cargo_fund::report::FundReport;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// The most characters Discord accepts in a message.
+const DISCORD_LIMIT: usize = 2000;
+
+/// The most groups of links listed in a summary.
+const MAX_GROUPS: usize = 20;
+
+/// The most packages named for each group of links in a summary.
+const MAX_PACKAGES: usize = 3;
+
+/// Whether `webhook` is a Discord webhook, rather than a Slack one or one that takes the same
+/// payload.
+fn is_discord(webhook: &str) -> bool {
+    let host = webhook.parse::<http::Uri>().ok();
+    let host = host.as_ref().and_then(|uri| uri.host()).unwrap_or_default();
+    host == "discord.com" || host == "discordapp.com" || host.ends_with(".discord.com")
+}
+
+/// The name to announce the workspace by: its root directory's name.
+fn workspace_name(report: &FundReport) -> String {
+    report.workspace_root.file_name().map_or_else(
+        || report.workspace_root.display().to_string(),
+        |name| name.to_string_lossy().into_owned(),
+    )
+}
+
+/// A summary of `report`: how many dependencies can be funded, and the links to fund them with.
+pub fn summary(report: &FundReport) -> String {
+    let mut message = String::new();
+    writeln!(
+        message,
+        "*cargo fund* for `{}`: found funding links for {} out of {} dependencies",
+        workspace_name(report),
+        report.packages.len(),
+        report.considered()
+    )
+    .unwrap();
+    let names = report
+        .packages
+        .iter()
+        .map(|pkg| (&pkg.id, pkg.name.as_str()))
+        .collect::<HashMap<_, _>>();
+    let groups = cargo_fund::invert_mapping(report);
+    for group in groups.iter().take(MAX_GROUPS) {
+        let urls = group.links.iter().map(|link| link.url.as_str());
+        let mut packages = group
+            .pkgs
+            .iter()
+            .take(MAX_PACKAGES)
+            .filter_map(|id| names.get(id).copied())
+            .collect::<Vec<_>>()
+            .join(", ");
+        if group.pkgs.len() > MAX_PACKAGES {
+            write!(packages, " and {} more", group.pkgs.len() - MAX_PACKAGES).unwrap();
+        }
+        writeln!(
+            message,
+            "• {} ({})",
+            urls.collect::<Vec<_>>().join(", "),
+            packages
+        )
+        .unwrap();
+    }
+    if groups.len() > MAX_GROUPS {
+        writeln!(message, "…and {} more", groups.len() - MAX_GROUPS).unwrap();
+    }
+    message
+}
+
+/// A message announcing the changes since a baseline, already written out as `changes`.
+pub fn changes(report: &FundReport, changes: &str) -> String {
+    format!(
+        "*cargo fund* for `{}`: changes since the baseline\n```\n{}```\n",
+        workspace_name(report),
+        changes
+    )
+}
+
+/// Post `message` to `webhook`.
+pub async fn post(client: &reqwest::Client, webhook: &str, message: &str) -> Result<(), Error> {
+    let payload = if is_discord(webhook) {
+        serde_json::json!({ "content": shorten(message, DISCORD_LIMIT) })
+    } else {
+        serde_json::json!({ "text": message })
+    };
+    client
+        .post(webhook)
+        .json(&payload)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        // the webhook's URL is its secret, so it's left out of the error
+        .map_err(|e| e.without_url())
+        .context("error posting to the webhook")?;
+    Ok(())
+}
+
+/// Cut `message` short to at most `limit` characters, keeping a code block closed.
+fn shorten(message: &str, limit: usize) -> String {
+    if message.chars().count() <= limit {
+        return message.to_string();
+    }
+    let closing = if message.contains("```") { "\n```" } else { "" };
+    let keep = limit - "\n…".chars().count() - closing.chars().count();
+    let kept = message.chars().take(keep).collect::<String>();
+    // cut at the end of a line, so that no link is left half written
+    let kept = kept.rfind('\n').map_or(kept.as_str(), |ix| &kept[..ix]);
+    format!("{}\n…{}", kept, closing)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn recognizes_discord() {
+        assert!(is_discord("https://discord.com/api/webhooks/1/abc"));
+        assert!(is_discord("https://ptb.discord.com/api/webhooks/1/abc"));
+        assert!(!is_discord("https://hooks.slack.com/services/T/B/abc"));
+    }
+
+    #[test]
+    fn shortens_long_messages() {
+        let message = format!("heading\n```\n{}```\n", "https://ko-fi.com/x\n".repeat(200));
+        let short = shorten(&message, DISCORD_LIMIT);
+        assert!(short.chars().count() <= DISCORD_LIMIT);
+        assert!(short.ends_with("https://ko-fi.com/x\n…\n```"));
+        assert_eq!(shorten("short", DISCORD_LIMIT), "short");
+    }
+}
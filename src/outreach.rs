@@ -0,0 +1,113 @@
+//! `cargo fund outreach`: messages asking maintainers without funding links to add them.
+//!
+//! One message is written for each repository that was found but has no funding links, naming the
+//! packages from it and the workspace's packages that depend on them. A custom template can use
+//! these placeholders:
+//!
+//! - `{repository}`: the repository's URL
+//! - `{packages}`: the packages from the repository
+//! - `{dependents}`: the workspace's packages that depend on them
+//! - `{project}`: the workspace's name
+
+use crate::args::OutreachKind;
+use crate::impact;
+use cargo_fund::Group;
+use cargo_metadata::Metadata;
+use std::collections::BTreeSet;
+
+const ISSUE: &str = "\
+# Consider adding a FUNDING.yml file
+
+Hi! {project} depends on {packages} from this repository, through {dependents}, and we would like \
+to support your work financially. We couldn't find a way to do that, though.
+
+If you accept donations, would you consider adding a `.github/FUNDING.yml` file? GitHub then shows \
+a Sponsor button on the repository, and tools like `cargo fund` can point users of your crates to \
+it: https://docs.github.com/en/repositories/managing-your-repositorys-settings-and-features/customizing-your-repository/displaying-a-sponsor-button-in-your-repository
+
+Thank you for your work!
+";
+
+const EMAIL: &str = "\
+Subject: Supporting {packages}
+
+Hello,
+
+{project} depends on {packages}, from {repository}, through {dependents}. We would like to support \
+your work financially, but couldn't find a way to do so.
+
+If you accept donations, would you consider adding a `.github/FUNDING.yml` file to the \
+repository? GitHub then shows a Sponsor button on it, and tools like `cargo fund` can point users \
+of your crates to it.
+
+Thank you for your work!
+";
+
+/// The built-in template for `kind`.
+pub fn template(kind: OutreachKind) -> &'static str {
+    match kind {
+        OutreachKind::Issue => ISSUE,
+        OutreachKind::Email => EMAIL,
+    }
+}
+
+/// Join names as in prose, like `a, b, and c`.
+fn list(names: &BTreeSet<String>) -> String {
+    let names = names
+        .iter()
+        .map(|name| format!("`{}`", name))
+        .collect::<Vec<_>>();
+    match names.as_slice() {
+        [] => String::new(),
+        [one] => one.clone(),
+        [a, b] => format!("{} and {}", a, b),
+        [rest @ .., last] => format!("{}, and {}", rest.join(", "), last),
+    }
+}
+
+/// Fill in `template` for each repository in `groups`, as given by
+/// [`cargo_fund::missing_groups`].
+pub fn messages(metadata: &Metadata, groups: &[Group], template: &str) -> Vec<String> {
+    let members = metadata
+        .workspace_members
+        .iter()
+        .map(|member| (&metadata[member].name, impact::depths(metadata, &[member])))
+        .collect::<Vec<_>>();
+    let project = metadata
+        .workspace_root
+        .file_name()
+        .map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+    groups
+        .iter()
+        .map(|group| {
+            let packages = group
+                .pkgs
+                .iter()
+                .map(|id| metadata[id].name.clone())
+                .collect::<BTreeSet<_>>();
+            let dependents = members
+                .iter()
+                .filter(|(_, depths)| group.pkgs.iter().any(|id| depths.contains_key(id)))
+                .map(|(name, _)| name.to_string())
+                .collect::<BTreeSet<_>>();
+            template
+                .replace("{repository}", group.heading.as_deref().unwrap_or_default())
+                .replace("{packages}", &list(&packages))
+                .replace("{dependents}", &list(&dependents))
+                .replace("{project}", &project)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn lists() {
+        let names = |names: &[&str]| names.iter().map(|s| s.to_string()).collect();
+        assert_eq!(list(&names(&["a"])), "`a`");
+        assert_eq!(list(&names(&["b", "a"])), "`a` and `b`");
+        assert_eq!(list(&names(&["a", "b", "c"])), "`a`, `b`, and `c`");
+    }
+}
@@ -0,0 +1,59 @@
+//! Paging long output, in the manner of `git` and `cargo`.
+
+use anyhow::{Context, Error};
+use std::io::{IsTerminal, Write};
+use std::process::{Command, Stdio};
+use tracing::debug;
+
+/// The pager used when neither `CARGO_FUND_PAGER` nor `PAGER` is set.
+pub const DEFAULT_PAGER: &str = "less -R";
+
+/// Print `output` to stdout, piping it through `pager` if stdout is a terminal and the output is
+/// too tall to fit on it.
+///
+/// An empty pager or `cat` disables paging, as does passing `None`. If the pager can't be started,
+/// the output is printed directly.
+pub fn print(output: &str, pager: Option<&str>) -> Result<(), Error> {
+    let mut words = pager.unwrap_or_default().split_whitespace();
+    let program = match words.next() {
+        Some(program) if program != "cat" && fits_terminal(output) == Some(false) => program,
+        _ => {
+            print!("{}", output);
+            return Ok(());
+        }
+    };
+    let mut child = match Command::new(program)
+        .args(words)
+        .stdin(Stdio::piped())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(e) => {
+            debug!(
+                "could not start pager {:?}; printing directly: {}",
+                program, e
+            );
+            print!("{}", output);
+            return Ok(());
+        }
+    };
+    let mut stdin = child.stdin.take().expect("pager stdin is piped");
+    match stdin.write_all(output.as_bytes()) {
+        // the user quit the pager before reading everything
+        Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => (),
+        res => res.context("error writing to pager")?,
+    }
+    drop(stdin);
+    child.wait().context("error waiting for pager")?;
+    Ok(())
+}
+
+/// Whether `output` fits on the terminal attached to stdout, or `None` if stdout is not a
+/// terminal.
+fn fits_terminal(output: &str) -> Option<bool> {
+    if !std::io::stdout().is_terminal() {
+        return None;
+    }
+    let (_, terminal_size::Height(height)) = terminal_size::terminal_size()?;
+    Some(output.lines().count() < height as usize)
+}
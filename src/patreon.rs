@@ -0,0 +1,154 @@
+//! `--show-patrons`: checking the campaign behind each Patreon link, to show how many patrons it
+//! has and to leave out the links of deleted campaigns, which FUNDING.yml files go on referencing
+//! long after.
+//!
+//! Patreon's API needs an OAuth client even to read public campaigns, so each campaign's page is
+//! requested instead, and the patron count is read from the data embedded in it. A page that's
+//! gone means the campaign was deleted; one whose count can't be found is kept, without a count.
+
+use crate::responses::Responses;
+use anyhow::{anyhow, Context, Error};
+use cargo_fund::report::{FundReport, FundingLink};
+use futures_util::StreamExt;
+use reqwest::StatusCode;
+use std::collections::{BTreeSet, HashMap};
+
+/// The number of campaigns to look up at once.
+const JOBS: usize = 8;
+
+/// What became of the campaign behind a Patreon link.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Campaign {
+    /// The campaign is still running, with this many patrons if the page says.
+    Active {
+        patrons: Option<u64>,
+    },
+    Deleted,
+}
+
+/// Look up the campaign behind each Patreon link in `report`, by the link's URL. Campaigns that
+/// couldn't be looked up are left out, with a warning.
+pub async fn campaigns(
+    client: &reqwest::Client,
+    responses: &Responses,
+    report: &FundReport,
+) -> HashMap<String, Campaign> {
+    let urls: BTreeSet<&str> = report
+        .packages
+        .iter()
+        .flat_map(|pkg| &pkg.links)
+        .filter(|link| link.platform == "PATREON")
+        .map(|link| link.url.as_str())
+        .collect();
+    futures_util::stream::iter(urls)
+        .map(|url| async move { (url, campaign(client, responses, url).await) })
+        .buffered(JOBS)
+        .filter_map(|(url, campaign)| async move {
+            match campaign {
+                Ok(campaign) => Some((url.to_string(), campaign)),
+                Err(e) => {
+                    tracing::warn!("{:#}", e);
+                    None
+                }
+            }
+        })
+        .collect()
+        .await
+}
+
+/// Look up the campaign whose page is at `url`. Only the patron count is kept of the page.
+async fn campaign(
+    client: &reqwest::Client,
+    responses: &Responses,
+    url: &str,
+) -> Result<Campaign, Error> {
+    let context = || format!("error looking up the Patreon campaign at {}", url);
+    let keep = |page: String| patron_count(&page).map_or_else(String::new, |n| n.to_string());
+    let resp = responses
+        .get(client, url, keep)
+        .await
+        .map_err(|e| e.without_url())
+        .with_context(context)?;
+    match resp.status {
+        StatusCode::NOT_FOUND | StatusCode::GONE => Ok(Campaign::Deleted),
+        status if !status.is_success() => {
+            Err(anyhow!("Patreon answered with {}", status)).with_context(context)
+        }
+        _ => Ok(Campaign::Active {
+            patrons: resp.body.parse().ok(),
+        }),
+    }
+}
+
+/// Add each campaign's patron count to the links to it in `report`, and leave out the links to
+/// deleted campaigns, then the packages left without links.
+pub fn apply(report: &mut FundReport, campaigns: &HashMap<String, Campaign>) {
+    report.retain_links(|link: &mut FundingLink| match campaigns.get(&link.url) {
+        Some(Campaign::Deleted) => {
+            tracing::info!("leaving out {}, whose campaign was deleted", link.url);
+            false
+        }
+        Some(Campaign::Active { patrons }) => {
+            link.patrons = *patrons;
+            true
+        }
+        None => true,
+    });
+}
+
+/// The patron count in the data embedded in a campaign's page.
+fn patron_count(page: &str) -> Option<u64> {
+    const KEY: &str = "\"patron_count\":";
+    let rest = page[page.find(KEY)? + KEY.len()..].trim_start();
+    let digits = rest
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(rest.len());
+    rest[..digits].parse().ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn reads_patron_counts() {
+        let page = r#"<script>{"attributes":{"name":"someone","patron_count": 42,"#;
+        assert_eq!(patron_count(page), Some(42));
+        assert_eq!(patron_count(r#"{"patron_count":null}"#), None);
+        assert_eq!(patron_count("<html></html>"), None);
+    }
+
+    #[test]
+    fn leaves_out_deleted_campaigns() {
+        let link = |url: &str| serde_json::json!({ "platform": "PATREON", "url": url, "provenance": ["repository"] });
+        let package = |name: &str, links: Vec<serde_json::Value>| {
+            serde_json::json!({
+                "id": name, "name": name, "version": "1.0.0", "description": null, "links": links,
+            })
+        };
+        let mut report: FundReport = serde_json::from_value(serde_json::json!({
+            "workspace_root": "/ws",
+            "dependencies": 3,
+            "packages": [
+                package("active", vec![link("https://patreon.com/a")]),
+                package("deleted", vec![link("https://patreon.com/d")]),
+                package("both", vec![link("https://patreon.com/a"), link("https://patreon.com/d")]),
+            ],
+        }))
+        .unwrap();
+        let campaigns = HashMap::from([
+            (
+                "https://patreon.com/a".to_string(),
+                Campaign::Active { patrons: Some(42) },
+            ),
+            ("https://patreon.com/d".to_string(), Campaign::Deleted),
+        ]);
+        apply(&mut report, &campaigns);
+        let names = report.packages.iter().map(|pkg| &pkg.name[..]);
+        assert_eq!(names.collect::<Vec<_>>(), ["active", "both"]);
+        for pkg in &report.packages {
+            assert_eq!(pkg.links.len(), 1);
+            assert_eq!(pkg.links[0].patrons, Some(42));
+        }
+    }
+}
@@ -0,0 +1,407 @@
+//! `cargo fund plan`: a proposal for splitting a budget between maintainers.
+//!
+//! A workspace can also commit the plan it settled on as `fund-plan.toml`, giving the monthly
+//! amount for each maintainer on each funding platform, by the platform's name in Github's
+//! `FundingPlatform` enum:
+//!
+//! ```toml
+//! [maintainers.dtolnay]
+//! GITHUB = 10
+//!
+//! [maintainers.example]
+//! OPEN_COLLECTIVE = "4.50"
+//! ```
+//!
+//! `cargo fund plan --check` then compares it with the current dependencies.
+
+use crate::args::Strategy;
+use crate::ledger::Amount;
+use anyhow::{Context, Error};
+use cargo_fund::report::FundingLink;
+use cargo_fund::Group;
+use cargo_metadata::Metadata;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::Path;
+
+/// The name of a workspace's committed funding plan.
+pub const FILE_NAME: &str = "fund-plan.toml";
+
+/// A maintainer's share of the budget.
+#[derive(Debug, Serialize)]
+pub struct Share {
+    pub maintainer: String,
+    /// The amount, in hundredths of the budget's currency.
+    pub cents: u64,
+    pub links: Vec<String>,
+    pub packages: Vec<String>,
+    /// A GitHub Sponsors checkout page pre-filled with the share, if the maintainer is listed there.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checkout: Option<String>,
+}
+
+/// The GitHub Sponsors checkout page for sponsoring `cents` a month through `link`, if it is a
+/// Sponsors listing. GitHub takes whole amounts of at least one dollar, so the share is rounded.
+pub fn checkout_url(link: &FundingLink, cents: u64) -> Option<String> {
+    link.login.as_ref()?;
+    let amount = ((cents + 50) / 100).max(1);
+    Some(format!(
+        "{}/sponsorships?frequency=recurring&amount={}",
+        link.url.trim_end_matches('/'),
+        amount
+    ))
+}
+
+/// Split `budget` cents in proportion to `weights`, rounding so that the shares add up to the
+/// whole budget.
+pub fn allocate(weights: &[f64], budget: u64) -> Vec<u64> {
+    let total = weights.iter().sum::<f64>();
+    if total <= 0.0 {
+        return vec![0; weights.len()];
+    }
+    let exact = weights
+        .iter()
+        .map(|weight| weight / total * budget as f64)
+        .collect::<Vec<_>>();
+    let mut shares = exact.iter().map(|share| *share as u64).collect::<Vec<_>>();
+    // hand out what was lost to rounding down, largest remainders first
+    let mut order = (0..weights.len()).collect::<Vec<_>>();
+    order.sort_by(|&a, &b| {
+        let remainder = |ix: usize| exact[ix] - shares[ix] as f64;
+        remainder(b).total_cmp(&remainder(a)).then(a.cmp(&b))
+    });
+    let left = budget.saturating_sub(shares.iter().sum::<u64>());
+    for &ix in order.iter().cycle().take(left as usize) {
+        shares[ix] += 1;
+    }
+    shares
+}
+
+/// Propose how to split `budget` cents between the maintainers in `groups`.
+pub fn plan(metadata: &Metadata, groups: &[Group], budget: u64, strategy: Strategy) -> Vec<Share> {
+    let members = metadata.workspace_members.iter().collect::<Vec<_>>();
+    let depths = crate::impact::depths(metadata, &members);
+    let weights = groups
+        .iter()
+        .map(|group| match strategy {
+            Strategy::Equal => 1.0,
+            Strategy::Packages => group.pkgs.len() as f64,
+            // a direct dependency counts fully, and one n steps away counts 1/n as much
+            Strategy::Depth => group
+                .pkgs
+                .iter()
+                .map(|id| 1.0 / depths.get(id).copied().unwrap_or(1).max(1) as f64)
+                .sum(),
+        })
+        .collect::<Vec<_>>();
+    let shares = allocate(&weights, budget);
+    groups
+        .iter()
+        .zip(shares)
+        .map(|(group, cents)| {
+            let packages = group
+                .pkgs
+                .iter()
+                .map(|id| metadata[id].name.clone())
+                .collect::<Vec<_>>();
+            let links = group
+                .links
+                .iter()
+                .map(|link| link.url.clone())
+                .collect::<Vec<_>>();
+            Share {
+                maintainer: group.heading.clone().unwrap_or_else(|| packages.join(", ")),
+                cents,
+                checkout: group
+                    .links
+                    .iter()
+                    .find_map(|link| checkout_url(link, cents)),
+                links,
+                packages,
+            }
+        })
+        .collect()
+}
+
+/// Write the plan as a table of amounts, maintainers, and links.
+pub fn write_plan(out: &mut String, shares: &[Share]) {
+    for share in shares {
+        writeln!(
+            out,
+            "{:>10}.{:02}  {} ({} packages)",
+            share.cents / 100,
+            share.cents % 100,
+            share.maintainer,
+            share.packages.len()
+        )
+        .unwrap();
+        for link in &share.links {
+            writeln!(out, "             {}", link).unwrap();
+        }
+        if let Some(checkout) = &share.checkout {
+            writeln!(out, "             checkout: {}", checkout).unwrap();
+        }
+    }
+}
+
+/// Write the plan as a Markdown checklist, with a link to pay each maintainer.
+pub fn write_shopping_list(out: &mut String, shares: &[Share]) {
+    writeln!(out, "# Sponsorship shopping list").unwrap();
+    writeln!(out).unwrap();
+    let total = shares.iter().map(|share| share.cents).sum::<u64>();
+    writeln!(
+        out,
+        "{}.{:02} a month, split between {} maintainers.",
+        total / 100,
+        total % 100,
+        shares.len()
+    )
+    .unwrap();
+    writeln!(out).unwrap();
+    for share in shares.iter().filter(|share| share.cents > 0) {
+        let link = share.checkout.as_ref().or(share.links.first());
+        write!(
+            out,
+            "- [ ] {}.{:02} to **{}**",
+            share.cents / 100,
+            share.cents % 100,
+            share.maintainer
+        )
+        .unwrap();
+        match link {
+            Some(link) => writeln!(out, ": <{}>", link),
+            None => writeln!(out),
+        }
+        .unwrap();
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PlanFile {
+    /// The monthly amount for each maintainer on each platform, keyed by Github account or
+    /// funding link and then by platform.
+    maintainers: BTreeMap<String, BTreeMap<String, Amount>>,
+}
+
+impl PlanFile {
+    /// Load the plan at `path`.
+    pub fn load(path: &Path) -> Result<PlanFile, Error> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("error reading {}", path.display()))?;
+        toml::from_str(&text).with_context(|| format!("error parsing {}", path.display()))
+    }
+}
+
+/// A payment the plan would make.
+#[derive(Debug, Serialize)]
+pub struct Payment {
+    pub maintainer: String,
+    pub platform: String,
+    /// The monthly amount, in hundredths.
+    pub cents: u64,
+    /// The maintainer's funding link on the platform, if they still have one.
+    pub link: Option<String>,
+}
+
+/// How a committed plan compares with the current dependencies.
+#[derive(Debug, Default, Serialize)]
+pub struct PlanCheck {
+    /// The payments the plan would make.
+    pub payments: Vec<Payment>,
+    /// Planned maintainers who no longer maintain any dependency.
+    pub stale: Vec<String>,
+    /// Fundable maintainers missing from the plan.
+    pub newcomers: Vec<String>,
+}
+
+impl PlanCheck {
+    /// Whether the plan is out of date: it has stale maintainers, newcomers are missing from it,
+    /// or it pays a maintainer on a platform they no longer have a link on.
+    pub fn is_outdated(&self) -> bool {
+        !self.stale.is_empty()
+            || !self.newcomers.is_empty()
+            || self.payments.iter().any(|payment| payment.link.is_none())
+    }
+}
+
+/// Compare `plan` with the fundable maintainers in `groups`. Maintainers are matched by their
+/// Github account or one of their funding links, and platforms by name, regardless of case.
+pub fn check(plan: &PlanFile, groups: &[Group]) -> PlanCheck {
+    let platform = |name: &str| name.to_ascii_uppercase().replace('-', "_");
+    let mut check = PlanCheck::default();
+    let mut planned = vec![false; groups.len()];
+    for (maintainer, amounts) in &plan.maintainers {
+        let found = groups.iter().position(|group| {
+            let keys = group
+                .heading
+                .iter()
+                .chain(group.links.iter().map(|link| &link.url));
+            keys.into_iter()
+                .any(|key| key.eq_ignore_ascii_case(maintainer))
+        });
+        let Some(ix) = found else {
+            check.stale.push(maintainer.clone());
+            continue;
+        };
+        planned[ix] = true;
+        for (name, amount) in amounts {
+            let link = groups[ix]
+                .links
+                .iter()
+                .find(|link| platform(&link.platform) == platform(name));
+            check.payments.push(Payment {
+                maintainer: maintainer.clone(),
+                platform: platform(name),
+                cents: amount.0,
+                link: link.map(|link| link.url.clone()),
+            });
+        }
+    }
+    for (group, planned) in groups.iter().zip(planned) {
+        if !planned {
+            check
+                .newcomers
+                .push(group.heading.clone().unwrap_or_else(|| group.name()));
+        }
+    }
+    check
+}
+
+/// Write the payments a plan would make, and how it is out of date.
+pub fn write_check(out: &mut String, check: &PlanCheck) {
+    for payment in &check.payments {
+        let to = match &payment.link {
+            Some(link) => link.clone(),
+            None => format!("no {} link any more", payment.platform),
+        };
+        writeln!(
+            out,
+            "{:>10}.{:02}  {} ({})",
+            payment.cents / 100,
+            payment.cents % 100,
+            payment.maintainer,
+            to
+        )
+        .unwrap();
+    }
+    let total = check
+        .payments
+        .iter()
+        .map(|payment| payment.cents)
+        .sum::<u64>();
+    writeln!(
+        out,
+        "{:>10}.{:02}  in total a month",
+        total / 100,
+        total % 100
+    )
+    .unwrap();
+    for maintainer in &check.stale {
+        writeln!(out, "no longer depended on: {}", maintainer).unwrap();
+    }
+    for maintainer in &check.newcomers {
+        writeln!(out, "missing from the plan: {}", maintainer).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn allocates_whole_budget() {
+        assert_eq!(allocate(&[1.0, 1.0, 1.0], 100), [34, 33, 33]);
+        assert_eq!(allocate(&[3.0, 1.0], 20000), [15000, 5000]);
+        assert_eq!(allocate(&[0.0, 0.0], 100), [0, 0]);
+        assert_eq!(allocate(&[], 100), Vec::<u64>::new());
+    }
+
+    #[test]
+    fn checks_plan_file() {
+        use std::collections::BTreeSet;
+
+        let group = |heading: &str, platform: &str, url: &str| Group {
+            heading: Some(heading.to_string()),
+            links: BTreeSet::from([FundingLink {
+                platform: platform.to_string(),
+                url: url.to_string(),
+                login: None,
+                provenance: BTreeSet::new(),
+                disallowed: false,
+                goal: None,
+                sponsoring: false,
+                sponsors: None,
+                patrons: None,
+            }]),
+            pkgs: BTreeSet::new(),
+        };
+        let plan: PlanFile = toml::from_str(
+            r#"
+            [maintainers.dtolnay]
+            github = 10
+            KO_FI = "2.50"
+
+            [maintainers.gone]
+            GITHUB = 5
+            "#,
+        )
+        .unwrap();
+        let groups = [
+            group("DTolnay", "GITHUB", "https://github.com/sponsors/dtolnay"),
+            group("new", "OPEN_COLLECTIVE", "https://opencollective.com/new"),
+        ];
+        let check = check(&plan, &groups);
+        assert_eq!(check.payments.len(), 2);
+        let payment = |platform: &str| {
+            let mut payments = check.payments.iter();
+            payments.find(|p| p.platform == platform).unwrap()
+        };
+        assert_eq!(payment("GITHUB").cents, 1000);
+        assert_eq!(
+            payment("GITHUB").link.as_deref(),
+            Some("https://github.com/sponsors/dtolnay")
+        );
+        assert_eq!(payment("KO_FI").link, None);
+        assert_eq!(check.stale, ["gone"]);
+        assert_eq!(check.newcomers, ["new"]);
+        assert!(check.is_outdated());
+    }
+
+    #[test]
+    fn checkout_urls() {
+        let link = |platform: &str, url: &str, login: Option<&str>| {
+            let link = cargo_fund::Link::try_from((platform, url)).unwrap();
+            let link = cargo_fund::Link {
+                login: login.map(str::to_string),
+                ..link
+            };
+            FundingLink::new(&link, Default::default())
+        };
+        let listing = link(
+            "GITHUB",
+            "https://github.com/sponsors/dtolnay",
+            Some("dtolnay"),
+        );
+        assert_eq!(
+            checkout_url(&listing, 1049).unwrap(),
+            "https://github.com/sponsors/dtolnay/sponsorships?frequency=recurring&amount=10"
+        );
+        // the listing's own URL is used, wherever it is
+        let listing = link(
+            "GITHUB",
+            "https://github.example.com/sponsors/someone/",
+            Some("someone"),
+        );
+        assert_eq!(
+            checkout_url(&listing, 10).unwrap(),
+            "https://github.example.com/sponsors/someone/sponsorships?frequency=recurring&amount=1"
+        );
+        // a profile whose owner has no listing, and other platforms, have no checkout
+        let profile = link("GITHUB", "https://github.com/someone", None);
+        assert_eq!(checkout_url(&profile, 1000), None);
+        let other = link("KO_FI", "https://ko-fi.com/someone", None);
+        assert_eq!(checkout_url(&other, 1000), None);
+    }
+}
@@ -0,0 +1,105 @@
+//! A progress indicator for the slow part of a run: querying funding sources.
+
+use crate::Event;
+use parking_lot::Mutex;
+use std::io::{IsTerminal, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+#[cfg(feature = "native")]
+use std::time::Duration;
+
+#[cfg(feature = "native")]
+const FRAMES: &[char] = &['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+/// A spinner on stderr showing how many of the repositories have been queried so far, driven by
+/// the [`Event`]s of resolving funding links.
+///
+/// The spinner is only drawn when stderr is a terminal and the `native` feature is enabled. It is
+/// cleared when the `Progress` is finished or dropped.
+pub struct Progress {
+    state: Option<Arc<State>>,
+}
+
+// without the `native` feature, the spinner is never drawn
+#[cfg_attr(not(feature = "native"), allow(dead_code))]
+struct State {
+    done: AtomicUsize,
+    total: AtomicUsize,
+    /// Held while drawing; `true` once the spinner has been cleared for good.
+    finished: Mutex<bool>,
+}
+
+impl Progress {
+    /// Start a spinner, unless `enabled` is false or stderr is not a terminal.
+    pub fn new(enabled: bool) -> Self {
+        if !enabled || !cfg!(feature = "native") || !std::io::stderr().is_terminal() {
+            return Progress { state: None };
+        }
+        let state = Arc::new(State {
+            done: AtomicUsize::new(0),
+            total: AtomicUsize::new(0),
+            finished: Mutex::new(false),
+        });
+        #[cfg(feature = "native")]
+        Self::spawn(state.clone());
+        Progress { state: Some(state) }
+    }
+
+    /// Redraw the spinner until it is finished.
+    #[cfg(feature = "native")]
+    fn spawn(task_state: Arc<State>) {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_millis(100));
+            for frame in FRAMES.iter().cycle() {
+                interval.tick().await;
+                let finished = task_state.finished.lock();
+                if *finished {
+                    break;
+                }
+                eprint!(
+                    "\r{} queried {} of {} repositories",
+                    frame,
+                    task_state.done.load(Ordering::Relaxed),
+                    task_state.total.load(Ordering::Relaxed)
+                );
+                let _ = std::io::stderr().flush();
+            }
+        });
+    }
+
+    /// Update the spinner for an event from resolving funding links.
+    pub fn handle(&self, event: &Event<'_>) {
+        let state = match &self.state {
+            Some(state) => state,
+            None => return,
+        };
+        match event {
+            Event::SourcesCollected { repos, .. } => {
+                state.total.store(*repos, Ordering::Relaxed);
+            }
+            Event::SourceResolved { source } if source.is_repo() => {
+                state.done.fetch_add(1, Ordering::Relaxed);
+            }
+            Event::Finished => self.finish(),
+            _ => (),
+        }
+    }
+
+    /// Stop and clear the spinner.
+    pub fn finish(&self) {
+        if let Some(state) = &self.state {
+            let mut finished = state.finished.lock();
+            if !*finished {
+                *finished = true;
+                eprint!("\r\x1b[2K");
+                let _ = std::io::stderr().flush();
+            }
+        }
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
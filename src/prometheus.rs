@@ -0,0 +1,125 @@
+//! `--format prometheus`: metrics about the report in the Prometheus text format, for tracking
+//! how well funded a workspace's dependencies are over time.
+
+use cargo_fund::report::FundReport;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+/// Write metrics about the report in the Prometheus text format, labeled with the workspace so that
+/// the metrics of many workspaces can be collected side by side.
+pub fn write_prometheus(out: &mut String, report: &FundReport) {
+    let workspace = escape(&report.workspace_root.display().to_string());
+    let mut metric = |name: &str, help: &str, samples: &[(Option<(&str, &str)>, usize)]| {
+        writeln!(out, "# HELP cargo_fund_{} {}", name, help).unwrap();
+        writeln!(out, "# TYPE cargo_fund_{} gauge", name).unwrap();
+        for (label, value) in samples {
+            write!(out, "cargo_fund_{}{{workspace=\"{}\"", name, workspace).unwrap();
+            if let Some((label, label_value)) = label {
+                write!(out, ",{}=\"{}\"", label, label_value).unwrap();
+            }
+            writeln!(out, "}} {}", value).unwrap();
+        }
+    };
+    metric(
+        "dependencies_total",
+        "Dependencies considered for funding, leaving out ignored packages.",
+        &[(None, report.considered())],
+    );
+    metric(
+        "funded_total",
+        "Dependencies with at least one funding link.",
+        &[(None, report.packages.len())],
+    );
+    let skipped = &report.skipped;
+    metric(
+        "skipped_total",
+        "Dependencies without funding links, by the reason none were found.",
+        &[
+            (Some(("reason", "no-repository")), skipped.no_repository),
+            (Some(("reason", "no-links")), skipped.no_links),
+            (Some(("reason", "not-looked-up")), skipped.not_looked_up),
+            (Some(("reason", "rate-limited")), skipped.rate_limited),
+        ],
+    );
+    let mut platforms = BTreeMap::new();
+    for pkg in &report.packages {
+        let pkg_platforms = pkg.links.iter().map(|link| link.platform.as_str());
+        for platform in pkg_platforms.collect::<BTreeSet<_>>() {
+            *platforms.entry(platform).or_insert(0) += 1;
+        }
+    }
+    let platforms = platforms
+        .into_iter()
+        .map(|(platform, count)| (Some(("platform", platform)), count))
+        .collect::<Vec<_>>();
+    metric(
+        "platform_packages_total",
+        "Dependencies with a funding link on each platform.",
+        &platforms,
+    );
+}
+
+/// Escape `s` for a label value, in which backslashes, quotes, and line breaks are escaped.
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn writes_metrics() {
+        let link = |platform: &str, url: &str| serde_json::json!({ "platform": platform, "url": url, "provenance": ["repository"] });
+        let report: FundReport = serde_json::from_value(serde_json::json!({
+            "workspace_root": "/home/me/\"quoted\"\nC:\\ws",
+            "dependencies": 5,
+            "packages": [{
+                "id": "dep 1.0.0", "name": "dep", "version": "1.0.0", "description": null,
+                "links": [link("KO_FI", "https://ko-fi.com/a"), link("KO_FI", "https://ko-fi.com/b")],
+            }],
+            "skipped": {
+                "no_repository": 1, "no_links": 1, "not_looked_up": 0, "rate_limited": 1,
+                "ignored": 1,
+            },
+        }))
+        .unwrap();
+        let mut out = String::new();
+        write_prometheus(&mut out, &report);
+        let workspace = r#"workspace="/home/me/\"quoted\"\nC:\\ws""#;
+        let samples = out
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            samples,
+            [
+                format!("cargo_fund_dependencies_total{{{}}} 4", workspace),
+                format!("cargo_fund_funded_total{{{}}} 1", workspace),
+                format!(
+                    "cargo_fund_skipped_total{{{},reason=\"no-repository\"}} 1",
+                    workspace
+                ),
+                format!(
+                    "cargo_fund_skipped_total{{{},reason=\"no-links\"}} 1",
+                    workspace
+                ),
+                format!(
+                    "cargo_fund_skipped_total{{{},reason=\"not-looked-up\"}} 0",
+                    workspace
+                ),
+                format!(
+                    "cargo_fund_skipped_total{{{},reason=\"rate-limited\"}} 1",
+                    workspace
+                ),
+                format!(
+                    "cargo_fund_platform_packages_total{{{},platform=\"KO_FI\"}} 1",
+                    workspace
+                ),
+            ]
+        );
+        assert!(out.contains("# TYPE cargo_fund_funded_total gauge\n"));
+    }
+}
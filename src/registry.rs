@@ -0,0 +1,170 @@
+//! `--registry` and `--exclude-registry`: leaving out packages from some registries, such as a
+//! company's own alternate registry, whose crates are its own code rather than the work of
+//! maintainers to fund.
+//!
+//! `cargo metadata` only says which index a package came from, so registries given by name are
+//! looked up the way Cargo does: `crates-io` is built in, and others are read from
+//! `CARGO_REGISTRIES_<NAME>_INDEX` or the `[registries]` tables of Cargo's configuration files.
+//! Path and git dependencies are never left out.
+
+use crate::diagnostic;
+use anyhow::{anyhow, Context, Error};
+use cargo_metadata::Metadata;
+use std::path::Path;
+
+/// The indexes crates.io is known by, through git and through the sparse protocol.
+const CRATES_IO: [&str; 2] = [
+    "https://github.com/rust-lang/crates.io-index",
+    "sparse+https://index.crates.io/",
+];
+
+/// Which registries' packages to keep.
+pub struct Filter {
+    /// The indexes whose packages to keep, or empty to keep those of any registry not excluded.
+    include: Vec<String>,
+    /// The indexes whose packages to leave out.
+    exclude: Vec<String>,
+}
+
+impl Filter {
+    /// The filter for the registries named in `include` and `exclude`, each a registry's name or
+    /// its index URL, with Cargo's configuration read from `dir` and its parents. There is no
+    /// filter if both are empty.
+    pub fn new(
+        include: &[String],
+        exclude: &[String],
+        dir: &Path,
+    ) -> Result<Option<Filter>, Error> {
+        if include.is_empty() && exclude.is_empty() {
+            return Ok(None);
+        }
+        let indexes = |names: &[String], option: &str| -> Result<Vec<String>, Error> {
+            let mut indexes = vec![];
+            for name in names {
+                let resolved = resolve(name, dir).map_err(|e| {
+                    diagnostic::with_help(
+                        e,
+                        option,
+                        format!(
+                            "add an index for it under [registries.{}] in .cargo/config.toml, \
+                             set CARGO_REGISTRIES_{}_INDEX, or give its index URL instead",
+                            name,
+                            name.to_ascii_uppercase().replace('-', "_")
+                        ),
+                    )
+                })?;
+                indexes.extend(resolved.iter().map(|index| normalize(index)));
+            }
+            Ok(indexes)
+        };
+        Ok(Some(Filter {
+            include: indexes(include, "--registry, or `registries` in the configuration")?,
+            exclude: indexes(
+                exclude,
+                "--exclude-registry, or `exclude-registries` in the configuration",
+            )?,
+        }))
+    }
+
+    /// Whether to leave out a package from `source`, as `cargo metadata` writes it.
+    fn excludes(&self, source: &str) -> bool {
+        if !source.starts_with("registry+") && !source.starts_with("sparse+") {
+            return false;
+        }
+        let index = normalize(source);
+        let listed = |indexes: &[String]| indexes.contains(&index);
+        listed(&self.exclude) || (!self.include.is_empty() && !listed(&self.include))
+    }
+
+    /// Leave the packages that the filter excludes out of `metadata`, so that they are neither
+    /// looked up nor counted. The dependency graph is left alone, so the packages they depend on
+    /// are still reached through them.
+    pub fn apply(&self, metadata: &mut Metadata) {
+        let members = &metadata.workspace_members;
+        metadata.packages.retain(|pkg| {
+            let source = pkg.source.as_ref().map(|source| source.to_string());
+            let excluded =
+                !members.contains(&pkg.id) && source.is_some_and(|source| self.excludes(&source));
+            if excluded {
+                tracing::debug!("leaving out {} {} for its registry", pkg.name, pkg.version);
+            }
+            !excluded
+        });
+    }
+}
+
+/// An index URL in a form that can be compared with others: without Cargo's `registry+` prefix,
+/// a `.git` suffix, or a trailing slash.
+fn normalize(index: &str) -> String {
+    let index = index.strip_prefix("registry+").unwrap_or(index);
+    let index = index.trim_end_matches('/');
+    index.strip_suffix(".git").unwrap_or(index).to_string()
+}
+
+/// The indexes of the registry `name`, which may be an index URL itself.
+fn resolve(name: &str, dir: &Path) -> Result<Vec<String>, Error> {
+    if name == "crates-io" {
+        return Ok(CRATES_IO.iter().map(|index| index.to_string()).collect());
+    }
+    if name.contains("://") {
+        return Ok(vec![name.to_string()]);
+    }
+    let var = format!(
+        "CARGO_REGISTRIES_{}_INDEX",
+        name.to_ascii_uppercase().replace('-', "_")
+    );
+    if let Ok(index) = std::env::var(var) {
+        return Ok(vec![index]);
+    }
+    // Cargo reads the configuration files in the directory and its parents first, then the one in
+    // Cargo's home directory
+    let cargo_home = std::env::var_os("CARGO_HOME")
+        .map(Into::into)
+        .or_else(|| dirs::home_dir().map(|home| home.join(".cargo")));
+    let dirs = dir
+        .ancestors()
+        .map(|dir| dir.join(".cargo"))
+        .chain(cargo_home);
+    for dir in dirs {
+        for file in ["config.toml", "config"] {
+            let path = dir.join(file);
+            let Ok(contents) = std::fs::read_to_string(&path) else {
+                continue;
+            };
+            let config = toml::from_str::<toml::Value>(&contents)
+                .with_context(|| format!("error parsing {}", path.display()))?;
+            if let Some(index) = config
+                .get("registries")
+                .and_then(|registries| registries.get(name))
+                .and_then(|registry| registry.get("index"))
+                .and_then(|index| index.as_str())
+            {
+                return Ok(vec![index.to_string()]);
+            }
+        }
+    }
+    Err(anyhow!("unknown registry `{}`", name))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn filters_sources() {
+        let dir = Path::new(".");
+        let internal = ["https://registry.example.com/index".to_string()];
+        let filter = Filter::new(&[], &internal, dir).unwrap().unwrap();
+        assert!(filter.excludes("registry+https://registry.example.com/index"));
+        assert!(!filter.excludes("registry+https://github.com/rust-lang/crates.io-index"));
+        assert!(!filter.excludes("git+https://github.com/example/repo#abc123"));
+
+        let filter = Filter::new(&["crates-io".to_string()], &[], dir)
+            .unwrap()
+            .unwrap();
+        assert!(!filter.excludes("registry+https://github.com/rust-lang/crates.io-index"));
+        assert!(!filter.excludes("sparse+https://index.crates.io/"));
+        assert!(filter.excludes("sparse+https://registry.example.com/index/"));
+        assert!(Filter::new(&[], &[], dir).unwrap().is_none());
+    }
+}
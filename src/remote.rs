@@ -0,0 +1,136 @@
+//! `--git`: reporting on a repository that isn't checked out, so that a project's funding can be
+//! weighed before adopting it or contributing to it.
+//!
+//! The repository's default branch is cloned without its history into a new temporary directory
+//! with a random name, which is removed when the clone is dropped. Nothing in it is built, but
+//! `cargo metadata` still resolves its dependencies, which can mean fetching its git dependencies
+//! and writing a lockfile into the clone if it has none. Its `.cargo-fund.toml` and
+//! `[workspace.metadata.fund]` aren't read, as the configuration comes from the directory
+//! `cargo fund` is run in.
+
+use crate::diagnostic;
+use anyhow::{anyhow, Context, Error};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use tempfile::TempDir;
+
+/// A shallow clone of a repository, removed when dropped.
+pub struct Clone {
+    /// The directory the repository is cloned into, which is only `None` once it's removed.
+    dir: Option<TempDir>,
+}
+
+impl Clone {
+    /// Clone the default branch of the repository at `url`, without its history.
+    pub fn shallow(url: &str) -> Result<Clone, Error> {
+        // created anew, so that nothing else can have put anything in it or linked it elsewhere
+        let dir = tempfile::Builder::new()
+            .prefix("cargo-fund-")
+            .tempdir()
+            .context("error creating a directory to clone into")?;
+        let clone = Clone { dir: Some(dir) };
+        tracing::info!("cloning {}", url);
+        let status = Command::new("git")
+            .args(["clone", "--depth", "1", "--quiet", "--"])
+            .arg(url)
+            .arg(clone.dir())
+            .stdin(Stdio::null())
+            .status()
+            .context("error running git clone")?;
+        if !status.success() {
+            return Err(diagnostic::with_help(
+                anyhow!("could not clone {}: git clone returned {}", url, status),
+                "--git",
+                "check the URL, and that the repository is public or that git has credentials \
+                 for it",
+            ));
+        }
+        if !clone.manifest_path().is_file() {
+            return Err(diagnostic::with_help(
+                anyhow!("{} has no Cargo.toml at its root", url),
+                "--git",
+                "clone the repository yourself, and pass --manifest-path with the manifest of its \
+                 workspace",
+            ));
+        }
+        Ok(clone)
+    }
+
+    /// The directory the repository is cloned into.
+    fn dir(&self) -> PathBuf {
+        let dir = self.dir.as_ref().expect("the clone isn't removed yet");
+        dir.path().to_path_buf()
+    }
+
+    /// The manifest at the root of the clone.
+    pub fn manifest_path(&self) -> PathBuf {
+        self.dir().join("Cargo.toml")
+    }
+}
+
+impl Drop for Clone {
+    fn drop(&mut self) {
+        let Some(dir) = self.dir.take() else {
+            return;
+        };
+        let path = dir.path().to_path_buf();
+        if let Err(e) = dir.close() {
+            tracing::warn!("could not remove the clone at {}: {}", path.display(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::path::Path;
+
+    /// A repository with a single commit of `files`, in a new temporary directory.
+    fn repository(files: &[&str]) -> TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let git = |args: &[&str]| {
+            let status = Command::new("git")
+                .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+                .args(args)
+                .current_dir(dir.path())
+                .stdout(Stdio::null())
+                .status()
+                .unwrap();
+            assert!(status.success(), "git {:?} returned {}", args, status);
+        };
+        git(&["init", "--quiet"]);
+        for file in files {
+            std::fs::write(dir.path().join(file), "").unwrap();
+        }
+        git(&["add", "--all"]);
+        git(&["commit", "--quiet", "--allow-empty", "--message", "files"]);
+        dir
+    }
+
+    fn url(dir: &Path) -> String {
+        format!("file://{}", dir.display())
+    }
+
+    #[test]
+    fn clones_into_new_directories() {
+        let repository = repository(&["Cargo.toml"]);
+        let clone = Clone::shallow(&url(repository.path())).unwrap();
+        let other = Clone::shallow(&url(repository.path())).unwrap();
+        assert!(clone.manifest_path().is_file());
+        assert_ne!(clone.dir(), other.dir());
+        let dir = clone.dir();
+        assert!(dir.starts_with(std::env::temp_dir()));
+        drop(clone);
+        assert!(!dir.exists());
+        assert!(other.manifest_path().is_file());
+    }
+
+    #[test]
+    fn needs_a_manifest() {
+        let repository = repository(&["README.md"]);
+        let error = Clone::shallow(&url(repository.path())).err().unwrap();
+        assert!(error.to_string().contains("has no Cargo.toml"), "{}", error);
+        let missing = repository.path().join("missing");
+        assert!(Clone::shallow(&url(&missing)).is_err());
+    }
+}
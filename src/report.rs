@@ -0,0 +1,555 @@
+//! A serializable report of the funding links found for a workspace.
+//!
+//! Every output format is written from a [`FundReport`], and the JSON output is the report itself.
+
+use super::{try_get_sources, Link, Provenance, Resolved};
+use cargo_metadata::{Metadata, PackageId};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::path::{Path, PathBuf};
+
+/// The funding links found for the dependencies of a workspace.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct FundReport {
+    /// The root directory of the workspace.
+    pub workspace_root: PathBuf,
+    /// The number of dependencies, not counting the workspace's own packages.
+    pub dependencies: usize,
+    /// The dependencies with funding links, sorted by name and version.
+    pub packages: Vec<FundedPackage>,
+    /// Why the other dependencies have no funding links in the report.
+    #[serde(default)]
+    pub skipped: Skipped,
+    /// The dependencies whose funding links couldn't be looked up, sorted by name and version.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unresolved: Vec<UnresolvedPackage>,
+    /// When the rate limit resets, in seconds since the Unix epoch, if it ran out during the run.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate_limit_reset: Option<u64>,
+    /// When the report was saved as a baseline, in seconds since the Unix epoch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generated_at: Option<u64>,
+    /// A hash of the lockfile when the report was saved as a baseline, to tell whether the
+    /// dependencies changed since.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lockfile_hash: Option<String>,
+}
+
+/// Why dependencies have no funding links in a report, by number of dependencies.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Skipped {
+    /// Those without a repository in their manifest, or with one that can't be understood.
+    pub no_repository: usize,
+    /// Those whose repository and owner were looked up, but have no funding links.
+    pub no_links: usize,
+    /// Those whose funding links couldn't be looked up, such as when a query failed.
+    pub not_looked_up: usize,
+    /// Those that weren't looked up because the rate limit ran out.
+    #[serde(default)]
+    pub rate_limited: usize,
+    /// Those left out by the configuration, or whose only links are on platforms it hides or to
+    /// maintainers that `--hide-sponsored` and the like leave out of the view.
+    pub ignored: usize,
+}
+
+/// A dependency whose funding links couldn't be looked up.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct UnresolvedPackage {
+    pub id: PackageId,
+    pub name: String,
+    pub version: String,
+    /// Why not: `rate-limited` if the rate limit ran out, otherwise `not-looked-up`.
+    pub reason: String,
+}
+
+/// A dependency and its funding links.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct FundedPackage {
+    pub id: PackageId,
+    pub name: String,
+    pub version: String,
+    pub description: Option<String>,
+    /// The funding links, sorted by platform and then URL.
+    pub links: Vec<FundingLink>,
+    /// How many times the crate has been downloaded from crates.io, if it was looked up.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub downloads: Option<u64>,
+    /// When the crate was last released on crates.io, such as `2024-03-01`, if it was looked up.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_release: Option<String>,
+    /// The latest stable version of the crate on crates.io, if it was looked up.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latest_version: Option<String>,
+    /// Whether the latest version isn't semver-compatible with this one, so that upgrading would
+    /// mean a breaking change.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub outdated: bool,
+    /// How far cargo-vet vouches for the package: `audited`, `exempted`, or `unaudited`, if it
+    /// was looked up.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vet_status: Option<String>,
+}
+
+/// A single way to fund a package.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct FundingLink {
+    /// The platform's name as it appears in Github's `FundingPlatform` enum, such as `KO_FI`.
+    pub platform: String,
+    pub url: String,
+    /// The login of the maintainer whose Github Sponsors listing this is, if it is one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub login: Option<String>,
+    /// Where the link was found.
+    pub provenance: BTreeSet<Provenance>,
+    /// Whether the link's platform is one that the configuration doesn't allow.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub disallowed: bool,
+    /// The maintainer's active Github Sponsors goal, for a Github Sponsors link, if it was looked
+    /// up.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub goal: Option<SponsorsGoal>,
+    /// Whether the user whose Github API token was used already sponsors the maintainer, for a
+    /// Github Sponsors link, if it was looked up.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub sponsoring: bool,
+    /// How many sponsors the maintainer has, for a Github Sponsors link, if it was looked up.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sponsors: Option<u64>,
+    /// How many patrons the campaign has, for a Patreon link, if it was looked up.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub patrons: Option<u64>,
+}
+
+/// A goal that a maintainer has set on their Github Sponsors profile, such as reaching a number
+/// of sponsors or a monthly amount.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct SponsorsGoal {
+    pub title: String,
+    /// How far towards the goal the maintainer is, from 0 to 100.
+    pub percent_complete: u64,
+}
+
+impl FundingLink {
+    /// The serializable form of `link`, found in the given places.
+    pub fn new(link: &Link, provenance: BTreeSet<Provenance>) -> Self {
+        FundingLink {
+            platform: link.platform.as_str().to_string(),
+            url: link.uri.to_string(),
+            login: link.login.clone(),
+            provenance,
+            disallowed: false,
+            goal: None,
+            sponsoring: false,
+            sponsors: None,
+            patrons: None,
+        }
+    }
+}
+
+impl FundReport {
+    /// Build a report from the packages in `metadata` and the funding links resolved for them.
+    pub fn new(metadata: &Metadata, resolved: &Resolved) -> Self {
+        let mut found = resolved.links.iter().collect::<Vec<_>>();
+        found.sort_by(|(a, _), (b, _)| {
+            let (a, b) = (&metadata[*a], &metadata[*b]);
+            (&a.name, &a.version, &a.id).cmp(&(&b.name, &b.version, &b.id))
+        });
+        let mut packages = vec![];
+        for (id, links) in found {
+            let pkg = &metadata[id];
+            let mut links = links.iter().collect::<Vec<_>>();
+            links.sort();
+            packages.push(FundedPackage {
+                id: id.clone(),
+                name: pkg.name.clone(),
+                version: pkg.version.to_string(),
+                description: pkg.description.clone(),
+                downloads: None,
+                last_release: None,
+                latest_version: None,
+                outdated: false,
+                vet_status: None,
+                links: links
+                    .into_iter()
+                    .map(|link| {
+                        let provenance = resolved.provenance.get(link).cloned();
+                        FundingLink::new(link, provenance.unwrap_or_default())
+                    })
+                    .collect(),
+            });
+        }
+        let mut report = FundReport {
+            workspace_root: metadata.workspace_root.clone(),
+            dependencies: 0,
+            packages,
+            skipped: Skipped::default(),
+            unresolved: vec![],
+            rate_limit_reset: None,
+            generated_at: None,
+            lockfile_hash: None,
+        };
+        report.count(metadata, resolved);
+        report
+    }
+
+    /// Count the dependencies, and why those without funding links in the report have none.
+    /// Reports built with [`FundReport::new`] are counted already, but this needs calling again
+    /// after packages are added or removed.
+    pub fn count(&mut self, metadata: &Metadata, resolved: &Resolved) {
+        let funded = self
+            .packages
+            .iter()
+            .map(|pkg| &pkg.id)
+            .collect::<HashSet<_>>();
+        let mut skipped = Skipped::default();
+        let mut unresolved = vec![];
+        let mut dependencies = 0;
+        for pkg in &metadata.packages {
+            if metadata.workspace_members.contains(&pkg.id) {
+                continue;
+            }
+            dependencies += 1;
+            if funded.contains(&pkg.id) {
+                continue;
+            }
+            // a repository that can't be understood is as good as none
+            let count = if try_get_sources(pkg).map_or(true, |sources| sources.is_empty()) {
+                &mut skipped.no_repository
+            } else if !resolved.packages.contains(&pkg.id) || resolved.links.contains_key(&pkg.id) {
+                // links were found, but the package or its links were left out since, such as
+                // by the configuration
+                &mut skipped.ignored
+            } else if resolved.incomplete.contains(&pkg.id) {
+                let (count, reason) = if resolved.rate_limited.contains(&pkg.id) {
+                    (&mut skipped.rate_limited, "rate-limited")
+                } else {
+                    (&mut skipped.not_looked_up, "not-looked-up")
+                };
+                unresolved.push(UnresolvedPackage {
+                    id: pkg.id.clone(),
+                    name: pkg.name.clone(),
+                    version: pkg.version.to_string(),
+                    reason: reason.to_string(),
+                });
+                count
+            } else {
+                &mut skipped.no_links
+            };
+            *count += 1;
+        }
+        unresolved.sort_by(|a, b| (&a.name, &a.version).cmp(&(&b.name, &b.version)));
+        self.dependencies = dependencies;
+        self.skipped = skipped;
+        self.unresolved = unresolved;
+        self.rate_limit_reset = resolved
+            .rate_limit_reset
+            .and_then(|reset| reset.duration_since(web_time::UNIX_EPOCH).ok())
+            .map(|since| since.as_secs());
+    }
+
+    /// Leave out the packages with a link to a maintainer that the viewer already sponsors.
+    pub fn retain_unsponsored(&mut self) {
+        let sponsored = |pkg: &FundedPackage| pkg.links.iter().any(|link| link.sponsoring);
+        self.packages.retain(|pkg| !sponsored(pkg));
+    }
+
+    /// Keep only the links for which `keep` returns true, which may also change them, then leave
+    /// out the packages left without links.
+    pub fn retain_links(&mut self, mut keep: impl FnMut(&mut FundingLink) -> bool) {
+        for pkg in &mut self.packages {
+            pkg.links.retain_mut(&mut keep);
+        }
+        self.packages.retain(|pkg| !pkg.links.is_empty());
+    }
+
+    /// The number of dependencies considered, which leaves out those ignored by the
+    /// configuration.
+    pub fn considered(&self) -> usize {
+        self.dependencies - self.skipped.ignored
+    }
+
+    /// The report without links on platforms that the configuration doesn't allow, leaving out
+    /// packages that have no other links.
+    pub fn allowed(&self) -> FundReport {
+        let mut report = self.clone();
+        for pkg in &mut report.packages {
+            pkg.links.retain(|link| !link.disallowed);
+        }
+        report.packages.retain(|pkg| !pkg.links.is_empty());
+        report
+    }
+
+    /// Record the current time and a hash of the lockfile at `lockfile`, for saving the report as a
+    /// baseline. The hash is left out if the lockfile can't be read.
+    pub fn stamp(&mut self, lockfile: &Path) {
+        self.generated_at = Some(crate::cache::now());
+        self.lockfile_hash = std::fs::read(lockfile)
+            .ok()
+            .map(|contents| format!("{:016x}", crate::cache::fnv1a(&contents)));
+    }
+}
+
+/// The changes between a baseline report and a newer one. Packages are matched by name, so that
+/// updating a dependency doesn't show up as a new package.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ReportDiff {
+    /// Packages with funding links that had none in the baseline, or weren't dependencies at all.
+    pub new_packages: Vec<FundedPackage>,
+    /// Packages that had funding links in the baseline but no longer do.
+    pub removed_packages: Vec<FundedPackage>,
+    /// Links gained by packages that already had some, by package name.
+    pub added_links: BTreeMap<String, Vec<FundingLink>>,
+    /// Links lost by packages that still have some, by package name.
+    pub removed_links: BTreeMap<String, Vec<FundingLink>>,
+    /// The URLs of owners' sponsor listings that the baseline didn't have.
+    pub new_sponsor_listings: BTreeSet<String>,
+}
+
+impl ReportDiff {
+    /// Whether nothing changed.
+    pub fn is_empty(&self) -> bool {
+        self == &ReportDiff::default()
+    }
+}
+
+impl FundReport {
+    /// The changes from `baseline` to this report.
+    pub fn diff(&self, baseline: &FundReport) -> ReportDiff {
+        let by_name = |report: &FundReport| -> BTreeMap<String, FundedPackage> {
+            let mut packages = BTreeMap::new();
+            for pkg in &report.packages {
+                // when several versions are present, combine their links
+                let entry = packages
+                    .entry(pkg.name.clone())
+                    .or_insert_with(|| FundedPackage {
+                        links: vec![],
+                        ..pkg.clone()
+                    });
+                for link in &pkg.links {
+                    if !entry.links.iter().any(|l: &FundingLink| l.url == link.url) {
+                        entry.links.push(link.clone());
+                    }
+                }
+            }
+            packages
+        };
+        let (old, new) = (by_name(baseline), by_name(self));
+        let old_urls = old
+            .values()
+            .flat_map(|pkg| &pkg.links)
+            .map(|link| link.url.as_str())
+            .collect::<BTreeSet<_>>();
+        let mut diff = ReportDiff::default();
+        for (name, pkg) in &new {
+            match old.get(name) {
+                None => diff.new_packages.push(pkg.clone()),
+                Some(old_pkg) => {
+                    let added = pkg
+                        .links
+                        .iter()
+                        .filter(|link| !old_pkg.links.iter().any(|l| l.url == link.url))
+                        .cloned()
+                        .collect::<Vec<_>>();
+                    if !added.is_empty() {
+                        diff.added_links.insert(name.clone(), added);
+                    }
+                }
+            }
+            for link in &pkg.links {
+                if link.provenance.contains(&Provenance::Owner)
+                    && !old_urls.contains(link.url.as_str())
+                {
+                    diff.new_sponsor_listings.insert(link.url.clone());
+                }
+            }
+        }
+        for (name, old_pkg) in &old {
+            match new.get(name) {
+                None => diff.removed_packages.push(old_pkg.clone()),
+                Some(pkg) => {
+                    let removed = old_pkg
+                        .links
+                        .iter()
+                        .filter(|link| !pkg.links.iter().any(|l| l.url == link.url))
+                        .cloned()
+                        .collect::<Vec<_>>();
+                    if !removed.is_empty() {
+                        diff.removed_links.insert(name.clone(), removed);
+                    }
+                }
+            }
+        }
+        diff
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn report(packages: &[(&str, &[(&str, Provenance)])]) -> FundReport {
+        let packages = packages
+            .iter()
+            .map(|(name, links)| FundedPackage {
+                id: PackageId {
+                    repr: name.to_string(),
+                },
+                name: name.to_string(),
+                version: "1.0.0".to_string(),
+                description: None,
+                downloads: None,
+                last_release: None,
+                latest_version: None,
+                outdated: false,
+                vet_status: None,
+                links: links
+                    .iter()
+                    .map(|(url, provenance)| FundingLink {
+                        platform: "CUSTOM".to_string(),
+                        url: url.to_string(),
+                        login: None,
+                        provenance: BTreeSet::from([*provenance]),
+                        disallowed: false,
+                        goal: None,
+                        sponsoring: false,
+                        sponsors: None,
+                        patrons: None,
+                    })
+                    .collect(),
+            })
+            .collect();
+        FundReport {
+            workspace_root: PathBuf::new(),
+            dependencies: 3,
+            packages,
+            skipped: Skipped::default(),
+            unresolved: vec![],
+            rate_limit_reset: None,
+            generated_at: None,
+            lockfile_hash: None,
+        }
+    }
+
+    #[test]
+    fn diff() {
+        let baseline = report(&[
+            ("kept", &[("https://a", Provenance::Repository)]),
+            ("dropped", &[("https://b", Provenance::Repository)]),
+        ]);
+        let current = report(&[
+            (
+                "kept",
+                &[
+                    ("https://c", Provenance::Repository),
+                    ("https://d", Provenance::Owner),
+                ],
+            ),
+            ("added", &[("https://a", Provenance::Owner)]),
+        ]);
+        let diff = current.diff(&baseline);
+        let names =
+            |pkgs: &[FundedPackage]| pkgs.iter().map(|p| p.name.clone()).collect::<Vec<_>>();
+        assert_eq!(names(&diff.new_packages), ["added"]);
+        assert_eq!(names(&diff.removed_packages), ["dropped"]);
+        assert_eq!(diff.added_links["kept"].len(), 2);
+        assert_eq!(diff.removed_links["kept"][0].url, "https://a");
+        // a link that was already in the baseline is not a new listing
+        assert_eq!(
+            diff.new_sponsor_listings,
+            BTreeSet::from(["https://d".to_string()])
+        );
+        assert!(current.diff(&current).is_empty());
+    }
+
+    #[test]
+    fn sponsored() {
+        let mut report = report(&[
+            (
+                "sponsored",
+                &[
+                    ("https://a", Provenance::Repository),
+                    ("https://b", Provenance::Repository),
+                ],
+            ),
+            ("unsponsored", &[("https://b", Provenance::Repository)]),
+            ("only-sponsored", &[("https://a", Provenance::Owner)]),
+        ]);
+        for link in report.packages.iter_mut().flat_map(|pkg| &mut pkg.links) {
+            link.sponsoring = link.url == "https://a";
+        }
+        let names = |report: &FundReport| {
+            let names = report.packages.iter().map(|pkg| pkg.name.clone());
+            names.collect::<Vec<_>>()
+        };
+
+        let mut unsponsored = report.clone();
+        unsponsored.retain_unsponsored();
+        assert_eq!(names(&unsponsored), ["unsponsored"]);
+
+        report.retain_links(|link| !link.sponsoring);
+        assert_eq!(names(&report), ["sponsored", "unsponsored"]);
+        assert_eq!(report.packages[0].links.len(), 1);
+        assert_eq!(report.packages[0].links[0].url, "https://b");
+    }
+
+    #[test]
+    fn counts() {
+        let metadata = crate::test::metadata(&[
+            ("funded", "1.0.0", Some("https://github.com/o/funded")),
+            ("funded", "2.0.0", Some("https://github.com/o/funded")),
+            ("no-repo", "1.0.0", None),
+            ("no-links", "1.0.0", Some("https://github.com/o/no-links")),
+            ("gitlab", "1.0.0", Some("https://gitlab.com/o/gitlab")),
+            ("limited", "1.0.0", Some("https://github.com/o/limited")),
+            (
+                "suppressed",
+                "1.0.0",
+                Some("https://github.com/o/suppressed"),
+            ),
+        ]);
+        let id = |repr: &str| PackageId {
+            repr: repr.to_string(),
+        };
+        let mut resolved = Resolved::default();
+        for pkg in &metadata.packages {
+            if pkg.repository.is_some() {
+                resolved.packages.insert(pkg.id.clone());
+            }
+        }
+        let link = |url: &str| Link::try_from(("CUSTOM", url)).unwrap();
+        let funded = [id("funded 1.0.0"), id("funded 2.0.0")];
+        resolved.insert(&funded, link("https://a"), Provenance::Repository);
+        let suppressed = [id("suppressed 1.0.0")];
+        resolved.insert(&suppressed, link("https://b"), Provenance::Repository);
+        // no resolver handles Gitlab repositories
+        resolved.incomplete.insert(id("gitlab 1.0.0"));
+        resolved.incomplete.insert(id("limited 1.0.0"));
+        resolved.rate_limited.insert(id("limited 1.0.0"));
+
+        let mut report = FundReport::new(&metadata, &resolved);
+        report.packages.retain(|pkg| pkg.name != "suppressed");
+        report.count(&metadata, &resolved);
+        // each version of a crate is a dependency of its own
+        assert_eq!(report.dependencies, 7);
+        assert_eq!(report.packages.len(), 2);
+        assert_eq!(
+            report.skipped,
+            Skipped {
+                no_repository: 1,
+                no_links: 1,
+                not_looked_up: 1,
+                rate_limited: 1,
+                ignored: 1,
+            }
+        );
+        assert_eq!(report.considered(), 6);
+        let unresolved = report.unresolved.iter().map(|pkg| {
+            let name = &pkg.name[..];
+            (name, &pkg.reason[..])
+        });
+        assert_eq!(
+            unresolved.collect::<Vec<_>>(),
+            [("gitlab", "not-looked-up"), ("limited", "rate-limited")]
+        );
+    }
+}
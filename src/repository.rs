@@ -0,0 +1,127 @@
+//! Cleaning up repository URLs as written in package manifests.
+//!
+//! The `repository` field is free-form, and crates on crates.io fill it in many ways: with a
+//! branch or subdirectory, a `#readme` fragment, a `?tab=readme` query, a `.git` suffix, a `git+`
+//! or `ssh://` scheme, scp-like `git@github.com:owner/repo` syntax, or no scheme at all. Some
+//! point at the repository's Github Pages site, such as `https://owner.github.io/repo`, instead.
+//! These are all normalized to a plain `https://` URL before the repository is looked up.
+
+/// Normalize a repository URL, so that the different ways of writing one are understood alike.
+///
+/// Fragments, queries, userinfo, trailing slashes, and `.git` suffixes are removed, Git and SSH
+/// schemes become `https`, and the host is lowercased. Github URLs are cut down to the owner and
+/// repository, dropping ports and paths like `/tree/main/crates/foo`, and Github Pages sites
+/// become the repositories they're published from.
+pub fn normalize(url: &str) -> String {
+    let url = url.trim();
+    let url = url.split(['#', '?']).next().unwrap_or_default();
+    let url = url.strip_prefix("git+").unwrap_or(url);
+    let (scheme, rest) = match url.split_once("://") {
+        Some(("http", rest)) => ("http", rest),
+        Some((_, rest)) => ("https", rest),
+        // scp-like syntax, such as `git@github.com:owner/repo.git`
+        None => match url.split_once(':') {
+            Some((host, path)) if !host.contains('/') => {
+                return normalize(&format!("https://{}/{}", host, path));
+            }
+            _ => ("https", url),
+        },
+    };
+    let (host, path) = rest.split_once('/').unwrap_or((rest, ""));
+    let host = host
+        .rsplit('@')
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let (name, port) = host.split_once(':').unwrap_or((&host, ""));
+    // a fully qualified name may end with a dot
+    let name = name.trim_end_matches('.');
+    let mut path = path.trim_end_matches('/');
+    if let Some(owner) = name.strip_suffix(".github.io") {
+        // a project site is published from the repository of the same name, and a user or
+        // organization site from the repository named after the site
+        let repo = path.split('/').next().filter(|repo| !repo.is_empty());
+        let repo = repo.map_or_else(|| name.to_string(), str::to_string);
+        return format!("https://github.com/{}/{}", owner, repo);
+    }
+    let (scheme, host) = match name {
+        "github.com" | "www.github.com" => ("https", "github.com".to_string()),
+        _ if port.is_empty() => (scheme, name.to_string()),
+        _ => (scheme, format!("{}:{}", name, port)),
+    };
+    if host == "github.com" {
+        // only the owner and repository matter, not the branch or directory
+        let end = path
+            .match_indices('/')
+            .nth(1)
+            .map_or(path.len(), |(ix, _)| ix);
+        path = &path[..end];
+    }
+    let path = path
+        .strip_suffix(".git")
+        .unwrap_or(path)
+        .trim_end_matches('/');
+    if path.is_empty() {
+        format!("{}://{}", scheme, host)
+    } else {
+        format!("{}://{}/{}", scheme, host, path)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::normalize;
+
+    #[test]
+    fn normalizes() {
+        let repo = "https://github.com/owner/repo";
+        for url in [
+            "https://github.com/owner/repo",
+            " https://github.com/owner/repo\n",
+            "https://github.com/owner/repo/",
+            "https://github.com/owner/repo.git",
+            "https://github.com/owner/repo/tree/main",
+            "https://github.com/owner/repo/tree/main/crates/foo",
+            "https://github.com/owner/repo/blob/main/README.md",
+            "https://github.com/owner/repo#readme",
+            "https://github.com/owner/repo?tab=readme-ov-file",
+            "https://www.github.com/owner/repo",
+            "https://GitHub.com/owner/repo",
+            "git://github.com/owner/repo",
+            "git+https://github.com/owner/repo.git",
+            "ssh://git@github.com/owner/repo.git",
+            "git@github.com:owner/repo.git",
+            "github.com/owner/repo",
+            "http://github.com/owner/repo",
+            "https://GITHUB.COM/owner/repo",
+            "https://github.com:443/owner/repo",
+            "https://github.com./owner/repo",
+            "ssh://git@github.com:22/owner/repo.git",
+            "https://owner.github.io/repo",
+            "https://Owner.GitHub.io/repo/",
+            "https://owner.github.io/repo/docs/index.html",
+        ] {
+            assert_eq!(normalize(url), repo, "normalizing {:?}", url);
+        }
+        assert_eq!(
+            normalize("http://gitlab.com/group/sub/project.git/#readme"),
+            "http://gitlab.com/group/sub/project"
+        );
+        assert_eq!(
+            normalize("https://github.com/owner"),
+            "https://github.com/owner"
+        );
+        assert_eq!(
+            normalize("https://github.com/Owner/Repo"),
+            "https://github.com/Owner/Repo"
+        );
+        assert_eq!(
+            normalize("https://owner.github.io"),
+            "https://github.com/owner/owner.github.io"
+        );
+        assert_eq!(
+            normalize("https://git.example.com:8443/group/project"),
+            "https://git.example.com:8443/group/project"
+        );
+    }
+}
@@ -0,0 +1,329 @@
+//! Conditional requests for the lookups made outside the Github API: crates.io, Patreon's pages,
+//! and the links checked by `cargo fund verify`.
+//!
+//! The `ETag` and `Last-Modified` of each response are kept next to what was read from it, in
+//! `responses.json` beside the cache of funding links, and sent back as `If-None-Match` and
+//! `If-Modified-Since` the next time. A resource that hasn't changed is answered with `304 Not
+//! Modified` and no body, which counts as a hit: nothing is downloaded again, and sites that rate
+//! limit their APIs usually don't count it.
+//!
+//! Responses that no run has used for `MAX_UNUSED` are forgotten when the rest are saved, so the
+//! file doesn't keep growing with every link ever checked. `cargo fund cache clear` removes it
+//! along with the cache.
+
+use parking_lot::Mutex;
+use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::{RequestBuilder, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// The name of the file the responses are kept in, in the cache's directory.
+const FILE_NAME: &str = "responses.json";
+
+/// How long a response is kept without being used.
+const MAX_UNUSED: Duration = Duration::from_secs(90 * 24 * 60 * 60);
+
+/// What was kept of a response, and how to ask whether it has changed.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+struct Entry {
+    etag: Option<String>,
+    last_modified: Option<String>,
+    /// The URL the request ended up at, after following redirects.
+    url: String,
+    /// What was kept of the body.
+    body: String,
+    /// When the response was last sent or found unchanged, in seconds since the Unix epoch.
+    #[serde(default)]
+    used: u64,
+}
+
+/// A response, or what was kept of it if it hadn't changed.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Response {
+    pub status: StatusCode,
+    /// The URL the request ended up at, after following redirects.
+    pub url: String,
+    pub body: String,
+}
+
+/// The responses kept from earlier runs, by method and URL.
+#[derive(Default)]
+pub struct Responses {
+    /// Where to keep the responses, or `None` to keep them only for this run.
+    path: Option<PathBuf>,
+    entries: Mutex<BTreeMap<String, Entry>>,
+}
+
+/// Where the responses are kept, beside the cache of funding links at `cache_path`.
+pub fn path(cache_path: &Path) -> PathBuf {
+    cache_path.with_file_name(FILE_NAME)
+}
+
+impl Responses {
+    /// Load the responses kept at `path`. A missing or unreadable file is treated as empty.
+    pub fn load(path: Option<PathBuf>) -> Responses {
+        let entries = path.as_deref().map(read).unwrap_or_default();
+        Responses {
+            path,
+            entries: Mutex::new(entries),
+        }
+    }
+
+    /// Send a `GET` request for `url`, keeping only what `keep` returns of the body, which is
+    /// also what's returned, whether the response is fresh or kept from before.
+    pub async fn get(
+        &self,
+        client: &reqwest::Client,
+        url: &str,
+        keep: impl FnOnce(String) -> String,
+    ) -> Result<Response, reqwest::Error> {
+        let key = format!("GET {}", url);
+        let resp = self.send(client.get(url), &key).await?;
+        let (status, landed) = (resp.status(), resp.url().to_string());
+        let validators = validators(&resp);
+        if status == StatusCode::NOT_MODIFIED {
+            if let Some(entry) = self.entries.lock().get_mut(&key) {
+                entry.used = now();
+                return Ok(Response {
+                    status: StatusCode::OK,
+                    url: entry.url.clone(),
+                    body: entry.body.clone(),
+                });
+            }
+        }
+        let body = keep(resp.text().await?);
+        self.record(key, status, validators, &landed, &body);
+        Ok(Response {
+            status,
+            url: landed,
+            body,
+        })
+    }
+
+    /// Send a `HEAD` request for `url`.
+    pub async fn head(
+        &self,
+        client: &reqwest::Client,
+        url: &str,
+    ) -> Result<Response, reqwest::Error> {
+        let key = format!("HEAD {}", url);
+        let resp = self.send(client.head(url), &key).await?;
+        let (status, landed) = (resp.status(), resp.url().to_string());
+        if status == StatusCode::NOT_MODIFIED {
+            if let Some(entry) = self.entries.lock().get_mut(&key) {
+                entry.used = now();
+                return Ok(Response {
+                    status: StatusCode::OK,
+                    url: entry.url.clone(),
+                    body: String::new(),
+                });
+            }
+        }
+        self.record(key, status, validators(&resp), &landed, "");
+        Ok(Response {
+            status,
+            url: landed,
+            body: String::new(),
+        })
+    }
+
+    /// Send `request`, asking whether the response kept for `key` has changed if there is one.
+    async fn send(
+        &self,
+        mut request: RequestBuilder,
+        key: &str,
+    ) -> Result<reqwest::Response, reqwest::Error> {
+        if let Some(entry) = self.entries.lock().get(key) {
+            if let Some(etag) = &entry.etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &entry.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+        let resp = request.send().await?;
+        if resp.status() == StatusCode::NOT_MODIFIED {
+            tracing::trace!("{} is unchanged", key);
+        }
+        Ok(resp)
+    }
+
+    /// Keep a successful response that can be asked about again, and forget any other.
+    fn record(
+        &self,
+        key: String,
+        status: StatusCode,
+        (etag, last_modified): (Option<String>, Option<String>),
+        url: &str,
+        body: &str,
+    ) {
+        let mut entries = self.entries.lock();
+        if status.is_success() && (etag.is_some() || last_modified.is_some()) {
+            let entry = Entry {
+                etag,
+                last_modified,
+                url: url.to_string(),
+                body: body.to_string(),
+                used: now(),
+            };
+            entries.insert(key, entry);
+        } else {
+            entries.remove(&key);
+        }
+    }
+
+    /// Write the responses back to where they were loaded from, if anywhere, merging in those
+    /// that other runs have kept since and leaving out those unused for `MAX_UNUSED`. Failing to
+    /// is only a warning, as the next run can still send the requests in full.
+    pub fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let mut entries = read(path);
+        for (key, entry) in self.entries.lock().iter() {
+            match entries.get(key) {
+                Some(theirs) if theirs.used > entry.used => (),
+                _ => {
+                    entries.insert(key.clone(), entry.clone());
+                }
+            }
+        }
+        let oldest = now().saturating_sub(MAX_UNUSED.as_secs());
+        entries.retain(|_, entry| entry.used >= oldest);
+        let written = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|()| Ok(serde_json::to_vec(&entries)?))
+            .and_then(|json| {
+                // write to a temporary file first so that an interrupted run can't corrupt it
+                let tmp = path.with_extension("json.tmp");
+                std::fs::write(&tmp, json)?;
+                std::fs::rename(&tmp, path)
+            });
+        if let Err(e) = written {
+            tracing::warn!("could not save responses to {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// The `ETag` and `Last-Modified` of `resp`.
+fn validators(resp: &reqwest::Response) -> (Option<String>, Option<String>) {
+    let header = |name| {
+        let value = resp.headers().get(name)?;
+        value.to_str().ok().map(str::to_string)
+    };
+    (header(ETAG), header(LAST_MODIFIED))
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// Read the responses kept at `path`.
+fn read(path: &Path) -> BTreeMap<String, Entry> {
+    let entries = std::fs::read(path).and_then(|bytes| Ok(serde_json::from_slice(&bytes)?));
+    entries.unwrap_or_else(|e| {
+        tracing::debug!("not using responses at {}: {}", path.display(), e);
+        BTreeMap::new()
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Body, Server};
+    use std::convert::Infallible;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn revalidates() {
+        // a server whose one page has an `ETag`, and that counts the bodies it sends
+        let sent = Arc::new(AtomicUsize::new(0));
+        let counter = sent.clone();
+        let make_service = make_service_fn(move |_| {
+            let sent = counter.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request: hyper::Request<Body>| {
+                    let sent = sent.clone();
+                    async move {
+                        let response = hyper::Response::builder();
+                        let response = if request.headers().get(IF_NONE_MATCH)
+                            == Some(&"\"v1\"".parse().unwrap())
+                        {
+                            response.status(304).body(Body::empty())
+                        } else {
+                            sent.fetch_add(1, Ordering::SeqCst);
+                            response
+                                .header(ETAG, "\"v1\"")
+                                .body(Body::from("patron_count: 42"))
+                        };
+                        Ok::<_, Infallible>(response.unwrap())
+                    }
+                }))
+            }
+        });
+        let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_service);
+        let url = format!("http://{}/someone", server.local_addr());
+        tokio::spawn(server);
+
+        let client = reqwest::Client::new();
+        let responses = Responses::default();
+        let keep = |body: String| body.replace("patron_count: ", "");
+        for _ in 0..2 {
+            let response = responses.get(&client, &url, keep).await.unwrap();
+            assert_eq!(response.status, StatusCode::OK);
+            assert_eq!(response.body, "42");
+        }
+        assert_eq!(sent.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            responses.head(&client, &url).await.unwrap().status,
+            StatusCode::OK
+        );
+        assert_eq!(
+            responses.head(&client, &url).await.unwrap().status,
+            StatusCode::OK
+        );
+        assert_eq!(sent.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn forgets_unused_responses() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(FILE_NAME);
+        let entry = |body: &str, used: u64| Entry {
+            etag: Some("\"v1\"".to_string()),
+            last_modified: None,
+            url: "https://example.com/".to_string(),
+            body: body.to_string(),
+            used,
+        };
+        let stale = now() - MAX_UNUSED.as_secs() - 1;
+        // another run kept these since this one loaded
+        let theirs = BTreeMap::from([
+            ("GET https://a/".to_string(), entry("theirs", now())),
+            ("GET https://b/".to_string(), entry("stale", stale)),
+        ]);
+        std::fs::write(&path, serde_json::to_vec(&theirs).unwrap()).unwrap();
+        let responses = Responses::load(Some(path.clone()));
+        *responses.entries.lock() = BTreeMap::from([
+            ("GET https://a/".to_string(), entry("ours", stale)),
+            ("GET https://c/".to_string(), entry("ours", now())),
+        ]);
+        responses.save();
+        let saved = read(&path);
+        let bodies = saved
+            .iter()
+            .map(|(key, entry)| (key.as_str(), entry.body.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            bodies,
+            [("GET https://a/", "theirs"), ("GET https://c/", "ours")]
+        );
+    }
+}
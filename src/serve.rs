@@ -0,0 +1,158 @@
+//! `cargo fund serve`: a long-running HTTP server for funding queries.
+//!
+//! The server answers `POST /report` requests whose body is the output of
+//! `cargo metadata --format-version 1` with the JSON report that `cargo fund --format json` would
+//! print. The cache and the Github client are kept between requests, so repeated queries over the
+//! same dependencies are answered without going back to Github.
+
+use anyhow::{Context as _, Error};
+use cargo_fund::cache::Cache;
+use cargo_fund::report::FundReport;
+use cargo_fund::Context;
+use cargo_metadata::Metadata;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, StatusCode};
+use parking_lot::Mutex;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// The state shared by every request.
+pub struct Server {
+    pub cx: Context,
+    /// The cache, which is only locked to take a copy or to merge results back in, so that slow
+    /// queries don't hold up requests that can be answered from the cache.
+    pub cache: Mutex<Cache>,
+    /// Where to save the cache after each request, if anywhere.
+    pub cache_path: Option<PathBuf>,
+    pub ttl: Duration,
+}
+
+/// Serve requests on `addr` until the process is stopped.
+pub async fn run(server: Server, addr: SocketAddr) -> Result<(), Error> {
+    let server = Arc::new(server);
+    let make_service = make_service_fn(move |_| {
+        let server = server.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                let server = server.clone();
+                async move { Ok::<_, Infallible>(server.handle(request).await) }
+            }))
+        }
+    });
+    let listener =
+        hyper::Server::try_bind(&addr).with_context(|| format!("error listening on {}", addr))?;
+    eprintln!("listening on http://{}", addr);
+    listener.serve(make_service).await?;
+    Ok(())
+}
+
+impl Server {
+    async fn handle(&self, request: Request<Body>) -> Response<Body> {
+        match (request.method(), request.uri().path()) {
+            (&Method::POST, "/report") => (),
+            (_, "/report") => return error(StatusCode::METHOD_NOT_ALLOWED, None, "use POST"),
+            _ => return error(StatusCode::NOT_FOUND, None, "not found"),
+        }
+        let body = match hyper::body::to_bytes(request.into_body()).await {
+            Ok(body) => body,
+            Err(e) => return error(StatusCode::BAD_REQUEST, None, e),
+        };
+        let metadata = match serde_json::from_slice::<Metadata>(&body) {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                let message = format!(
+                    "expected the output of `cargo metadata --format-version 1`: {}",
+                    e
+                );
+                return error(StatusCode::BAD_REQUEST, None, message);
+            }
+        };
+        match self.report(&metadata).await {
+            Ok(report) => json(StatusCode::OK, &report),
+            Err(e) => error(StatusCode::BAD_GATEWAY, Some(e.code()), e),
+        }
+    }
+
+    async fn report(&self, metadata: &Metadata) -> Result<FundReport, cargo_fund::Error> {
+        let source_map = cargo_fund::collect_sources(&self.cx, metadata)?;
+        let mut cache = self.cache.lock().clone();
+        let resolved =
+            cargo_fund::resolve_links(&self.cx, &source_map, &mut cache, self.ttl, &|_| ()).await?;
+        for (source, error) in &resolved.failed {
+            tracing::warn!("could not resolve funding links for {}: {}", source, error);
+        }
+        let cache = {
+            let mut shared = self.cache.lock();
+            shared.merge(cache);
+            shared.clone()
+        };
+        if let Some(path) = &self.cache_path {
+            if let Err(e) = cache.save(path) {
+                tracing::warn!("could not save cache: {:#}", Error::from(e));
+            }
+        }
+        Ok(FundReport::new(metadata, &resolved))
+    }
+}
+
+fn json(status: StatusCode, body: &impl serde::Serialize) -> Response<Body> {
+    let body = serde_json::to_vec(body).expect("responses serialize");
+    Response::builder()
+        .status(status)
+        .header(hyper::header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body))
+        .expect("responses are valid")
+}
+
+/// A JSON error response, with the library's code for the error if it came from there.
+fn error(status: StatusCode, code: Option<&str>, message: impl ToString) -> Response<Body> {
+    let body = serde_json::json!({
+        "error": message.to_string(),
+        "code": code,
+    });
+    json(status, &body)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn rejects_bad_requests() {
+        let server = Server {
+            cx: Context {
+                github_api_token: None,
+                client: reqwest::Client::new(),
+                batch_size: cargo_fund::github::DEFAULT_BATCH_SIZE,
+                jobs: cargo_fund::github::DEFAULT_JOBS,
+                wait_for_rate_limit: false,
+                strict: false,
+                offline: true,
+                quiet: true,
+                fixtures: None,
+                resolvers: cargo_fund::default_resolvers(),
+            },
+            cache: Mutex::new(Cache::default()),
+            cache_path: None,
+            ttl: Duration::MAX,
+        };
+        let request = |method, path, body: &str| {
+            Request::builder()
+                .method(method)
+                .uri(path)
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+        let response = server.handle(request(Method::GET, "/", "")).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let response = server.handle(request(Method::GET, "/report", "")).await;
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+        let response = server
+            .handle(request(Method::POST, "/report", "[[package]]"))
+            .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,106 @@
+//! `--job-summary`: a Markdown summary of the results for the job summary of a GitHub Actions
+//! run, which is shown on the run's page.
+
+use cargo_fund::report::FundReport;
+use cargo_fund::LinkSource;
+use cargo_metadata::{Metadata, PackageId};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+/// Write a Markdown summary of the results for a GitHub Actions job summary: a table of each
+/// maintainer, their funding links, and the packages they maintain.
+pub fn write_job_summary(
+    out: &mut String,
+    metadata: &Metadata,
+    source_map: &HashMap<LinkSource, HashSet<PackageId>>,
+    report: &FundReport,
+) {
+    writeln!(out, "## Funding\n").unwrap();
+    writeln!(
+        out,
+        "Found funding links for {} out of {} dependencies.\n",
+        report.packages.len(),
+        report.considered()
+    )
+    .unwrap();
+    let mut groups = cargo_fund::group_by_owner(source_map, &report.allowed());
+    if groups.is_empty() {
+        return;
+    }
+    groups.sort_by_cached_key(|group| group.name());
+    writeln!(out, "| Maintainer | Funding links | Packages |").unwrap();
+    writeln!(out, "|---|---|---|").unwrap();
+    for group in &groups {
+        let links = group
+            .links
+            .iter()
+            .map(|link| format!("<{}>", cell(&link.url)))
+            .collect::<Vec<_>>();
+        let pkgs = group
+            .pkgs
+            .iter()
+            .map(|id| format!("`{} {}`", metadata[id].name, metadata[id].version))
+            .collect::<Vec<_>>();
+        writeln!(
+            out,
+            "| {} | {} | {} |",
+            cell(group.heading.as_deref().unwrap_or("")),
+            links.join("<br>"),
+            pkgs.join(", ")
+        )
+        .unwrap();
+    }
+    writeln!(out).unwrap();
+}
+
+/// Escape `s` for a table cell, which a pipe would end early and a line break would end the row.
+fn cell(s: &str) -> String {
+    s.replace('|', "\\|").replace(['\r', '\n'], " ")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cargo_fund::github::GithubLinkSource;
+
+    #[test]
+    fn escapes_cells() {
+        assert_eq!(cell("a|b\nc"), "a\\|b c");
+    }
+
+    #[test]
+    fn writes_summaries() {
+        let metadata = crate::test::metadata(&[("dep", "1.0.0", None)]);
+        let report: FundReport = serde_json::from_value(serde_json::json!({
+            "workspace_root": "/ws",
+            "dependencies": 2,
+            "packages": [{
+                "id": "dep 1.0.0", "name": "dep", "version": "1.0.0", "description": null,
+                "links": [
+                    { "platform": "CUSTOM", "url": "https://example.com/?a|b", "provenance": [] },
+                    { "platform": "KO_FI", "url": "https://ko-fi.com/owner", "provenance": [] },
+                ],
+            }],
+        }))
+        .unwrap();
+        let source = LinkSource::Github(GithubLinkSource::Repo {
+            owner: "owner".to_string(),
+            name: "dep".to_string(),
+        });
+        let pkgs = HashSet::from([PackageId {
+            repr: "dep 1.0.0".to_string(),
+        }]);
+        let source_map = HashMap::from([(source, pkgs)]);
+
+        let mut out = String::new();
+        write_job_summary(&mut out, &metadata, &source_map, &report);
+        assert_eq!(
+            out,
+            "## Funding\n\n\
+             Found funding links for 1 out of 2 dependencies.\n\n\
+             | Maintainer | Funding links | Packages |\n\
+             |---|---|---|\n\
+             | owner | <https://example.com/?a\\|b><br><https://ko-fi.com/owner> | `dep 1.0.0` |\n\n"
+        );
+    }
+}
@@ -0,0 +1,105 @@
+//! Colors for the tree output.
+
+use crate::args::{Color, ThemeName};
+use std::fmt::Display;
+use std::io::IsTerminal;
+
+/// The parts of the output that are colored differently.
+#[derive(Clone, Copy, Debug)]
+pub enum Role {
+    /// The characters that draw the tree.
+    Tree,
+    /// Group headings, such as owners.
+    Heading,
+    Link,
+    Package,
+    /// Secondary information, such as package counts and descriptions.
+    Detail,
+}
+
+/// A set of ANSI SGR parameters for each role. An empty string leaves that role unstyled.
+#[derive(Clone, Copy, Debug)]
+pub struct Theme {
+    tree: &'static str,
+    heading: &'static str,
+    link: &'static str,
+    package: &'static str,
+    detail: &'static str,
+}
+
+impl Theme {
+    pub const DEFAULT: Theme = Theme {
+        tree: "2",
+        heading: "1",
+        link: "36",
+        package: "",
+        detail: "2",
+    };
+
+    /// Bright colors that stand out on a dark background.
+    pub const DARK: Theme = Theme {
+        tree: "90",
+        heading: "1;93",
+        link: "96",
+        package: "97",
+        detail: "90",
+    };
+
+    /// Deeper colors that stay legible on a light background.
+    pub const LIGHT: Theme = Theme {
+        tree: "90",
+        heading: "1;35",
+        link: "34",
+        package: "30",
+        detail: "90",
+    };
+
+    /// No colors at all, only bold and underlined text.
+    pub const MONOCHROME_BOLD: Theme = Theme {
+        tree: "",
+        heading: "1",
+        link: "4",
+        package: "1",
+        detail: "",
+    };
+
+    pub fn for_name(name: ThemeName) -> Theme {
+        match name {
+            ThemeName::Default => Theme::DEFAULT,
+            ThemeName::Dark => Theme::DARK,
+            ThemeName::Light => Theme::LIGHT,
+            ThemeName::MonochromeBold => Theme::MONOCHROME_BOLD,
+        }
+    }
+
+    /// Wrap `text` in the escape codes for `role`.
+    pub fn paint(&self, role: Role, text: impl Display) -> String {
+        let style = match role {
+            Role::Tree => self.tree,
+            Role::Heading => self.heading,
+            Role::Link => self.link,
+            Role::Package => self.package,
+            Role::Detail => self.detail,
+        };
+        if style.is_empty() {
+            text.to_string()
+        } else {
+            format!("\x1b[{}m{}\x1b[0m", style, text)
+        }
+    }
+}
+
+/// Whether to color the output, given the value of `--color`.
+///
+/// `always` and `never` are obeyed; otherwise, output is colored when stdout is a terminal and
+/// the `NO_COLOR` environment variable is unset or empty.
+pub fn color_enabled(color: Option<Color>) -> bool {
+    match color {
+        Some(Color::Always) => true,
+        Some(Color::Never) => false,
+        Some(Color::Auto) | None => {
+            let no_color = matches!(std::env::var_os("NO_COLOR"), Some(v) if !v.is_empty());
+            !no_color && std::io::stdout().is_terminal()
+        }
+    }
+}
@@ -0,0 +1,106 @@
+//! `--timings`: how long each phase of a run took, for finding out why a run is slow.
+//!
+//! The phases are timed as the run goes, and the resolvers and their queries are timed from the
+//! [`Event`]s of resolving funding links, so that timing them needs nothing from the library.
+
+use cargo_fund::{Event, LinkSource};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+/// How long a resolver took.
+struct Resolver {
+    name: String,
+    started: Instant,
+    elapsed: Duration,
+    /// The number of sources it looked up.
+    sources: usize,
+    /// The round trip of each of its queries, with the number of sources in it.
+    queries: Vec<(usize, Duration)>,
+}
+
+#[derive(Default)]
+pub struct Timings {
+    /// The phases in the order they finished, with how long each took.
+    phases: Mutex<Vec<(String, Duration)>>,
+    /// The resolvers in the order they ran. Resolvers run one at a time, so queries belong to the
+    /// last one.
+    resolvers: Mutex<Vec<Resolver>>,
+    /// When each query in flight was sent, keyed by its first source.
+    sent: Mutex<HashMap<LinkSource, Instant>>,
+}
+
+impl Timings {
+    /// Record that `phase` took `elapsed`.
+    pub fn record(&self, phase: &str, elapsed: Duration) {
+        self.phases.lock().push((phase.to_string(), elapsed));
+    }
+
+    /// Time the resolvers and their queries.
+    pub fn handle(&self, event: &Event<'_>) {
+        match event {
+            Event::ResolverStarted { resolver, sources } => self.resolvers.lock().push(Resolver {
+                name: resolver.to_string(),
+                started: Instant::now(),
+                elapsed: Duration::ZERO,
+                sources: *sources,
+                queries: vec![],
+            }),
+            Event::ResolverFinished { .. } => {
+                if let Some(resolver) = self.resolvers.lock().last_mut() {
+                    resolver.elapsed = resolver.started.elapsed();
+                }
+            }
+            Event::BatchSent { sources } => {
+                if let Some(first) = sources.first() {
+                    self.sent.lock().insert(first.clone(), Instant::now());
+                }
+            }
+            Event::BatchResolved { sources } => {
+                let sent = sources
+                    .first()
+                    .and_then(|first| self.sent.lock().remove(first));
+                if let (Some(sent), Some(resolver)) = (sent, self.resolvers.lock().last_mut()) {
+                    resolver.queries.push((sources.len(), sent.elapsed()));
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// Write how long each phase took, with the resolvers under the `resolving` phase. With
+    /// `queries`, the round trip of each query is listed too.
+    pub fn write(&self, out: &mut String, queries: bool) {
+        let seconds = |elapsed: &Duration| format!("{:.2}s", elapsed.as_secs_f64());
+        writeln!(out, "timings:").unwrap();
+        for (phase, elapsed) in self.phases.lock().iter() {
+            writeln!(out, "  {:<24} {:>8}", phase, seconds(elapsed)).unwrap();
+            if phase != "resolving" {
+                continue;
+            }
+            for resolver in self.resolvers.lock().iter() {
+                write!(
+                    out,
+                    "    {:<22} {:>8} ({} sources, {} queries",
+                    resolver.name,
+                    seconds(&resolver.elapsed),
+                    resolver.sources,
+                    resolver.queries.len()
+                )
+                .unwrap();
+                match resolver.queries.iter().map(|(_, elapsed)| elapsed).max() {
+                    Some(slowest) => writeln!(out, ", slowest {})", seconds(slowest)).unwrap(),
+                    None => writeln!(out, ")").unwrap(),
+                }
+                if !queries {
+                    continue;
+                }
+                for (ix, (sources, elapsed)) in resolver.queries.iter().enumerate() {
+                    let query = format!("query {} ({} sources)", ix + 1, sources);
+                    writeln!(out, "      {:<20} {:>8}", query, seconds(elapsed)).unwrap();
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,543 @@
+//! Rendering results as a tree.
+
+use crate::args::{Charset, Style};
+use crate::crates_io;
+use crate::impact;
+use crate::ledger::Date;
+use crate::theme::{Role, Theme};
+use crate::vet;
+use anyhow::{bail, Error};
+use cargo_fund::report::FundReport;
+use cargo_fund::{Group, Platform};
+use cargo_metadata::{Metadata, PackageId};
+use std::collections::HashMap;
+use std::fmt::{Display, Write};
+use std::io::IsTerminal;
+
+/// The characters used to draw the tree.
+#[derive(Clone, Copy, Debug)]
+pub struct Glyphs {
+    pub horizontal: char,
+    pub vertical: char,
+    pub tee: char,
+    pub corner: char,
+    pub down_tee: char,
+}
+
+impl Glyphs {
+    pub const UNICODE: Glyphs = Glyphs {
+        horizontal: '─',
+        vertical: '│',
+        tee: '├',
+        corner: '└',
+        down_tee: '┬',
+    };
+
+    pub const ASCII: Glyphs = Glyphs {
+        horizontal: '-',
+        vertical: '|',
+        tee: '|',
+        corner: '`',
+        down_tee: '+',
+    };
+
+    pub const ROUNDED: Glyphs = Glyphs {
+        corner: '╰',
+        ..Glyphs::UNICODE
+    };
+
+    /// The glyphs for a style preset, drawn with ASCII characters whatever the style if that's
+    /// all `charset` allows.
+    pub fn for_style(style: Style, charset: Charset) -> Glyphs {
+        match (style, charset) {
+            (_, Charset::Ascii) | (Style::Ascii, _) => Glyphs::ASCII,
+            (Style::Unicode | Style::Compact, _) => Glyphs::UNICODE,
+            (Style::Rounded, _) => Glyphs::ROUNDED,
+        }
+    }
+
+    /// Whether every glyph is an ASCII character.
+    pub fn is_ascii(&self) -> bool {
+        [
+            self.horizontal,
+            self.vertical,
+            self.tee,
+            self.corner,
+            self.down_tee,
+        ]
+        .iter()
+        .all(char::is_ascii)
+    }
+
+    /// Override a single glyph from a `NAME=GLYPH` specification, such as `corner=+`.
+    pub fn set(&mut self, spec: &str) -> Result<(), Error> {
+        let (name, glyph) = match spec.split_once('=') {
+            Some(split) => split,
+            None => bail!("glyph must be given as NAME=GLYPH: {}", spec),
+        };
+        let mut chars = glyph.chars();
+        let glyph = match (chars.next(), chars.next()) {
+            (Some(glyph), None) => glyph,
+            _ => bail!("glyph must be a single character: {:?}", glyph),
+        };
+        match name {
+            "horizontal" => self.horizontal = glyph,
+            "vertical" => self.vertical = glyph,
+            "tee" => self.tee = glyph,
+            "corner" => self.corner = glyph,
+            "down-tee" => self.down_tee = glyph,
+            _ => bail!(
+                "unknown glyph {:?}; expected one of horizontal, vertical, tee, corner, down-tee",
+                name
+            ),
+        }
+        Ok(())
+    }
+}
+
+/// The width of the tree drawn before each link line, including the following space.
+const PREFIX_WIDTH: usize = 5;
+
+/// The width of the tree drawn before each package line, including the following space.
+const PACKAGE_PREFIX_WIDTH: usize = 8;
+
+/// Options controlling how the tree is drawn and what is written in each line.
+pub struct TreeOptions {
+    pub glyphs: Glyphs,
+    /// The characters that may be written besides the glyphs.
+    pub charset: Charset,
+    /// Put all the links of a group on a single line.
+    pub compact: bool,
+    pub show_descriptions: bool,
+    /// Show where each link was found.
+    pub show_provenance: bool,
+    /// The impact of each package, to show next to each group, if it should be shown.
+    pub impact: Option<HashMap<PackageId, f64>>,
+    /// The crates.io download count of each crate by name, to show next to each package, if it
+    /// should be shown.
+    pub downloads: Option<HashMap<String, u64>>,
+    /// The date of each crate's latest release by name, to show next to each package, if it
+    /// should be shown.
+    pub releases: Option<HashMap<String, Date>>,
+    /// The latest version on crates.io of each package that's a breaking change from it, to show
+    /// next to it, if it should be shown.
+    pub outdated: Option<HashMap<PackageId, String>>,
+    /// How far cargo-vet vouches for each package, to show next to it, if it should be shown.
+    pub vet: Option<HashMap<PackageId, vet::Status>>,
+    /// The colors to use, or `None` for uncolored output.
+    pub theme: Option<Theme>,
+    /// The number of columns to fit the output into, or `None` for no limit.
+    pub width: Option<usize>,
+    /// Prefix each link with an icon for its platform, unless the charset is ASCII.
+    pub icons: bool,
+}
+
+impl TreeOptions {
+    fn paint(&self, role: Role, text: impl Display) -> String {
+        match &self.theme {
+            Some(theme) => theme.paint(role, text),
+            None => text.to_string(),
+        }
+    }
+}
+
+/// Write the results in a pretty tree.
+pub fn write_results(
+    out: &mut String,
+    metadata: &Metadata,
+    report: &FundReport,
+    groups: &[Group],
+    options: &TreeOptions,
+) {
+    let skipped = &report.skipped;
+    let reasons = [
+        (skipped.no_repository, "have no repository"),
+        (skipped.no_links, "have no funding links"),
+        (skipped.not_looked_up, "couldn't be looked up"),
+        (
+            skipped.rate_limited,
+            "weren't looked up before the rate limit ran out",
+        ),
+    ]
+    .iter()
+    .filter(|(count, _)| *count > 0)
+    .map(|(count, reason)| format!("{} {}", count, reason))
+    .collect::<Vec<_>>();
+    write!(
+        out,
+        "{} (found funding links for {} out of {} dependencies",
+        report.workspace_root.display(),
+        report.packages.len(),
+        report.considered()
+    )
+    .unwrap();
+    if !reasons.is_empty() {
+        write!(out, "; {}", reasons.join(", ")).unwrap();
+    }
+    writeln!(out, ")").unwrap();
+    write_tree(out, metadata, groups, options);
+}
+
+/// Write groups of links and packages as a tree.
+pub fn write_tree(out: &mut String, metadata: &Metadata, groups: &[Group], options: &TreeOptions) {
+    let Glyphs {
+        horizontal: h,
+        vertical: v,
+        tee,
+        corner,
+        down_tee,
+    } = options.glyphs;
+    let last_mapping_ix = if let Some(ix) = groups.len().checked_sub(1) {
+        ix
+    } else {
+        return;
+    };
+    for (mapping_ix, group) in groups.iter().enumerate() {
+        let mut count = match group.pkgs.len() {
+            1 => " (1 package".to_string(),
+            n => format!(" ({} packages", n),
+        };
+        if let Some(scores) = &options.impact {
+            write!(count, ", impact {:.2}", impact::group_score(scores, group)).unwrap();
+        }
+        count.push(')');
+        let lines = link_lines(group, &count, options);
+        let last_link_ix = lines.len() - 1;
+        for (link_ix, line) in lines.iter().enumerate() {
+            let mut prefix = String::new();
+            // first two characters of each link line
+            match (mapping_ix, link_ix) {
+                (0, 0) if last_mapping_ix == 0 => {
+                    // first line of first and only link section
+                    write!(prefix, "{}{}", h, h).unwrap();
+                }
+                (mapping_ix, 0) if mapping_ix < last_mapping_ix => {
+                    // first line of a link section
+                    write!(prefix, "{}{}", tee, h).unwrap();
+                }
+                (mapping_ix, _) if mapping_ix < last_mapping_ix => {
+                    // non-first line of non-final link section
+                    write!(prefix, "{} ", v).unwrap();
+                }
+                (mapping_ix, 0) if mapping_ix == last_mapping_ix => {
+                    // first line of last link section of many
+                    write!(prefix, "{}{}", corner, h).unwrap();
+                }
+                // non-first line of final link section
+                _ => prefix.push_str("  "),
+            }
+            // second two characters of each link line
+            match link_ix {
+                0 if last_link_ix > 0 => {
+                    // first link line of many
+                    write!(prefix, "{}{}", down_tee, h).unwrap();
+                }
+                0 if last_link_ix == 0 => {
+                    // first and only link line
+                    write!(prefix, "{}{}", h, h).unwrap();
+                }
+                link_ix if link_ix < last_link_ix => {
+                    // non-first, non-final link line
+                    write!(prefix, "{}{}", tee, h).unwrap();
+                }
+                link_ix if link_ix == last_link_ix => {
+                    // final link line of many
+                    write!(prefix, "{}{}", corner, h).unwrap();
+                }
+                _ => prefix.push_str("  "),
+            }
+            write!(out, "{} {}", options.paint(Role::Tree, prefix), line).unwrap();
+            if link_ix == 0 {
+                out.push_str(&options.paint(Role::Detail, &count));
+            }
+            out.push('\n');
+        }
+        let last_pkg_ix = group.pkgs.len() - 1;
+        for (pkg_ix, pkg) in group.pkgs.iter().enumerate() {
+            let mut prefix = String::new();
+            if mapping_ix < last_mapping_ix {
+                write!(prefix, "{}    ", v).unwrap();
+            } else {
+                prefix.push_str("     ");
+            }
+            if pkg_ix == last_pkg_ix {
+                write!(prefix, "{}{}", corner, h).unwrap();
+            } else {
+                write!(prefix, "{}{}", tee, h).unwrap();
+            }
+            let details = package_details(pkg, &metadata[pkg].name, options);
+            let pkg = &metadata[pkg];
+            write!(
+                out,
+                "{} {} {}",
+                options.paint(Role::Tree, prefix),
+                options.paint(Role::Package, &pkg.name),
+                pkg.version
+            )
+            .unwrap();
+            if !details.is_empty() {
+                out.push_str(&options.paint(Role::Detail, &details));
+            }
+            match &pkg.description {
+                Some(description) if options.show_descriptions => {
+                    let mut description = format!(" - {}", one_line(description));
+                    if let Some(width) = options.width {
+                        let used = PACKAGE_PREFIX_WIDTH
+                            + pkg.name.chars().count()
+                            + pkg.version.to_string().len()
+                            + details.chars().count()
+                            + 1;
+                        description =
+                            ellipsize(&description, width.saturating_sub(used), options.charset);
+                    }
+                    writeln!(out, "{}", options.paint(Role::Detail, description)).unwrap()
+                }
+                _ => out.push('\n'),
+            }
+        }
+    }
+}
+
+/// A line heading a group, before it is colored.
+struct HeadLine {
+    /// A heading shown before the text in compact mode.
+    lead: Option<String>,
+    role: Role,
+    text: String,
+    /// An annotation shown after the text.
+    note: String,
+}
+
+/// The lines that head a group: its heading, if any, followed by its links.
+///
+/// In compact mode, these are all joined onto one line. If a width is set, the text of each line
+/// is shortened to fit, leaving room for the tree prefix and, on the first line, for `count`.
+fn link_lines(group: &Group, count: &str, options: &TreeOptions) -> Vec<String> {
+    let mut lines = vec![];
+    if let Some(heading) = &group.heading {
+        lines.push(HeadLine {
+            lead: None,
+            role: Role::Heading,
+            text: heading.clone(),
+            note: String::new(),
+        });
+    }
+    for link in &group.links {
+        let mut note = if options.show_provenance && !link.provenance.is_empty() {
+            let provenance = link
+                .provenance
+                .iter()
+                .map(|p| p.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!(" (from {})", provenance)
+        } else {
+            String::new()
+        };
+        if link.disallowed {
+            note.push_str(" (platform not allowed)");
+        }
+        if link.sponsoring {
+            note.push_str(" (sponsoring)");
+        }
+        if let Some(sponsors) = link.sponsors {
+            let plural = if sponsors == 1 { "" } else { "s" };
+            write!(note, " ({} sponsor{})", sponsors, plural).unwrap();
+        }
+        if let Some(patrons) = link.patrons {
+            let plural = if patrons == 1 { "" } else { "s" };
+            write!(note, " ({} patron{})", patrons, plural).unwrap();
+        }
+        if let Some(goal) = &link.goal {
+            write!(
+                note,
+                " ({}% of goal: {})",
+                goal.percent_complete, goal.title
+            )
+            .unwrap();
+        }
+        let text = if options.icons && options.charset == Charset::Unicode {
+            let platform = Platform::from(link.platform.as_str());
+            format!("{} {}", platform_icon(&platform), link.url)
+        } else {
+            link.url.clone()
+        };
+        lines.push(HeadLine {
+            lead: None,
+            role: Role::Link,
+            text,
+            note,
+        });
+    }
+    if options.compact && !group.links.is_empty() {
+        let lead = group.heading.as_ref().map(|heading| {
+            lines.remove(0);
+            heading.clone()
+        });
+        let text = lines
+            .iter()
+            .map(|line| format!("{}{}", line.text, line.note))
+            .collect::<Vec<_>>()
+            .join(", ");
+        lines = vec![HeadLine {
+            lead,
+            role: Role::Link,
+            text,
+            note: String::new(),
+        }];
+    }
+    lines
+        .into_iter()
+        .enumerate()
+        .map(|(ix, line)| {
+            let mut out = String::new();
+            let mut used = PREFIX_WIDTH + line.note.chars().count();
+            if ix == 0 {
+                used += count.chars().count();
+            }
+            if let Some(lead) = &line.lead {
+                used += lead.chars().count() + 2;
+                write!(out, "{}: ", options.paint(Role::Heading, lead)).unwrap();
+            }
+            let text = match options.width {
+                Some(width) => ellipsize(&line.text, width.saturating_sub(used), options.charset),
+                None => line.text,
+            };
+            out.push_str(&options.paint(line.role, text));
+            if !line.note.is_empty() {
+                out.push_str(&options.paint(Role::Detail, &line.note));
+            }
+            out
+        })
+        .collect()
+}
+
+/// A small icon to make each platform easy to spot.
+fn platform_icon(platform: &Platform) -> &'static str {
+    match platform {
+        Platform::CommunityBridge => "🌉",
+        Platform::Custom => "🔗",
+        Platform::Github => "💖",
+        Platform::IssueHunt => "🎯",
+        Platform::Kofi => "☕",
+        Platform::Liberapay => "🔁",
+        Platform::OpenCollective => "🤝",
+        Platform::Otechie => "💼",
+        Platform::Patreon => "🎨",
+        Platform::Tidelift => "🌊",
+        Platform::Other(_) => "🔗",
+    }
+}
+
+/// The charset to use when none is configured. A style drawn with box-drawing characters asks
+/// for them; otherwise they're used on a terminal, but not when the output is piped, redirected,
+/// or written to a file with `--output`.
+pub fn default_charset(style: Option<Style>, to_file: bool) -> Charset {
+    match style {
+        Some(Style::Unicode | Style::Rounded | Style::Compact) => Charset::Unicode,
+        _ if to_file || !std::io::stdout().is_terminal() => Charset::Ascii,
+        _ => Charset::Unicode,
+    }
+}
+
+/// The width of the terminal attached to stdout, if any.
+pub fn terminal_width() -> Option<usize> {
+    if !std::io::stdout().is_terminal() {
+        return None;
+    }
+    terminal_size::terminal_size().map(|(terminal_size::Width(width), _)| width as usize)
+}
+
+/// Shorten `text` to at most `max` characters, marking the cut with an ellipsis, or with `...`
+/// if the charset is ASCII.
+fn ellipsize(text: &str, max: usize, charset: Charset) -> String {
+    let ellipsis = match charset {
+        Charset::Unicode => "…",
+        Charset::Ascii => "...",
+    };
+    if text.chars().count() <= max {
+        text.to_string()
+    } else {
+        let kept = max.saturating_sub(ellipsis.chars().count());
+        let mut shortened: String = text.chars().take(kept).collect();
+        shortened.push_str(ellipsis);
+        shortened
+    }
+}
+
+/// What crates.io and cargo-vet know about a package, to write after its version, such as
+/// ` (3.4M downloads, active, released 2 months ago, outdated: 2.0.0 is out, unaudited)`, or
+/// nothing if none of it is shown.
+fn package_details(id: &PackageId, name: &str, options: &TreeOptions) -> String {
+    let mut details = vec![];
+    if let Some(&count) = options.downloads.as_ref().and_then(|d| d.get(name)) {
+        details.push(format!("{} downloads", crates_io::abbreviate(count)));
+    }
+    if let Some(&release) = options.releases.as_ref().and_then(|r| r.get(name)) {
+        let today = Date::today();
+        if today.days() - release.days() <= crates_io::ACTIVE_DAYS {
+            details.push("active".to_string());
+        }
+        details.push(format!("released {}", crates_io::ago(release, today)));
+    }
+    if let Some(latest) = options.outdated.as_ref().and_then(|o| o.get(id)) {
+        details.push(format!("outdated: {} is out", latest));
+    }
+    if let Some(status) = options.vet.as_ref().and_then(|v| v.get(id)) {
+        details.push(status.as_str().to_string());
+    }
+    if details.is_empty() {
+        return String::new();
+    }
+    format!(" ({})", details.join(", "))
+}
+
+/// Collapse a possibly multi-line description onto a single line.
+fn one_line(description: &str) -> String {
+    description.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn set_glyph() {
+        let mut glyphs = Glyphs::UNICODE;
+        glyphs.set("corner=+").unwrap();
+        assert_eq!(glyphs.corner, '+');
+        assert!(glyphs.set("corner").is_err());
+        assert!(glyphs.set("corner=++").is_err());
+        assert!(glyphs.set("elbow=+").is_err());
+    }
+
+    #[test]
+    fn ellipsize_long_text() {
+        let unicode = Charset::Unicode;
+        assert_eq!(
+            ellipsize("https://ko-fi.com/x", 40, unicode),
+            "https://ko-fi.com/x"
+        );
+        assert_eq!(ellipsize("https://ko-fi.com/x", 10, unicode), "https://k…");
+        assert_eq!(ellipsize("https://ko-fi.com/x", 0, unicode), "…");
+        assert_eq!(
+            ellipsize("https://ko-fi.com/x", 10, Charset::Ascii),
+            "https:/..."
+        );
+    }
+
+    #[test]
+    fn ascii_charset() {
+        for style in [Style::Unicode, Style::Rounded, Style::Compact] {
+            assert!(Glyphs::for_style(style, Charset::Ascii).is_ascii());
+            assert!(!Glyphs::for_style(style, Charset::Unicode).is_ascii());
+        }
+        // asking for box-drawing characters asks for them even when the output is a file
+        assert_eq!(
+            default_charset(Some(Style::Rounded), true),
+            Charset::Unicode
+        );
+        assert_eq!(default_charset(Some(Style::Ascii), true), Charset::Ascii);
+        assert_eq!(default_charset(None, true), Charset::Ascii);
+    }
+}
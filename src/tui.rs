@@ -0,0 +1,327 @@
+//! `cargo fund tui`: an interactive browser of the funding links found.
+//!
+//! Maintainers are listed on the left, and the selected one's funding links and packages on the
+//! right. The list can be searched and filtered by platform, and maintainers can be marked as
+//! funded. Marks are kept in the user's data directory, so they persist between sessions and
+//! projects.
+
+use anyhow::{Context as _, Error};
+use cargo_fund::report::{FundReport, FundingLink};
+use cargo_fund::LinkSource;
+use cargo_metadata::{Metadata, PackageId};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::PathBuf;
+
+/// Browse the report until the user quits.
+pub fn run(
+    metadata: &Metadata,
+    source_map: &HashMap<LinkSource, HashSet<PackageId>>,
+    report: &FundReport,
+) -> Result<(), Error> {
+    let mut app = App::new(items(metadata, source_map, report), Marks::load());
+    let mut terminal = ratatui::init();
+    let result = app.run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+/// A maintainer, or a set of links without one, as listed.
+struct Item {
+    /// The key that marks are kept by.
+    key: String,
+    title: String,
+    links: Vec<FundingLink>,
+    pkgs: Vec<String>,
+}
+
+fn items(
+    metadata: &Metadata,
+    source_map: &HashMap<LinkSource, HashSet<PackageId>>,
+    report: &FundReport,
+) -> Vec<Item> {
+    let mut groups = cargo_fund::group_by_owner(source_map, &report.allowed());
+    groups.sort_by_cached_key(|group| group.name());
+    groups
+        .into_iter()
+        .map(|group| {
+            let pkgs = group
+                .pkgs
+                .iter()
+                .map(|id| format!("{} {}", metadata[id].name, metadata[id].version))
+                .collect::<Vec<_>>();
+            Item {
+                key: group.name(),
+                title: group.heading.clone().unwrap_or_else(|| pkgs.join(", ")),
+                links: group.links.into_iter().collect(),
+                pkgs,
+            }
+        })
+        .collect()
+}
+
+/// The maintainers marked as funded, by key.
+#[derive(Default)]
+struct Marks {
+    path: Option<PathBuf>,
+    funded: BTreeSet<String>,
+}
+
+impl Marks {
+    /// Load the marks from the user's data directory. Missing or unreadable marks are empty.
+    fn load() -> Marks {
+        let path = dirs::data_local_dir().map(|dir| dir.join("cargo-fund").join("funded.json"));
+        let funded = path
+            .as_ref()
+            .and_then(|path| std::fs::read(path).ok())
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+        Marks { path, funded }
+    }
+
+    fn save(&self) -> Result<(), Error> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, serde_json::to_vec(&self.funded)?)
+            .with_context(|| format!("error writing {}", path.display()))
+    }
+}
+
+struct App {
+    items: Vec<Item>,
+    marks: Marks,
+    /// The search text, which matches maintainers, links, and packages.
+    query: String,
+    searching: bool,
+    /// Every platform with links, and the one being filtered on, if any.
+    platforms: Vec<String>,
+    platform: Option<usize>,
+    list: ListState,
+    /// A message for the status line, such as an error opening a link.
+    message: Option<String>,
+}
+
+impl App {
+    fn new(items: Vec<Item>, marks: Marks) -> App {
+        let platforms = items
+            .iter()
+            .flat_map(|item| &item.links)
+            .map(|link| link.platform.clone())
+            .collect::<BTreeSet<_>>();
+        App {
+            items,
+            marks,
+            query: String::new(),
+            searching: false,
+            platforms: platforms.into_iter().collect(),
+            platform: None,
+            list: ListState::default().with_selected(Some(0)),
+            message: None,
+        }
+    }
+
+    /// The indexes of the items that match the search and platform filter.
+    fn visible(&self) -> Vec<usize> {
+        let query = self.query.to_lowercase();
+        let platform = self.platform.map(|ix| &self.platforms[ix]);
+        let matches = |item: &Item| {
+            let texts = std::iter::once(&item.title)
+                .chain(&item.pkgs)
+                .chain(item.links.iter().map(|link| &link.url));
+            let searched =
+                query.is_empty() || texts.into_iter().any(|t| t.to_lowercase().contains(&query));
+            let filtered = match platform {
+                Some(platform) => item.links.iter().any(|l| &l.platform == platform),
+                None => true,
+            };
+            searched && filtered
+        };
+        (0..self.items.len())
+            .filter(|&ix| matches(&self.items[ix]))
+            .collect()
+    }
+
+    fn selected(&self) -> Option<&Item> {
+        let visible = self.visible();
+        let ix = self.list.selected()?.min(visible.len().checked_sub(1)?);
+        Some(&self.items[visible[ix]])
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<(), Error> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            if self.searching {
+                match key.code {
+                    KeyCode::Enter => self.searching = false,
+                    KeyCode::Esc => {
+                        self.searching = false;
+                        self.query.clear();
+                    }
+                    KeyCode::Backspace => {
+                        self.query.pop();
+                    }
+                    KeyCode::Char(c) => self.query.push(c),
+                    _ => (),
+                }
+                self.list.select(Some(0));
+                continue;
+            }
+            self.message = None;
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Down | KeyCode::Char('j') => self.list.select_next(),
+                KeyCode::Up | KeyCode::Char('k') => self.list.select_previous(),
+                KeyCode::Char('/') => self.searching = true,
+                KeyCode::Char('p') => {
+                    self.platform = match self.platform {
+                        None if !self.platforms.is_empty() => Some(0),
+                        Some(ix) if ix + 1 < self.platforms.len() => Some(ix + 1),
+                        _ => None,
+                    };
+                    self.list.select(Some(0));
+                }
+                KeyCode::Char('f') | KeyCode::Char(' ') => {
+                    if let Some(key) = self.selected().map(|item| item.key.clone()) {
+                        if !self.marks.funded.remove(&key) {
+                            self.marks.funded.insert(key);
+                        }
+                        if let Err(e) = self.marks.save() {
+                            self.message = Some(format!("{:#}", e));
+                        }
+                    }
+                }
+                KeyCode::Char('o') | KeyCode::Enter => {
+                    let urls = self.selected().map(|item| {
+                        let urls = item.links.iter().map(|link| link.url.clone());
+                        urls.collect::<Vec<_>>()
+                    });
+                    for url in urls.unwrap_or_default() {
+                        if let Err(e) = crate::browser::open(&url) {
+                            self.message = Some(format!("could not open {}: {}", url, e));
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, status] =
+            Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+        let [left, right] =
+            Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)])
+                .areas(main);
+
+        let visible = self.visible();
+        let list = visible
+            .iter()
+            .map(|&ix| {
+                let item = &self.items[ix];
+                let mark = if self.marks.funded.contains(&item.key) {
+                    "[x]"
+                } else {
+                    "[ ]"
+                };
+                ListItem::new(format!("{} {} ({})", mark, item.title, item.pkgs.len()))
+            })
+            .collect::<Vec<_>>();
+        let list = List::new(list)
+            .block(Block::default().borders(Borders::ALL).title("Maintainers"))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, left, &mut self.list);
+
+        let mut lines = vec![];
+        if let Some(item) = self.selected() {
+            let bold = Style::default().add_modifier(Modifier::BOLD);
+            lines.push(Line::from(Span::styled(item.title.clone(), bold)));
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled("Funding links", bold)));
+            for link in &item.links {
+                lines.push(Line::from(format!("  {}  {}", link.platform, link.url)));
+            }
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled("Packages", bold)));
+            for pkg in &item.pkgs {
+                lines.push(Line::from(format!("  {}", pkg)));
+            }
+        }
+        let details = Paragraph::new(lines)
+            .block(Block::default().borders(Borders::ALL).title("Details"))
+            .wrap(Wrap { trim: false });
+        frame.render_widget(details, right);
+
+        let line = if self.searching {
+            format!("/{}", self.query)
+        } else if let Some(message) = &self.message {
+            message.clone()
+        } else {
+            let platform = match self.platform {
+                Some(ix) => self.platforms[ix].as_str(),
+                None => "all",
+            };
+            format!(
+                "↑↓ move  / search  p platform: {}  f mark funded  o open  q quit",
+                platform
+            )
+        };
+        frame.render_widget(Paragraph::new(line), status);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn item(title: &str, platform: &str, pkg: &str) -> Item {
+        Item {
+            key: title.to_lowercase(),
+            title: title.to_string(),
+            links: vec![FundingLink {
+                platform: platform.to_string(),
+                url: format!("https://example.com/{}", title),
+                login: None,
+                provenance: BTreeSet::new(),
+                disallowed: false,
+                goal: None,
+                sponsoring: false,
+                sponsors: None,
+                patrons: None,
+            }],
+            pkgs: vec![pkg.to_string()],
+        }
+    }
+
+    #[test]
+    fn filters() {
+        let mut app = App::new(
+            vec![
+                item("alice", "GITHUB", "anyhow 1.0.0"),
+                item("bob", "OPEN_COLLECTIVE", "serde 1.0.0"),
+            ],
+            Marks::default(),
+        );
+        assert_eq!(app.visible(), [0, 1]);
+        app.query = "SERDE".to_string();
+        assert_eq!(app.visible(), [1]);
+        app.query.clear();
+        app.platform = Some(0);
+        assert_eq!(app.platforms[0], "GITHUB");
+        assert_eq!(app.visible(), [0]);
+    }
+}
@@ -0,0 +1,179 @@
+//! `cargo fund verify`: checking that funding links still lead somewhere, since stale links such
+//! as a closed Patreon page or a lapsed custom domain waste the time of anyone trying to donate.
+//!
+//! Each link is requested with `HEAD`, falling back to `GET` for sites that don't allow `HEAD`.
+//! The `HEAD` requests are conditional, so a link unchanged since it was last checked is judged as
+//! it was then.
+//! Redirects are followed, and a link that ends up on a different page is listed as redirected so
+//! that it can be updated; one that only gains `https` or loses a trailing slash is not.
+
+use crate::responses::Responses;
+use cargo_fund::report::FundReport;
+use cargo_fund::Link;
+use futures_util::StreamExt;
+use reqwest::StatusCode;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::convert::TryFrom;
+use std::fmt::Write;
+
+/// The number of links to check at once.
+const JOBS: usize = 8;
+
+/// What became of a funding link when it was requested.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(tag = "status", rename_all = "kebab-case")]
+pub enum Verdict {
+    Live,
+    /// The link led to a working page elsewhere.
+    Redirected {
+        to: String,
+    },
+    /// The site answered with an error, such as `404 Not Found`.
+    Dead {
+        code: u16,
+        reason: String,
+    },
+    /// The site couldn't be reached at all.
+    Unreachable {
+        reason: String,
+    },
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Checked {
+    pub url: String,
+    #[serde(flatten)]
+    pub verdict: Verdict,
+}
+
+/// Check every funding link in the report, in the order of their URLs.
+pub async fn verify(
+    client: &reqwest::Client,
+    responses: &Responses,
+    report: &FundReport,
+) -> Vec<Checked> {
+    let urls: BTreeSet<&str> = report
+        .packages
+        .iter()
+        .flat_map(|pkg| &pkg.links)
+        .map(|link| link.url.as_str())
+        .collect();
+    futures_util::stream::iter(urls)
+        .map(|url| async move {
+            Checked {
+                url: url.to_string(),
+                verdict: probe(client, responses, url).await,
+            }
+        })
+        .buffered(JOBS)
+        .collect()
+        .await
+}
+
+/// Request `url`, and judge what came back.
+async fn probe(client: &reqwest::Client, responses: &Responses, url: &str) -> Verdict {
+    let mut resp = responses
+        .head(client, url)
+        .await
+        .map(|resp| (resp.status, resp.url));
+    if let Ok((status, _)) = &resp {
+        // plenty of sites refuse `HEAD` outright, or answer it differently from `GET`
+        if status.is_client_error() || status.is_server_error() {
+            let get = client.get(url).send().await;
+            resp = get.map(|resp| (resp.status(), resp.url().to_string()));
+        }
+    }
+    let (status, landed) = match resp {
+        Ok(resp) => resp,
+        Err(e) => {
+            return Verdict::Unreachable {
+                reason: e.without_url().to_string(),
+            }
+        }
+    };
+    judge(url, status, &landed)
+}
+
+/// Judge a link by the status and URL it ended up at after following redirects.
+fn judge(url: &str, status: StatusCode, landed: &str) -> Verdict {
+    if !status.is_success() {
+        return Verdict::Dead {
+            code: status.as_u16(),
+            reason: status.canonical_reason().unwrap_or_default().to_string(),
+        };
+    }
+    // custom links keep the case of their paths, which some sites care about
+    let canonical = |url: &str| {
+        Link::try_from(("CUSTOM", url))
+            .map_or_else(|_| url.to_string(), |link| link.canonical().uri.to_string())
+    };
+    if canonical(url) == canonical(landed) {
+        Verdict::Live
+    } else {
+        Verdict::Redirected {
+            to: landed.to_string(),
+        }
+    }
+}
+
+/// Write the links that are dead, unreachable, or redirected, after a count of each.
+pub fn write(out: &mut String, checked: &[Checked]) {
+    let count = |f: fn(&Verdict) -> bool| checked.iter().filter(|c| f(&c.verdict)).count();
+    let redirected = count(|v| matches!(v, Verdict::Redirected { .. }));
+    let dead = count(|v| matches!(v, Verdict::Dead { .. }));
+    let unreachable = count(|v| matches!(v, Verdict::Unreachable { .. }));
+    writeln!(
+        out,
+        "checked {} funding links: {} live, {} redirected, {} dead, {} unreachable",
+        checked.len(),
+        checked.len() - redirected - dead - unreachable,
+        redirected,
+        dead,
+        unreachable
+    )
+    .unwrap();
+    for checked in checked {
+        match &checked.verdict {
+            Verdict::Live => (),
+            Verdict::Redirected { to } => {
+                writeln!(out, "  redirected  {} -> {}", checked.url, to).unwrap()
+            }
+            Verdict::Dead { code, reason } => {
+                writeln!(out, "  dead        {} ({} {})", checked.url, code, reason).unwrap()
+            }
+            Verdict::Unreachable { reason } => {
+                writeln!(out, "  unreachable {} ({})", checked.url, reason).unwrap()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn judges() {
+        let url = "https://www.patreon.com/someone";
+        assert_eq!(judge(url, StatusCode::OK, url), Verdict::Live);
+        // gaining a trailing slash or losing `www.` is the same page
+        assert_eq!(
+            judge(url, StatusCode::OK, "https://patreon.com/someone/"),
+            Verdict::Live
+        );
+        assert_eq!(
+            judge(url, StatusCode::OK, "https://patreon.com/someone-else"),
+            Verdict::Redirected {
+                to: "https://patreon.com/someone-else".to_string()
+            }
+        );
+        assert_eq!(
+            judge(url, StatusCode::NOT_FOUND, url),
+            Verdict::Dead {
+                code: 404,
+                reason: "Not Found".to_string()
+            }
+        );
+    }
+}
@@ -0,0 +1,193 @@
+//! `--show-vet`: whether each dependency has been audited with cargo-vet, read from the
+//! workspace's `supply-chain` directory, so that supply-chain teams can see which of the crates
+//! they rely on they haven't been able to audit.
+//!
+//! A version is audited if a full audit covers it, or a chain of delta audits leads to it from one,
+//! whether the audits are the workspace's own, imported, or of a trusted publisher. Failing that,
+//! it's exempted if an exemption covers it, or delta audits lead to it from one. Audit criteria
+//! aren't distinguished, and violations are left out.
+
+use anyhow::{Context, Error};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::Path;
+
+/// The directory cargo-vet keeps its store in, in the workspace root.
+pub const STORE: &str = "supply-chain";
+
+/// How far cargo-vet vouches for a version of a crate.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Status {
+    Audited,
+    Exempted,
+    Unaudited,
+}
+
+impl Status {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Status::Audited => "audited",
+            Status::Exempted => "exempted",
+            Status::Unaudited => "unaudited",
+        }
+    }
+}
+
+/// An audit or exemption, of a single version or of the changes between two.
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+struct Entry {
+    version: Option<String>,
+    /// The changes audited, such as `1.0.0 -> 1.1.0`.
+    delta: Option<String>,
+    violation: Option<String>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct Audits {
+    audits: HashMap<String, Vec<Entry>>,
+    trusted: HashMap<String, Vec<toml::Value>>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct Config {
+    exemptions: HashMap<String, Vec<Entry>>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct Imports {
+    audits: HashMap<String, Audits>,
+}
+
+/// The audits and exemptions in a cargo-vet store.
+#[derive(Default)]
+pub struct Store {
+    audits: HashMap<String, Vec<Entry>>,
+    exemptions: HashMap<String, Vec<Entry>>,
+    trusted: HashSet<String>,
+}
+
+/// Read a TOML file of the store, treating a missing one as empty.
+fn read<T: Default + for<'de> Deserialize<'de>>(path: &Path) -> Result<T, Error> {
+    match std::fs::read_to_string(path) {
+        Ok(contents) => {
+            toml::from_str(&contents).with_context(|| format!("error parsing {}", path.display()))
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(T::default()),
+        Err(e) => Err(Error::from(e).context(format!("error reading {}", path.display()))),
+    }
+}
+
+impl Store {
+    /// Read the store in `dir`, such as a workspace's `supply-chain` directory.
+    pub fn load(dir: &Path) -> Result<Store, Error> {
+        if !dir.is_dir() {
+            anyhow::bail!(
+                "no cargo-vet store at {}; create one with `cargo vet init`",
+                dir.display()
+            );
+        }
+        let own: Audits = read(&dir.join("audits.toml"))?;
+        let config: Config = read(&dir.join("config.toml"))?;
+        let imports: Imports = read(&dir.join("imports.lock"))?;
+        let mut store = Store {
+            exemptions: config.exemptions,
+            ..Store::default()
+        };
+        for audits in std::iter::once(own).chain(imports.audits.into_values()) {
+            for (name, entries) in audits.audits {
+                store.audits.entry(name).or_default().extend(entries);
+            }
+            store.trusted.extend(audits.trusted.into_keys());
+        }
+        Ok(store)
+    }
+
+    /// How far cargo-vet vouches for `version` of the crate `name`.
+    pub fn status(&self, name: &str, version: &str) -> Status {
+        let audits = self.audits.get(name).map_or(&[][..], Vec::as_slice);
+        let exemptions = self.exemptions.get(name).map_or(&[][..], Vec::as_slice);
+        let audited = full_versions(audits);
+        if self.trusted.contains(name) || reachable(audited.clone(), audits, version) {
+            Status::Audited
+        } else if reachable(audited.chain(full_versions(exemptions)), audits, version) {
+            Status::Exempted
+        } else {
+            Status::Unaudited
+        }
+    }
+}
+
+/// The versions that entries cover in full, rather than as deltas.
+fn full_versions(entries: &[Entry]) -> impl Iterator<Item = &str> + Clone {
+    entries
+        .iter()
+        .filter(|entry| entry.violation.is_none())
+        .filter_map(|entry| entry.version.as_deref())
+}
+
+/// Whether `version` is one of `roots`, or is reached from one through the deltas of `audits`.
+fn reachable<'a>(roots: impl Iterator<Item = &'a str>, audits: &'a [Entry], version: &str) -> bool {
+    let mut deltas: HashMap<&str, Vec<&str>> = HashMap::new();
+    for audit in audits.iter().filter(|audit| audit.violation.is_none()) {
+        if let Some((from, to)) = audit.delta.as_deref().and_then(|d| d.split_once("->")) {
+            deltas.entry(from.trim()).or_default().push(to.trim());
+        }
+    }
+    let mut queue = roots.collect::<VecDeque<_>>();
+    let mut seen = HashSet::new();
+    while let Some(reached) = queue.pop_front() {
+        if reached == version {
+            return true;
+        }
+        if seen.insert(reached) {
+            queue.extend(deltas.get(reached).into_iter().flatten());
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn follows_deltas() {
+        let store = Store {
+            audits: toml::from_str::<Audits>(
+                r#"
+                [[audits.serde]]
+                criteria = "safe-to-deploy"
+                version = "1.0.0"
+
+                [[audits.serde]]
+                criteria = "safe-to-deploy"
+                delta = "1.0.0 -> 1.1.0"
+
+                [[audits.serde]]
+                criteria = "safe-to-deploy"
+                delta = "2.0.0 -> 2.1.0"
+                "#,
+            )
+            .unwrap()
+            .audits,
+            exemptions: toml::from_str::<Config>(
+                r#"
+                [[exemptions.serde]]
+                version = "2.0.0"
+                criteria = "safe-to-deploy"
+                "#,
+            )
+            .unwrap()
+            .exemptions,
+            trusted: HashSet::new(),
+        };
+        assert_eq!(store.status("serde", "1.1.0"), Status::Audited);
+        assert_eq!(store.status("serde", "2.1.0"), Status::Exempted);
+        assert_eq!(store.status("serde", "3.0.0"), Status::Unaudited);
+        assert_eq!(store.status("rand", "1.0.0"), Status::Unaudited);
+    }
+}
@@ -8,22 +8,26 @@ fn sanitize_stderr(bytes: &[u8]) -> String {
     stderr.replace("    Blocking waiting for file lock on package cache\n", "")
 }
 
+fn client_package_expected(client_package: &Path) -> String {
+    format!(
+        "{} (found funding links for 1 out of 3 dependencies; 2 have no funding links)
+--+- https://acfoltzer.net/bare_relative_link (1 package)
+  |- https://www.acfoltzer.net/
+  |- https://www.acfoltzer.net/another_url
+  |- https://issuehunt.io/r/acfoltzer
+  |- https://ko-fi.com/acfoltzer
+  |- https://liberapay.com/acfoltzer
+  `- https://patreon.com/acfoltzer
+     `- funding-test 0.1.0\n",
+        client_package.display()
+    )
+}
+
 #[test]
 fn client_package_output_expected() {
     let root = Path::new(env!("CARGO_MANIFEST_DIR"));
     let client_package = root.join("tests").join("client-package");
-    let expected = format!(
-        "{} (found funding links for 1 out of 3 dependencies)
-──┬─ https://acfoltzer.net/bare_relative_link
-  ├─ https://www.acfoltzer.net/
-  ├─ https://www.acfoltzer.net/another_url
-  ├─ https://issuehunt.io/r/acfoltzer
-  ├─ https://ko-fi.com/acfoltzer
-  ├─ https://liberapay.com/acfoltzer
-  └─ https://patreon.com/acfoltzer
-     └─ funding-test 0.1.0\n",
-        client_package.display()
-    );
+    let expected = client_package_expected(&client_package);
     let exe = Path::new(env!("CARGO_BIN_EXE_cargo-fund"));
     let output = Command::new(exe)
         .current_dir(client_package)
@@ -41,21 +45,50 @@ fn client_package_output_expected() {
     assert_eq!(&sanitize_stderr(&output.stderr), "", "stderr matches");
 }
 
+#[test]
+fn client_package_replayed() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let client_package = root.join("tests").join("client-package");
+    let expected = client_package_expected(&client_package);
+    let exe = Path::new(env!("CARGO_BIN_EXE_cargo-fund"));
+    // the responses in `tests/fixtures` stand in for Github, so no token is needed
+    let output = Command::new(exe)
+        .current_dir(&client_package)
+        .arg("fund")
+        .arg("--replay")
+        .arg(root.join("tests").join("fixtures"))
+        .env_remove("CARGO_FUND_GITHUB_API_TOKEN")
+        .env("XDG_CONFIG_HOME", root.join("tests").join("no-config"))
+        .output()
+        .expect("cargo-fund runs");
+
+    assert!(output.status.success());
+    let stdout = std::str::from_utf8(&output.stdout).expect("stdout is valid UTF-8");
+    assert_eq!(stdout, expected, "stdout matches");
+}
+
 #[test]
 fn missing_token() {
     let root = Path::new(env!("CARGO_MANIFEST_DIR"));
     let expected =
         "Error: Github API token must be provided through the CARGO_FUND_GITHUB_API_TOKEN \
-         environment variable or the --github-api-token flag.\n";
+         environment variable or the --github-api-token flag, or kept with `cargo fund login`.\n";
     let exe = Path::new(env!("CARGO_BIN_EXE_cargo-fund"));
     let output = Command::new(exe)
         .current_dir(root.join("tests").join("client-package"))
         .arg("fund")
         // not necessary for CI, but makes local testing easier
         .env_remove("CARGO_FUND_GITHUB_API_TOKEN")
+        // nor is hiding a token kept with `cargo fund login`
+        .env("XDG_CONFIG_HOME", root.join("tests").join("no-config"))
         .output()
         .expect("cargo-fund runs");
     assert!(!output.status.success());
+    assert_eq!(
+        output.status.code(),
+        Some(2),
+        "exits with the authentication status"
+    );
     assert_eq!(&output.stdout, b"", "stdout matches");
     assert_eq!(&sanitize_stderr(&output.stderr), expected, "stderr matches");
 }
@@ -65,7 +98,8 @@ fn invalid_token() {
     let root = Path::new(env!("CARGO_MANIFEST_DIR"));
     let expected =
         "Error: Invalid Github API token. Create a token with the `public_repo` and `user` scopes \
-         at https://github.com/settings/tokens.\n";
+         at https://github.com/settings/tokens.\n  = note: for more, run `cargo fund explain \
+         invalid-token`\n";
     let exe = Path::new(env!("CARGO_BIN_EXE_cargo-fund"));
     let mut token = std::env::var("VALID_CARGO_FUND_GITHUB_API_TOKEN").unwrap();
     // remove a character to invalidate the token
@@ -78,6 +112,11 @@ fn invalid_token() {
         .output()
         .expect("cargo-fund runs");
     assert!(!output.status.success());
+    assert_eq!(
+        output.status.code(),
+        Some(2),
+        "exits with the authentication status"
+    );
     assert_eq!(&output.stdout, b"", "stdout matches");
     assert_eq!(&sanitize_stderr(&output.stderr), expected, "stderr matches");
 }
@@ -86,7 +125,8 @@ fn invalid_token() {
 fn insufficient_scopes() {
     let root = Path::new(env!("CARGO_MANIFEST_DIR"));
     let expected = "Error: Insufficient Github API token scopes. Modify your token to include the \
-                    `public_repo` and `user` scopes at https://github.com/settings/tokens.\n";
+                    `public_repo` and `user` scopes at https://github.com/settings/tokens.\n  = \
+                    note: for more, run `cargo fund explain insufficient-scopes`\n";
     let exe = Path::new(env!("CARGO_BIN_EXE_cargo-fund"));
     let output = Command::new(exe)
         .current_dir(root.join("tests").join("client-package"))
@@ -98,6 +138,11 @@ fn insufficient_scopes() {
         .output()
         .expect("cargo-fund runs");
     assert!(!output.status.success());
+    assert_eq!(
+        output.status.code(),
+        Some(2),
+        "exits with the authentication status"
+    );
     assert_eq!(&output.stdout, b"", "stdout matches");
     assert_eq!(&sanitize_stderr(&output.stderr), expected, "stderr matches");
 }